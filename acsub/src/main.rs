@@ -1,164 +1,646 @@
-use std::{env::{args, current_dir}, fs::{read_to_string, File}, io::{Read, Stderr, Write}, process::{exit, Command, Stdio}};
-use dirs::home_dir;
+use std::{env::{args, current_dir}, path::{Path, PathBuf}, process::exit, sync::OnceLock};
 
-use serde::Deserialize;
-use toml::{from_str, Value};
+use cp_assists_core::{ahc, alias, build_all::{self, BuildStatus}, bundle, bundle_cpp, bundle_py, bundle_source, cases, constraints, genmax, history, project, repro, scores, share, size, status, stress, style, tune, variants, run_tests_cached, submit, workspace, Project, SubmitError, SubmitOptions, TestResult};
 
-#[derive(Deserialize)]
-struct Config {
-    url: String,
+mod oj_api;
+mod rpc;
+
+/// --judge で選んだ ac_config.toml の [judges.<name>]
+static JUDGE: OnceLock<String> = OnceLock::new();
+
+/// カレントディレクトリの Project を探して、見つからなければ終了する
+fn detect(problem_id: &str) -> Project {
+    if let Some(p) = project::try_detect_judge(problem_id, JUDGE.get().map(String::as_str)) {
+        return p;
+    }
+    eprintln!("ac_config.toml not found (and no cargo-compete / cargo-atcoder / acc project for `{problem_id}`).");
+    exit(1);
+}
+
+/// --variant か、acsub variant で選んだ variant の Project
+fn detect_variant(problem_id: &str, explicit: Option<&String>) -> Project {
+    let project = detect(problem_id);
+    let Some(name) = explicit.cloned().or_else(|| variants::active(problem_id)) else {
+        return project;
+    };
+    match project.variant(&name) {
+        Some(p) => p,
+        None => {
+            eprintln!("variant `{name}` of {problem_id} not found (expected {})", project.source.with_file_name(format!("{problem_id}{}{name}.rs", variants::SEP)).display());
+            exit(1);
+        }
+    }
+}
+
+/// `acsub variant <id> [name|main]`: 一覧か、提出に使う variant の切り替え
+fn variant_command(args: &[String]) {
+    let Some(problem_id) = args.first() else {
+        eprintln!("Usage: acsub variant <problem id> [<name>|main]");
+        exit(1);
+    };
+    let project = detect(problem_id);
+    let Some(name) = args.get(1) else {
+        let active = variants::active(problem_id);
+        let mark = |v: Option<&str>| if active.as_deref() == v { "*" } else { " " };
+        println!("{} main ({})", mark(None), project.source.display());
+        for v in variants::list(&project) {
+            println!("{} {v}", mark(Some(&v)));
+        }
+        return;
+    };
+    let name = (name != "main").then_some(name.as_str());
+    if let Some(n) = name && project.variant(n).is_none() {
+        eprintln!("variant `{n}` of {problem_id} not found");
+        exit(1);
+    }
+    if let Err(e) = variants::set_active(problem_id, name) {
+        eprintln!("failed to save the active variant: {e}");
+        exit(1);
+    }
+    println!("active variant of {problem_id}: {}", name.unwrap_or("main"));
+}
+
+/// `acsub stress <id> --spec <spec>`: brute variant と突き合わせ、食い違えば test_dir に残す
+fn stress_command(args: &[String]) {
+    let (Some(problem_id), Some(spec)) = (args.first(), opt_value(args, "--spec")) else {
+        eprintln!("Usage: acsub stress <problem id> --spec <spec> [--count n] [--seed s]");
+        exit(1);
+    };
+    let main = detect_variant(problem_id, opt_value(args, "--variant"));
+    let Some(brute) = detect(problem_id).variant(variants::BRUTE) else {
+        eprintln!("{problem_id}{}{}.rs not found", variants::SEP, variants::BRUTE);
+        exit(1);
+    };
+    let count: usize = opt_value(args, "--count").and_then(|c| c.parse().ok()).unwrap_or(100);
+    let mut rng = match opt_value(args, "--seed").and_then(|s| s.parse().ok()) {
+        Some(seed) => genmax::Rng::new(seed),
+        None => genmax::Rng::from_time(),
+    };
+    let mismatch = match stress::run(&main, &brute, spec, &Default::default(), count, &mut rng) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{}", e.message());
+            exit(1);
+        }
+    };
+    let Some(m) = mismatch else {
+        println!("{count} cases matched the brute force");
+        return;
+    };
+    println!("input:\n{}expected (brute):\n{}actual:\n{}", m.input, m.expected, m.actual);
+    match stress::save(&main, &m) {
+        Ok(path) => println!("saved as {}", path.display()),
+        Err(e) => eprintln!("failed to save the case: {e}"),
+    }
+    exit(1);
 }
 
-enum SubmitError {
-    SampleFailed,
-    CommandExecuteFailed,
+/// `acsub gen <id> --generator <cmd>`: 他人の generator を隔離して走らせ、gen-1.in, ... を作る。
+/// generator には何番目か (シードに使う) を引数で渡す
+fn gen_command(args: &[String]) {
+    let (Some(problem_id), Some(generator)) = (args.first(), opt_value(args, "--generator")) else {
+        eprintln!("Usage: acsub gen <problem id> --generator <cmd> [--count n]");
+        exit(1);
+    };
+    let project = detect(problem_id);
+    let count: usize = opt_value(args, "--count").and_then(|c| c.parse().ok()).unwrap_or(1);
+    match cases::generate(&project, generator, count) {
+        Ok(written) => written.iter().for_each(|p| println!("wrote {}", p.display())),
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1);
+        }
+    }
 }
 
-fn submit_url(problem_id: &String) -> String {
-    // $(pwd) の ac_config.tomlを読む
-    // 存在しない場合はエラー
-    let path = current_dir().unwrap().join("ac_config.toml");
-    if !path.exists() {
-        eprintln!("ac_config.toml not found.");
+/// `acsub score <id> [--seeds 0..100] [--jobs n]`: AHC の公式テスタで seed ごとの得点を出し、前回の実行と比べる
+fn score_command(args: &[String]) {
+    let Some(problem_id) = args.first() else {
+        eprintln!("Usage: acsub score <problem id> [--seeds 0..100] [--jobs n]");
+        exit(1);
+    };
+    let project = detect_variant(problem_id, opt_value(args, "--variant"));
+    let Some(seeds) = ahc::parse_seeds(opt_value(args, "--seeds").map_or("0..100", String::as_str)) else {
+        eprintln!("bad --seeds (0..100 / 100 / 1,5,9)");
         exit(1);
+    };
+    let jobs = opt_value(args, "--jobs").and_then(|j| j.parse().ok()).unwrap_or_else(ahc::default_jobs);
+    let results = match ahc::run_seeds(&project, &project.ahc, &seeds, jobs) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e.message());
+            exit(1);
+        }
+    };
+    for r in &results {
+        match (r.score, &r.error) {
+            (Some(score), _) => println!("{:04}  {score:>12}  {:.2}s", r.seed, r.secs),
+            (None, e) => println!("{:04}  {:>12}  {:.2}s  {}", r.seed, style::red("failed"), r.secs, e.as_deref().unwrap_or("").replace('\n', " / ")),
+        }
     }
-    
-    let src = read_to_string(&path).expect("failed to read content.");
-    let cfg: Config = from_str(&src).expect("failed to parse.");
-    // URLを生成
-    let place_holder = "{problem_id}";
+    let (total, avg, failed) = ahc::summary(&results);
+    println!("total {total}  average {avg:.1}  ({} seeds, {failed} failed)", results.len());
 
-    cfg.url.replace(place_holder, problem_id)
+    let version = std::fs::read_to_string(&project.source).map_or_else(|_| "-".to_string(), |s| history::short_hash(&s));
+    let prev = scores::load(problem_id).pop();
+    let now = scores::record(problem_id, &version, &results);
+    let best = scores::best(&scores::load(problem_id), project.ahc.objective);
+    let (rel, n) = scores::relative(&now, &best, project.ahc.objective);
+    println!("relative to best known: {rel:.3} / {n} ({:.1}%)", rel * 100.0 / n.max(1) as f64);
+    if let Some(prev) = prev {
+        let d = scores::compare(&prev, &now, project.ahc.objective);
+        println!(
+            "vs previous run ({}, {}): {:+}  {} improved, {} regressed, {} same",
+            prev.version,
+            history::format_time(prev.time),
+            d.total,
+            style::green(&d.improved.to_string()),
+            style::red(&d.regressed.to_string()),
+            d.same,
+        );
+    }
 }
 
-fn utf8_to_utf16le_bytes(src: &str) -> Vec<u8> {
-    let mut v = Vec::with_capacity(2 + src.len() * 2 + 2);
-    v.extend_from_slice(&[0xFF, 0xFE]); // BOM
-    for u in src.encode_utf16() {
-        v.push((u & 0x00FF) as u8);
-        v.push((u >> 8) as u8);
+/// `acsub tune <id> --param NAME=lo..hi[:step] ...`: パラメータを変えながら seed を走らせ、よかった組を出す
+fn tune_command(args: &[String]) {
+    let usage = || -> ! {
+        eprintln!("Usage: acsub tune <problem id> --param NAME=lo..hi[:step]|a,b,c ... [--trials n] [--seeds 0..20] [--jobs n] [--as-args]");
+        exit(1);
+    };
+    let Some(problem_id) = args.first() else { usage() };
+    let params: Option<Vec<tune::Param>> = args.windows(2)
+        .filter(|w| w[0] == "--param")
+        .map(|w| tune::parse_param(&w[1]))
+        .collect();
+    let Some(params) = params.filter(|p| !p.is_empty()) else { usage() };
+    let project = detect_variant(problem_id, opt_value(args, "--variant"));
+    let Some(seeds) = ahc::parse_seeds(opt_value(args, "--seeds").map_or("0..20", String::as_str)) else { usage() };
+    let trials: usize = opt_value(args, "--trials").and_then(|t| t.parse().ok()).unwrap_or(20);
+    let jobs = opt_value(args, "--jobs").and_then(|j| j.parse().ok()).unwrap_or_else(ahc::default_jobs);
+    let pass = if args.iter().any(|a| a == "--as-args") { tune::PassAs::Args } else { tune::PassAs::Env };
+    let show = |t: &tune::Trial| {
+        let ps: Vec<String> = t.params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        format!("{:>12}  {} failed  {}", t.total, t.failed, ps.join(" "))
+    };
+    let candidates = tune::candidates(&params, trials, &mut genmax::Rng::from_time());
+    eprintln!("{} candidates x {} seeds", candidates.len(), seeds.len());
+    let result = tune::run(&project, problem_id, &candidates, pass, &seeds, jobs, |t| println!("{}", show(t)));
+    match result {
+        Ok(ranked) => {
+            println!("best:");
+            for t in ranked.iter().take(5) {
+                println!("{}", show(t));
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e.message());
+            exit(1);
+        }
     }
-    v.extend_from_slice(&[0x00, 0x00]); // NUL 2Byte
-    v
 }
 
-fn submit(lang: &String, id: &String, url: &String, is_check: bool) -> Result<(), SubmitError> {
-    let _output = Command::new("rm")
-        .args(["-rf", "test"])
-        .status();
+/// `acsub vis <id> <seed> [--web]`: seed の結果を公式ビジュアライザで開く
+fn vis_command(args: &[String]) {
+    let (Some(problem_id), Some(seed)) = (args.first(), args.get(1).and_then(|s| s.parse::<u64>().ok())) else {
+        eprintln!("Usage: acsub vis <problem id> <seed> [--web]");
+        exit(1);
+    };
+    let project = detect(problem_id);
+    let target = if args.iter().any(|a| a == "--web") {
+        ahc::visualize_web(&project.ahc, seed).inspect(|_| println!("output of seed {seed} was copied to clipboard"))
+    } else {
+        ahc::visualize_local(&project.ahc, seed).map(|p| p.display().to_string())
+    };
+    match target.and_then(|t| ahc::open(&t).map(|_| t)) {
+        Ok(t) => println!("opened {t}"),
+        Err(e) => {
+            eprintln!("{}", e.message());
+            exit(1);
+        }
+    }
+}
 
-    let output = Command::new("oj")
-        .args(["d", url])
-        .stdout(Stdio::inherit())
-        .status();
-    if output.is_err() {
-        return Err(SubmitError::CommandExecuteFailed);
+/// `acsub size <id> [--watch]`: 束ねたソースの長さ。--watch ならソースが変わるたびに出し直す
+fn size_command(args: &[String]) {
+    let Some(problem_id) = args.first() else {
+        eprintln!("Usage: acsub size <problem id> [--watch] [--variant <name>]");
+        exit(1);
+    };
+    let project = detect_variant(problem_id, opt_value(args, "--variant"));
+    let report = || match bundle_source(&project) {
+        Ok(src) => {
+            let s = size::measure(&src);
+            println!("{} bytes, {} chars, {} bytes without trailing newline", s.bytes, s.chars, s.golf);
+        }
+        Err(e) => eprintln!("{}", e.message()),
+    };
+    report();
+    if args.iter().any(|a| a == "--watch") {
+        size::watch(&project.source, report);
     }
+}
 
+/// `acsub share <id> [--case n]`: ソース (--case なら repro 一式) を paste サービスに上げる
+fn share_command(args: &[String]) {
+    let Some(problem_id) = args.first() else {
+        eprintln!("Usage: acsub share <problem id> [--case n] [--backend paste.rs|gist]");
+        exit(1);
+    };
+    let project = detect_variant(problem_id, opt_value(args, "--variant"));
+    let fail = |msg: String| -> ! {
+        eprintln!("{msg}");
+        exit(1);
+    };
+    let backend = share::Backend::from_name(opt_value(args, "--backend").map(String::as_str)).unwrap_or_else(|e| fail(e));
+    let files = match opt_value(args, "--case") {
+        Some(case) => {
+            if let Err(e) = repro::export(&project, problem_id, case) {
+                fail(e.message());
+            }
+            share::files_in(&repro::dir_of(problem_id, case)).unwrap_or_else(|e| fail(e))
+        }
+        None => {
+            let name = project.source.file_name().map_or("main.rs".into(), |n| n.to_string_lossy().into_owned());
+            let src = std::fs::read_to_string(&project.source).unwrap_or_else(|e| fail(format!("failed to read {}: {e}", project.source.display())));
+            vec![(name, src)]
+        }
+    };
+    match share::upload(&backend, &format!("{problem_id} ({})", project.url), &files) {
+        Ok(url) => println!("{url}"),
+        Err(e) => fail(e),
+    }
+}
 
-    if lang == &"rs".to_string() {
-        if is_check {
-            // テスト実行
-            let execute_command = format!("cargo run --features local --bin {}",id);
-            let output = Command::new("oj")
-                .args(["t", "-c"])
-                .arg(execute_command)
-                .stdout(Stdio::inherit())
-                .status();
-            if output.is_err() {
-                return Err(SubmitError::CommandExecuteFailed);
+/// `acsub history [<problem id>] [--match <file>]`: 提出したソースの記録。
+/// --match ならジャッジから持ってきたコードと同じハッシュのものだけ
+fn history_command(args: &[String]) {
+    let wanted = match opt_value(args, "--match") {
+        Some(file) => match std::fs::read_to_string(file) {
+            Ok(src) => Some(history::short_hash(&src)),
+            Err(e) => {
+                eprintln!("failed to read {file}: {e}");
+                exit(1);
             }
-            if output.unwrap().code().unwrap() > 0 {
-                return Err(SubmitError::SampleFailed);
+        },
+        None => None,
+    };
+    let problem = args.first().filter(|a| !a.starts_with("--"));
+    let entries: Vec<_> = history::load().into_iter()
+        .filter(|e| problem.is_none_or(|p| e.url.ends_with(&format!("/{p}"))))
+        .filter(|e| wanted.as_ref().is_none_or(|h| &e.hash == h))
+        .collect();
+    if entries.is_empty() {
+        println!("no matching submissions");
+        exit(1);
+    }
+    for e in entries {
+        println!("{}  {}  {:>6}B  {}  {}", history::format_time(e.time), e.hash, e.bytes, e.source, e.url);
+    }
+}
+
+/// problem id があればその Project の、無ければ ./test のケース置き場
+fn test_dir_of(problem_id: Option<&String>) -> PathBuf {
+    problem_id.map_or_else(|| PathBuf::from("test"), |id| detect(id).test_dir)
+}
+
+/// `acsub case ...`
+fn case_command(args: &[String]) {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("import"), Some(archive)) => {
+            let dir = test_dir_of(args.get(2));
+            match cases::import_archive(Path::new(archive), &dir) {
+                Ok(n) => println!("imported {n} cases into {}", dir.display()),
+                Err(e) => {
+                    eprintln!("{e}");
+                    exit(1);
+                }
             }
         }
+        _ => {
+            eprintln!("Usage: acsub case import <archive> [problem id]");
+            exit(1);
+        }
+    }
+}
 
-        // ファイルマージ
-        // let output = Command::new("uv")
-        //     .args(["run", "python3"])
-        //     .arg("../../util/file_merger.py")
-        //     .arg(id)
-        //     .stdout(Stdio::inherit())
-        //     .status();
-        // if output.is_err() {
-        //     return Err(SubmitError::CommandExecuteFailed);
-        // }
-
-        let lib_root = home_dir().expect("Could not determine home directory")
-            .join("repos")
-            .join("adry_library")
-            .join("library")
-            .join("src");
-
-        let target   = format!("src/bin/{id}.rs");
-
-        let bundler_out = Command::new("bundler")
-            .arg(&lib_root)
-            .arg(&target)
-            .output()
-            .map_err(|_| SubmitError::CommandExecuteFailed)?;
-
-        if !bundler_out.status.success() {
-            eprintln!("bundler failed");
-            return Err(SubmitError::CommandExecuteFailed);
-        }
-
-        let bundled_src = String::from_utf8_lossy(&bundler_out.stdout);
-
-        // 3) submit.rs へ保存
-        let mut file = File::create("submit.rs").map_err(|_| SubmitError::CommandExecuteFailed)?;
-        file.write_all(bundled_src.as_bytes())
-            .map_err(|_| SubmitError::CommandExecuteFailed)?;
-
-        // 4) クリップボードへコピー（UTF-16LE）
-        let utf16_bytes = utf8_to_utf16le_bytes(&bundled_src);
-        let mut child = Command::new("clip.exe")
-            .stdin(Stdio::piped())
-            .spawn()
-            .map_err(|_| SubmitError::CommandExecuteFailed)?;
-        {
-            let stdin = child.stdin.as_mut().unwrap();
-            stdin.write_all(&utf16_bytes).unwrap();
-        }
-        child.wait().unwrap();
-    } else if lang == &"py".to_string() {
-        todo!()
-    } else if lang == &"cpp".to_string() {
-        todo!()
-    } else {
-        eprintln!("language {} is not supported.", lang);
+/// `--name value` の value
+fn opt_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
+
+/// `acsub gen-max <id>`: test_dir に max-1.in, max-2.in, ... を作る
+fn gen_max_command(args: &[String]) {
+    let Some(problem_id) = args.first() else {
+        eprintln!("Usage: acsub gen-max <problem id> [--spec <spec>] [--count n] [--seed s]");
+        exit(1);
+    };
+    let project = detect(problem_id);
+    let Some(spec) = opt_value(args, "--spec").cloned().or(project.gen_max.clone()) else {
+        eprintln!("no spec: pass --spec or add `{problem_id} = \"...\"` under [gen-max] in ac_config.toml");
+        exit(1);
+    };
+    let count: usize = opt_value(args, "--count").and_then(|c| c.parse().ok()).unwrap_or(1);
+    let mut rng = match opt_value(args, "--seed").and_then(|s| s.parse().ok()) {
+        Some(seed) => genmax::Rng::new(seed),
+        None => genmax::Rng::from_time(),
+    };
+    // 問題文の制約の上限は変数として spec から使える (N, A_i など)
+    let vars = constraints::vars(&constraints::load_or_fetch(Path::new("."), problem_id, &project.url));
+    match genmax::write_cases(&project, &spec, &vars, count, &mut rng) {
+        Ok(written) => written.iter().for_each(|p| println!("wrote {}", p.display())),
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1);
+        }
+    }
+}
+
+/// `acsub build --all`
+fn build_all_command() {
+    let status = match build_all::build_all() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e.message());
+            exit(1);
+        }
+    };
+    let mut failed = false;
+    for (bin, st) in &status {
+        match st {
+            BuildStatus::Ok => println!("  {}      {bin}", style::green("ok")),
+            BuildStatus::Failed(msg) => {
+                failed = true;
+                println!("  {}  {bin}: {msg}", style::red("FAILED"));
+            }
+            BuildStatus::NotBuilt => {
+                failed = true;
+                println!("  {} {bin}", style::yellow("skipped"));
+            }
+        }
+    }
+    if failed {
         exit(1);
     }
+}
+
+/// `acsub status`: コンテストの全問題の状況を 1 画面に
+fn status_command() {
+    let statuses = status::all(JUDGE.get().map(String::as_str));
+    if statuses.is_empty() {
+        eprintln!("no problems found in this directory");
+        exit(1);
+    }
+    let rules = alias::rules();
+    println!("{:<20} {:<9} {:<8} {:<20} verdict", "problem", "samples", "bundled", "submitted");
+    for s in &statuses {
+        // 色を付けると {:<9} が幅を数え違えるので、詰めてから色を付ける
+        let samples = match s.samples {
+            status::Samples::Passed => style::green(&format!("{:<9}", "passed")),
+            status::Samples::Failed => style::red(&format!("{:<9}", "failed")),
+            status::Samples::Stale => style::yellow(&format!("{:<9}", "stale")),
+            status::Samples::Untested => format!("{:<9}", "-"),
+        };
+        let submitted = match &s.submitted {
+            // 最後にコピーした後で書き換えていれば印を付ける
+            Some(e) if s.submitted_current => e.hash.clone(),
+            Some(e) => format!("{} (edited)", e.hash),
+            None => "-".to_string(),
+        };
+        let verdict = match s.verdict.as_deref() {
+            Some("AC") => style::green("AC"),
+            Some(v) => style::red(v),
+            None => "-".to_string(),
+        };
+        let problem = match rules.short(&s.problem_id) {
+            Some(a) => format!("{} ({a})", s.problem_id),
+            None => s.problem_id.clone(),
+        };
+        println!("{problem:<20} {samples} {:<8} {submitted:<20} {verdict}", if s.bundled { "yes" } else { "-" });
+    }
+}
 
-    Ok(())
+/// 問題 ID を取る位置の短い ID (`acsub rs a`) を ac_config.toml の規則で展開する
+fn expand_problem_id(args: &mut [String]) {
+    let pos = match args.get(1).map(String::as_str) {
+        Some("oj-api" | "new" | "workspace" | "build" | "status" | "rpc") | None => return,
+        Some("case") => 4,
+        _ => 2,
+    };
+    if let Some(id) = args.get_mut(pos).filter(|a| !a.starts_with("--")) {
+        *id = alias::resolve(id);
+    }
 }
 
 fn main() {
-    let args = args().collect::<Vec<String>>();
+    let mut args = args().collect::<Vec<String>>();
+    style::detect();
+    // --judge はどのサブコマンドでも使えるので先に取り除く
+    if let Some(i) = args.iter().position(|a| a == "--judge") {
+        let Some(name) = args.get(i + 1).cloned() else {
+            eprintln!("--judge needs a name");
+            exit(1);
+        };
+        args.drain(i..=i + 1);
+        let _ = JUDGE.set(name);
+    }
+    expand_problem_id(&mut args);
+    if args.get(1).is_some_and(|a| a == "oj-api") {
+        oj_api::run(&args[2..]);
+        return;
+    }
+    // workspace は new の古い名前
+    if args.get(1).is_some_and(|a| a == "new" || a == "workspace") {
+        let Some(contest_id) = args.get(2) else {
+            eprintln!("Usage: acsub new <contest id> [--preset abc|arc|agc|ahc] [--problems abcdefg]");
+            exit(1);
+        };
+        let preset = match opt_value(&args, "--preset") {
+            Some(p) => workspace::Preset::parse(p).unwrap_or_else(|| {
+                eprintln!("unknown preset: {p} (abc / arc / agc / ahc)");
+                exit(1);
+            }),
+            None => workspace::Preset::detect(contest_id),
+        };
+        let letters: Vec<char> = opt_value(&args, "--problems").map_or(Vec::new(), |p| p.chars().collect());
+        match workspace::generate(&current_dir().unwrap(), contest_id, preset, &letters) {
+            Ok(root) => println!("created {} ({preset:?})", root.display()),
+            Err(e) => {
+                eprintln!("failed to create workspace: {e}");
+                exit(1);
+            }
+        }
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "case") {
+        case_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "repro") {
+        let (Some(problem_id), Some(case)) = (args.get(2), args.get(3)) else {
+            eprintln!("Usage: acsub repro <problem id> <case>");
+            exit(1);
+        };
+        match repro::export(&detect(problem_id), problem_id, case) {
+            Ok(path) => println!("wrote {}", path.display()),
+            Err(e) => {
+                eprintln!("{}", e.message());
+                exit(1);
+            }
+        }
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "build") {
+        if !args.iter().any(|a| a == "--all") {
+            eprintln!("Usage: acsub build --all");
+            exit(1);
+        }
+        build_all_command();
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "gen-max") {
+        gen_max_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "variant") {
+        variant_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "stress") {
+        stress_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "gen") {
+        gen_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "score") {
+        score_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "status") {
+        status_command();
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "tune") {
+        tune_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "vis") {
+        vis_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "size") {
+        size_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "share") {
+        share_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "history") {
+        history_command(&args[2..]);
+        return;
+    }
+    if args.get(1).is_some_and(|a| a == "rpc") {
+        cp_assists_core::set_quiet_stdout(true);
+        style::set_plain(true);
+        rpc::serve();
+        return;
+    }
+    // acsub test <id> は acsub rs <id> --only-test と同じ
+    let args = if args.get(1).is_some_and(|a| a == "test") {
+        let mut a = vec![args[0].clone(), "rs".to_string()];
+        a.extend(args[2..].iter().cloned());
+        a.push("--only-test".to_string());
+        a
+    } else {
+        args
+    };
     if args.len() < 3 {
-        eprintln!("Usage: acsub <language> <problem id>");
+        eprintln!("Usage: acsub <rs|cpp|py> <problem id>");
+        eprintln!("       acsub test <problem id> [--force]");
+        eprintln!("       acsub oj-api <get-problem|get-contest|submit-code> <url>");
+        eprintln!("       acsub new <contest id> [--preset abc|arc|agc|ahc] [--problems abcdefg]");
+        eprintln!("       acsub case import <archive> [problem id]");
+        eprintln!("       acsub repro <problem id> <case>");
+        eprintln!("       acsub build --all");
+        eprintln!("       acsub gen-max <problem id> [--spec <spec>] [--count n] [--seed s]");
+        eprintln!("       acsub variant <problem id> [<name>|main]");
+        eprintln!("       acsub stress <problem id> --spec <spec> [--count n] [--seed s]");
+        eprintln!("       acsub gen <problem id> --generator <cmd> [--count n]");
+        eprintln!("       acsub score <problem id> [--seeds 0..100] [--jobs n]");
+        eprintln!("       acsub status");
+        eprintln!("       acsub tune <problem id> --param NAME=lo..hi[:step] ... [--trials n] [--seeds 0..20]");
+        eprintln!("       acsub vis <problem id> <seed> [--web]");
+        eprintln!("       acsub size <problem id> [--watch]");
+        eprintln!("       acsub share <problem id> [--case n] [--backend paste.rs|gist]");
+        eprintln!("       acsub history [<problem id>] [--match <file>]");
+        eprintln!("       acsub rpc  (JSON-RPC 2.0 over stdio)");
+        eprintln!("cpp の `#include \"…\"` は解答の隣 → ACSUB_CPP_LIBRARY / ac_config.toml の cpp_library から、py の `from mylib.x import …` は解答の隣 → ACSUB_PY_LIBRARY / py_library から探す");
+        eprintln!("problem id は `a` のように短く書いてもよい (ac_config.toml の [alias] で展開)");
         eprintln!("options:");
         eprintln!("  --with-no-test: sampleチェック無しでコピー");
+        eprintln!("  --no-test-bundled: 束ねた submit.rs ではサンプルを確認しない");
+        eprintln!("  --only-bundle: submit.rs (cpp / py なら submit.cpp / submit.py) を作るだけ (取得・テスト・コピー無し)");
+        eprintln!("  --only-test: サンプルの取得とテストだけ (バンドル・コピー無し)");
+        eprintln!("  --refresh: 手元にあってもサンプルを取り直して検査する");
+        eprintln!("  --force: 前回から変更が無くてもテストをやり直す");
+        eprintln!("  --build-info: 実行ファイルの大きさと debug-assertions などの設定を表示");
+        eprintln!("  --judge <name>: ac_config.toml の [judges.<name>] を使う");
+        eprintln!("  --checker <cmd>: <cmd> input actual expected で正誤を判定する (隔離して実行)");
+        eprintln!("  --variant <name>: {{id}}__<name>.rs を使う (acsub variant で選んだものより優先)");
         exit(1);
     }
 
     let language = args[1].clone();
     let problem_id = args[2].clone();
-    let v = args[3..].iter().cloned().collect::<Vec<String>>();
-    let is_check = !v.contains(&"--with-no-test".to_string());
-
-    let url = submit_url(&problem_id);
-    if let Err(er) = submit(&language, &problem_id, &url, is_check) {
-        match er {
-            SubmitError::CommandExecuteFailed => {
-                eprintln!("Something Wrong.")
-            },
-            SubmitError::SampleFailed => {
-                eprintln!("Wrong Answer, or Runtime Error occured.")
-            }
-        }
+    let v = args[3..].to_vec();
+    let opts = SubmitOptions {
+        check: !v.contains(&"--with-no-test".to_string()),
+        test_bundled: !v.contains(&"--no-test-bundled".to_string()),
+        refresh: v.contains(&"--refresh".to_string()),
+        force: v.contains(&"--force".to_string()),
+        build_info: v.contains(&"--build-info".to_string()),
+    };
+
+    let only_bundle = v.contains(&"--only-bundle".to_string());
+    let only_test = v.contains(&"--only-test".to_string());
+    if only_bundle && only_test {
+        eprintln!("--only-bundle and --only-test cannot be used together.");
+        exit(1);
+    }
+    if (only_test && language != "rs") || (only_bundle && !["rs", "cpp", "py"].contains(&language.as_str())) {
+        eprintln!("{}", SubmitError::UnsupportedLanguage(language).message());
         exit(1);
     }
 
-    println!("All Tests passed🎉 Code was copied to clipboard!");
+    let mut project = detect_variant(&problem_id, opt_value(&v, "--variant"));
+    if let Some(checker) = opt_value(&v, "--checker") {
+        project.checker = Some(checker.clone());
+    }
+    println!("[{}] {}", project.contest_id.as_deref().unwrap_or("-"), project.url);
+    let result = if only_bundle && language == "cpp" {
+        bundle_cpp(&project).map(|b| format!("Bundled into {}", b.path.display()))
+    } else if only_bundle && language == "py" {
+        bundle_py(&project).map(|b| format!("Bundled into {}", b.path.display()))
+    } else if only_bundle {
+        bundle(&project).map(|b| format!("Bundled into {}", b.path.display()))
+    } else if only_test {
+        project.fetch_samples(opts.refresh)
+            .and_then(|_| run_tests_cached(&project, &opts))
+            .and_then(|r| match r {
+                TestResult::Passed => Ok(format!("All Tests passed{}", style::emoji("🎉"))),
+                TestResult::Failed => Err(SubmitError::SampleFailed),
+            })
+    } else {
+        submit(&language, &project, &opts)
+            .map(|b| format!("All Tests passed{} Code was copied to clipboard! (hash {})", style::emoji("🎉"), b.hash))
+    };
+    match result {
+        Ok(msg) => println!("{}", style::green(&msg)),
+        Err(er) => {
+            eprintln!("{}", style::red(&er.message()));
+            exit(1);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,91 @@
+//! online-judge-api-client (oj-api) 互換のインターフェース
+use std::process::{exit, Command};
+
+use serde_json::{json, Value};
+
+use cp_assists_core::atcoder::{self, contest_url, Contest, Problem};
+
+pub fn problem_json(p: &Problem) -> Value {
+    let contest = contest_url(&p.url);
+    json!({
+        "url": p.url,
+        "name": p.name,
+        "tests": p.samples.iter().map(|s| json!({ "input": s.input, "output": s.output })).collect::<Vec<_>>(),
+        "context": {
+            "contest": { "url": contest },
+            "alphabet": p.name.split(" - ").next(),
+        },
+        "timeLimit": p.time_limit_ms,
+        "memoryLimit": p.memory_limit_mb,
+        "constraints": p.constraints,
+    })
+}
+
+pub fn contest_json(c: &Contest) -> Value {
+    json!({
+        "url": c.url,
+        "name": c.name,
+        "problems": c.tasks.iter().map(|t| json!({
+            "url": t.url,
+            "name": t.name,
+            "context": {
+                "contest": { "url": c.url, "name": c.name },
+                "alphabet": t.alphabet,
+            },
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn get_problem(url: &str) -> Result<Value, String> {
+    let html = atcoder::fetch(url)?;
+    Ok(problem_json(&atcoder::parse_problem(url, &html)))
+}
+
+fn get_contest(url: &str) -> Result<Value, String> {
+    let url = contest_url(url).ok_or_else(|| format!("not a contest url: {url}"))?;
+    let html = atcoder::fetch(&format!("{url}/tasks"))?;
+    Ok(contest_json(&atcoder::parse_contest(&url, &html)))
+}
+
+/// 提出は oj に任せる
+fn submit_code(url: &str, file: &str, language: Option<&str>) -> Result<Value, String> {
+    let mut cmd = Command::new("oj");
+    cmd.args(["s", "-y", "--no-open", url, file]);
+    if let Some(l) = language {
+        cmd.args(["-l", l]);
+    }
+    let out = cmd.output().map_err(|e| format!("failed to run oj: {e}"))?;
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    if !out.status.success() {
+        return Err(format!("oj s failed:\n{stdout}{}", String::from_utf8_lossy(&out.stderr)));
+    }
+    // oj の出力に提出 URL が含まれる
+    let submission = stdout.split_whitespace().find(|w| w.contains("/submissions/"));
+    Ok(json!({ "url": submission }))
+}
+
+/// `acsub oj-api <get-problem|get-contest|submit-code> <url> [--file F] [--language L]`
+pub fn run(args: &[String]) {
+    let (Some(cmd), Some(url)) = (args.first(), args.get(1)) else {
+        eprintln!("Usage: acsub oj-api <get-problem|get-contest|submit-code> <url> [--file <file>] [--language <id>]");
+        exit(1);
+    };
+    let opt = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).map(String::as_str);
+    let result = match cmd.as_str() {
+        "get-problem" => get_problem(url),
+        "get-contest" => get_contest(url),
+        "submit-code" => match opt("--file") {
+            Some(file) => submit_code(url, file, opt("--language")),
+            None => Err("--file is required".to_string()),
+        },
+        _ => Err(format!("unknown command: {cmd}")),
+    };
+    let (ok, body) = match result {
+        Ok(v) => (true, json!({ "status": "ok", "messages": [], "result": v })),
+        Err(e) => (false, json!({ "status": "error", "messages": [e], "result": null })),
+    };
+    println!("{body}");
+    if !ok {
+        exit(1);
+    }
+}
@@ -0,0 +1,210 @@
+//! エディタ連携用の JSON-RPC 2.0 サーバ (stdio)
+//!
+//! 1 行 1 リクエストの形式と、LSP と同じ `Content-Length` ヘッダ形式の両方を受け付ける。
+//! メソッド: problem / project / new (workspace) / download / test / stress / bundle / submit / status
+use std::{env::current_dir, io::{self, BufRead, Read, Write}};
+
+use serde_json::{json, Value};
+
+use cp_assists_core::{alias, atcoder, bundle, genmax, project, run_tests, status, stress, submit, variants, workspace, Project, SubmitError, SubmitOptions, TestResult};
+
+use crate::oj_api;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAILED: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<SubmitError> for RpcError {
+    fn from(e: SubmitError) -> RpcError {
+        RpcError { code: FAILED, message: e.message() }
+    }
+}
+
+fn param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params.get(name).and_then(Value::as_str).ok_or_else(|| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("missing parameter `{name}`"),
+    })
+}
+
+fn project_of(params: &Value) -> Result<Project, RpcError> {
+    let id = &alias::resolve(param(params, "problem_id")?);
+    let judge = params.get("judge").and_then(Value::as_str);
+    project::try_detect_judge(id, judge).ok_or_else(|| RpcError {
+        code: FAILED,
+        message: format!("no project found for `{id}`"),
+    })
+}
+
+fn lang_of(params: &Value) -> String {
+    params.get("lang").and_then(Value::as_str).unwrap_or("rs").to_string()
+}
+
+fn dispatch(method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "problem" => {
+            let url = param(params, "url")?;
+            let html = atcoder::fetch(url).map_err(|message| RpcError { code: FAILED, message })?;
+            Ok(oj_api::problem_json(&atcoder::parse_problem(url, &html)))
+        }
+        "project" => {
+            let p = project_of(params)?;
+            Ok(json!({
+                "contest_id": p.contest_id,
+                "url": p.url,
+                "source": p.source,
+                "test_dir": p.test_dir,
+                "judge": p.judge,
+                "language_id": p.language_id(&lang_of(params)),
+            }))
+        }
+        // `acsub new <contest id> [--preset …] [--problems …]` と同じ。workspace は古い名前
+        "new" | "workspace" => {
+            let contest_id = param(params, "contest_id")?;
+            let preset = match params.get("preset").and_then(Value::as_str) {
+                Some(p) => workspace::Preset::parse(p).ok_or_else(|| RpcError {
+                    code: INVALID_PARAMS,
+                    message: format!("unknown preset `{p}` (abc / arc / agc / ahc)"),
+                })?,
+                None => workspace::Preset::detect(contest_id),
+            };
+            let letters: Vec<char> = params.get("problems").and_then(Value::as_str).map_or(Vec::new(), |p| p.chars().collect());
+            let dir = current_dir().map_err(|e| RpcError { code: FAILED, message: e.to_string() })?;
+            let root = workspace::generate(&dir, contest_id, preset, &letters)
+                .map_err(|e| RpcError { code: FAILED, message: format!("failed to create workspace: {e}") })?;
+            Ok(json!({ "root": root, "preset": format!("{preset:?}") }))
+        }
+        "download" => {
+            let refresh = params.get("refresh").and_then(Value::as_bool).unwrap_or(false);
+            project_of(params)?.fetch_samples(refresh)?;
+            Ok(json!({}))
+        }
+        "test" => {
+            let p = project_of(params)?;
+            let result = run_tests(&p)?;
+            Ok(json!({ "passed": result == TestResult::Passed }))
+        }
+        // `acsub stress` と同じ突き合わせ。食い違ったケースは保存せずに返す
+        "stress" => {
+            let p = project_of(params)?;
+            let main = match params.get("variant").and_then(Value::as_str) {
+                Some(v) => p.variant(v).ok_or_else(|| RpcError { code: FAILED, message: format!("variant `{v}` not found") })?,
+                None => p.clone(),
+            };
+            let brute = p.variant(variants::BRUTE).ok_or_else(|| RpcError {
+                code: FAILED,
+                message: format!("no `{}` variant for this problem", variants::BRUTE),
+            })?;
+            let spec = param(params, "spec")?;
+            let count = params.get("count").and_then(Value::as_u64).unwrap_or(100) as usize;
+            let mut rng = match params.get("seed").and_then(Value::as_u64) {
+                Some(seed) => genmax::Rng::new(seed),
+                None => genmax::Rng::from_time(),
+            };
+            let mismatch = stress::run(&main, &brute, spec, &Default::default(), count, &mut rng)?;
+            Ok(json!({
+                "matched": mismatch.is_none(),
+                "count": count,
+                "mismatch": mismatch.map(|m| json!({ "input": m.input, "expected": m.expected, "actual": m.actual })),
+            }))
+        }
+        "bundle" => {
+            let b = bundle(&project_of(params)?)?;
+            Ok(json!({ "path": b.path, "source": b.source, "hash": b.hash }))
+        }
+        "submit" => {
+            // CLI の `acsub rs <id>` と同じ流れ (check: false で --with-no-test)
+            let p = project_of(params)?;
+            let flag = |name: &str, default: bool| params.get(name).and_then(Value::as_bool).unwrap_or(default);
+            let opts = SubmitOptions {
+                check: flag("check", true),
+                test_bundled: flag("test_bundled", true),
+                refresh: flag("refresh", false),
+                force: flag("force", false),
+                build_info: false,
+            };
+            let lang = lang_of(params);
+            let b = submit(&lang, &p, &opts)?;
+            Ok(json!({ "path": b.path, "source": b.source, "hash": b.hash, "language_id": p.language_id(&lang) }))
+        }
+        // `acsub status` の表と同じ中身 (カレントディレクトリの問題ごと)
+        "status" => {
+            let judge = params.get("judge").and_then(Value::as_str);
+            let rows: Vec<Value> = status::all(judge).into_iter().map(|s| json!({
+                "problem_id": s.problem_id,
+                "samples": match s.samples {
+                    status::Samples::Passed => "passed",
+                    status::Samples::Failed => "failed",
+                    status::Samples::Stale => "stale",
+                    status::Samples::Untested => "untested",
+                },
+                "bundled": s.bundled,
+                "submitted": s.submitted.map(|e| json!({ "hash": e.hash, "time": e.time, "current": s.submitted_current })),
+                "verdict": s.verdict,
+            })).collect();
+            Ok(Value::Array(rows))
+        }
+        _ => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("unknown method `{method}`") }),
+    }
+}
+
+fn handle(req: &str) -> Option<Value> {
+    let req: Value = match serde_json::from_str(req) {
+        Ok(v) => v,
+        Err(e) => return Some(json!({
+            "jsonrpc": "2.0", "id": null,
+            "error": { "code": -32700, "message": e.to_string() },
+        })),
+    };
+    let id = req.get("id").cloned();
+    let method = req.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = req.get("params").cloned().unwrap_or(Value::Null);
+    let result = dispatch(method, &params);
+    // id の無いものは通知なので返さない
+    let id = id?;
+    Some(match result {
+        Ok(r) => json!({ "jsonrpc": "2.0", "id": id, "result": r }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    })
+}
+
+pub fn serve() {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut stdout = io::stdout();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() { continue; }
+
+        if let Some(len) = trimmed.strip_prefix("Content-Length:") {
+            let len: usize = len.trim().parse().unwrap_or(0);
+            // 残りのヘッダを空行まで読み飛ばす
+            loop {
+                line.clear();
+                if input.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() { break; }
+            }
+            let mut body = vec![0; len];
+            if input.read_exact(&mut body).is_err() { break; }
+            if let Some(res) = handle(&String::from_utf8_lossy(&body)) {
+                let res = res.to_string();
+                let _ = write!(stdout, "Content-Length: {}\r\n\r\n{res}", res.len());
+            }
+        } else {
+            if trimmed == "exit" { break; }
+            if let Some(res) = handle(trimmed) {
+                let _ = writeln!(stdout, "{res}");
+            }
+        }
+        let _ = stdout.flush();
+    }
+}
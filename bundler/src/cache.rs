@@ -0,0 +1,126 @@
+//! ライブラリのファイルから読んだもののキャッシュ
+//!
+//! macro_export の索引を作るにはライブラリの全ファイルを読む必要があり、数百ファイルあると毎回の提出で重い。
+//! ファイルごとの結果を (パス, 更新時刻, 大きさ) と一緒に `~/.cache/cp-assists/bundler.json` に覚えておき、
+//! 変わっていないファイルは開かない。1 回の実行で何度も読む lib.rs などの構文木や、--all で問題ごとに辿るファイルを調べた結果はメモリに持つ。
+use std::{cell::RefCell, collections::BTreeMap, fs, path::{Path, PathBuf}, sync::Mutex, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use syn::File;
+
+use crate::{read_source, Options, Scanned};
+
+/// ファイルが変わったかを見る印
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    secs: u64,
+    nanos: u32,
+    len: u64,
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let meta = fs::metadata(path).ok()?;
+    let t = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(Stamp { secs: t.as_secs(), nanos: t.subsec_nanos(), len: meta.len() })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    stamp: Stamp,
+    /// macro_export しているマクロ名
+    macros: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Disk {
+    /// 書いた bundler のバージョン。違えば捨てる
+    version: String,
+    files: BTreeMap<PathBuf, Entry>,
+}
+
+fn disk_path() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("cp-assists").join("bundler.json"))
+}
+
+thread_local! {
+    /// 読み込んだ Disk と、書き戻す必要があるか
+    static DISK: RefCell<Option<(Disk, bool)>> = const { RefCell::new(None) };
+    static PARSED: RefCell<BTreeMap<PathBuf, (Stamp, File)>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// (ファイル, モジュールのパス, 設定) → scan の結果。scan は何本ものスレッドから呼ぶので thread_local でなく Mutex
+type ScanKey = (PathBuf, Vec<String>, String);
+static SCANNED: Mutex<BTreeMap<ScanKey, (Stamp, Scanned)>> = Mutex::new(BTreeMap::new());
+
+fn load() -> Disk {
+    let disk = disk_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str::<Disk>(&s).ok())
+        .filter(|d| d.version == env!("CARGO_PKG_VERSION"));
+    disk.unwrap_or_else(|| Disk { version: env!("CARGO_PKG_VERSION").to_string(), files: BTreeMap::new() })
+}
+
+/// path で macro_export しているマクロ名。compute はファイルを読んで数える (キャッシュに無いときだけ呼ぶ)
+pub(crate) fn exported_macros(path: &Path, use_disk: bool, compute: impl FnOnce() -> Vec<String>) -> Vec<String> {
+    let Some(st) = stamp(path).filter(|_| use_disk) else { return compute() };
+    let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    DISK.with(|d| {
+        let mut d = d.borrow_mut();
+        let (disk, dirty) = d.get_or_insert_with(|| (load(), false));
+        if let Some(e) = disk.files.get(&key).filter(|e| e.stamp == st) {
+            return e.macros.clone();
+        }
+        let macros = compute();
+        disk.files.insert(key, Entry { stamp: st, macros: macros.clone() });
+        *dirty = true;
+        macros
+    })
+}
+
+/// exported_macros で増えた分をファイルに書く。書けなくても束ねるのには困らないので黙って諦める
+pub(crate) fn flush() {
+    DISK.with(|d| {
+        let mut d = d.borrow_mut();
+        let Some((disk, dirty)) = d.as_mut().filter(|(_, dirty)| *dirty) else { return };
+        // 消えたファイルは忘れる
+        disk.files.retain(|p, _| p.exists());
+        if let Some(p) = disk_path()
+            && let Some(dir) = p.parent()
+            && fs::create_dir_all(dir).is_ok()
+            && let Ok(json) = serde_json::to_string(disk)
+        {
+            let tmp = p.with_extension("json.tmp");
+            if fs::write(&tmp, json).is_ok() {
+                let _ = fs::rename(&tmp, &p);
+            }
+        }
+        *dirty = false;
+    });
+}
+
+/// path を syn::parse_file したもの。同じ実行の中では、ファイルが変わらない限り 2 度目からは読まない
+pub(crate) fn parsed(path: &Path) -> Option<File> {
+    let st = stamp(path)?;
+    if let Some(f) = PARSED.with(|p| p.borrow().get(path).filter(|(s, _)| *s == st).map(|(_, f)| f.clone())) {
+        return Some(f);
+    }
+    let f = syn::parse_file(&read_source(path).ok()?).ok()?;
+    PARSED.with(|p| p.borrow_mut().insert(path.to_path_buf(), (st, f.clone())));
+    Some(f)
+}
+
+/// fp をモジュール path として調べたもの。同じ実行の中では、ファイルが変わらない限り compute は 1 度だけ
+/// (--all で何問も束ねるとき、共通のライブラリのファイルを読み直さない)
+pub(crate) fn scanned(fp: &Path, path: &[String], opts: &Options, compute: impl FnOnce() -> anyhow::Result<Option<Scanned>>) -> anyhow::Result<Option<Scanned>> {
+    let Some(st) = stamp(fp) else { return compute() };
+    let key = (fp.to_path_buf(), path.to_vec(), format!("{opts:?}"));
+    let hit = SCANNED.lock().unwrap_or_else(|e| e.into_inner()).get(&key).filter(|(s, _)| *s == st).map(|(_, s)| s.clone());
+    if let Some(s) = hit {
+        return Ok(Some(s));
+    }
+    let s = compute()?;
+    if let Some(s) = &s {
+        SCANNED.lock().unwrap_or_else(|e| e.into_inner()).insert(key, (st, s.clone()));
+    }
+    Ok(s)
+}
@@ -0,0 +1,234 @@
+//! bundler.toml (カレントディレクトリ、無ければホーム) の設定
+//!
+//! ```toml
+//! output = "submit.rs"   # カレントディレクトリからのパス。省略すると標準出力
+//! placement = "end"      # 束ねたライブラリの置き場所 (end / before-main / marker: `// @library` の行 / companion: 別のファイル)
+//! companion = "library.rs" # placement = "companion" のときにライブラリを書くファイル
+//! root_ident = "kyopro"  # `bundler <src> <target>` で束ねる use の名前 (既定は library)
+//! check = false
+//! verify = false        # 束ねたものを cargo check する
+//! allow_missing = false # ファイルが見つからないモジュールがあっても束ねる (既定はエラー)
+//! max_bytes = 65536      # 出力の長さの上限。超えたらエラー
+//! warn_over_limit = false # 上限を超えても警告だけにする
+//! allow_lints = ["dead_code", "unused_imports"]  # 束ねたライブラリに #[allow(…)] を付ける
+//! allow_scope = "module" # module: ライブラリの pub mod に付ける / crate: 先頭に #![allow(…)]
+//! strip_tests = true    # #[cfg(test)] / #[test] を落とす (既定)
+//! strip_docs = false    # /// や //! を落とす
+//! widen_visibility = false # pub(crate) / pub(super) を pub にする
+//! strip_doctests = false # ドキュメントの ``` のコードブロックだけを落とす
+//! attribution_markers = ["LICENSE:", "SPDX-License-Identifier:", "Copyright"]  # ファイルの先頭のコメントにあれば、ライセンス表記として必ず出力に残す
+//! keep_comments = false  # // のコメントも残す (元のソースをそのまま繋ぐ)
+//! source_map = "item"    # 元のモジュールと行を書き添える (module / item / trailing)
+//! tree_shake = false     # 使う項目だけを残す
+//! minify = false         # ライブラリ部分を詰める
+//! minify_idents = false  # 加えて非公開の名前を短くする
+//! fmt = "prettyplease"   # rustfmt なら出力を rustfmt に通す (解答の rustfmt.toml を使う)
+//! features = ["avx2"]    # 書くと #[cfg(feature = "…")] を評価する
+//! strip_features = ["local"]  # 常に無効として落とす feature (既定は local。[] で落とさない)
+//! edition = "2021"      # 提出先の edition (2018 / 2021 / 2024)
+//! strict = false         # 組み込みでない derive / 属性マクロがあればエラーにする (既定は警告)
+//! cache = true           # ライブラリを調べた結果を ~/.cache/cp-assists に覚える (既定)
+//! stats = false          # かかった時間と入れたモジュール・項目の数を標準エラーに出す
+//! revision = true        # ライブラリの git のコミットを出力に書く。コミットしていない変更があれば警告 (既定)
+//!
+//! [judge_limits]         # --judge <name> で選ぶジャッジごとの上限 (max_bytes より優先)
+//! codeforces = 65536
+//!
+//! [libraries]            # use で書く名前 = src (この設定ファイルからの相対パスか ~/...)。無ければ解答の Cargo.toml の path 依存を使う
+//! library = "~/adry_library/src"
+//! teamlib = "../team/src"
+//!
+//! [vendor]               # 丸ごと埋め込む外部クレート (ジャッジに無いとき)。名前 = そのクレートの src
+//! itertools = "~/vendor/itertools/src"
+//! either = "~/vendor/either/src"
+//! ```
+use std::{collections::BTreeMap, env::current_dir, fs::read_to_string, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{edition::Edition, source_map::Density, AllowScope, Fmt, Options, Placement};
+
+pub const FILE_NAME: &str = "bundler.toml";
+pub const DEFAULT_ROOT_IDENT: &str = "library";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub libraries: BTreeMap<String, String>,
+    #[serde(default)]
+    pub vendor: BTreeMap<String, String>,
+    pub root_ident: Option<String>,
+    pub output: Option<PathBuf>,
+    #[serde(default)]
+    pub check: bool,
+    #[serde(default)]
+    pub verify: bool,
+    #[serde(default)]
+    pub allow_missing: bool,
+    pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub warn_over_limit: bool,
+    #[serde(default)]
+    pub judge_limits: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub allow_lints: Vec<String>,
+    pub allow_scope: Option<String>,
+    pub fmt: Option<String>,
+    pub strip_tests: Option<bool>,
+    #[serde(default)]
+    pub strip_docs: bool,
+    #[serde(default)]
+    pub strip_doctests: bool,
+    pub attribution_markers: Option<Vec<String>>,
+    pub placement: Option<String>,
+    pub companion: Option<PathBuf>,
+    #[serde(default)]
+    pub widen_visibility: bool,
+    #[serde(default)]
+    pub keep_comments: bool,
+    pub source_map: Option<String>,
+    #[serde(default)]
+    pub tree_shake: bool,
+    #[serde(default)]
+    pub minify: bool,
+    #[serde(default)]
+    pub minify_idents: bool,
+    pub features: Option<Vec<String>>,
+    pub strip_features: Option<Vec<String>>,
+    #[serde(default)]
+    pub strict: bool,
+    pub cache: Option<bool>,
+    #[serde(default)]
+    pub stats: bool,
+    pub revision: Option<bool>,
+    pub edition: Option<String>,
+    /// 読んだファイルのディレクトリ (相対パスの基準)
+    #[serde(skip)]
+    pub base: PathBuf,
+}
+
+/// カレントディレクトリ → ホームの順に探す。どこにも無ければ None
+pub fn find() -> Result<Option<Config>> {
+    let candidates = [current_dir().ok(), dirs::home_dir()];
+    let Some(path) = candidates.into_iter().flatten().map(|d| d.join(FILE_NAME)).find(|p| p.is_file()) else {
+        return Ok(None);
+    };
+    let src = read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+    let mut cfg: Config = toml::from_str(&src).with_context(|| format!("parse {:?}", path))?;
+    cfg.base = path.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+    Ok(Some(cfg))
+}
+
+/// target から上へ辿って最初の Cargo.toml の path 依存を (use で書く名前, src) の組にする。
+/// `library = { path = "../adry_library" }` なら ("library", ../adry_library/src)。
+/// 名前は依存のキー (`-` は `_`)、src は依存の Cargo.toml の `[lib] path` のあるディレクトリ (無ければ src)
+pub fn cargo_roots(target: &Path) -> Vec<(String, PathBuf)> {
+    let dir = target.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let Some(manifest) = dir.ancestors().map(|d| d.join("Cargo.toml")).find(|p| p.is_file()) else { return Vec::new() };
+    let Some(deps) = read_to_string(&manifest).ok()
+        .and_then(|s| s.parse::<toml::Table>().ok())
+        .and_then(|mut t| t.remove("dependencies"))
+    else {
+        return Vec::new();
+    };
+    let base = manifest.parent().unwrap_or(dir);
+    let Some(deps) = deps.as_table() else { return Vec::new() };
+    deps.iter()
+        .filter_map(|(name, dep)| {
+            let crate_dir = base.join(dep.get("path")?.as_str()?);
+            let lib: Option<String> = read_to_string(crate_dir.join("Cargo.toml")).ok()
+                .and_then(|s| s.parse::<toml::Table>().ok())
+                .and_then(|t| Some(t.get("lib")?.get("path")?.as_str()?.to_string()));
+            let lib_rs = crate_dir.join(lib.as_deref().unwrap_or("src/lib.rs"));
+            if !lib_rs.is_file() {
+                eprintln!("warning: path dependency `{name}` in {} has no {}; not bundling it", manifest.display(), lib_rs.display());
+                return None;
+            }
+            Some((name.replace('-', "_"), lib_rs.parent()?.to_path_buf()))
+        })
+        .collect()
+}
+
+impl Config {
+    fn resolve(&self, p: &str) -> PathBuf {
+        match (p.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => self.base.join(p),
+        }
+    }
+
+    /// bundle_roots に渡す (名前, src) の組
+    pub fn roots(&self) -> Vec<(String, PathBuf)> {
+        self.libraries.iter().map(|(name, p)| (name.clone(), self.resolve(p))).collect()
+    }
+
+    /// [vendor] の (名前, src) の組
+    pub fn vendor_roots(&self) -> Vec<(String, PathBuf)> {
+        self.vendor.iter().map(|(name, p)| (name.clone(), self.resolve(p))).collect()
+    }
+
+    /// ライブラリを 1 つだけ渡したときの use の名前
+    pub fn root_ident(&self) -> &str {
+        self.root_ident.as_deref().unwrap_or(DEFAULT_ROOT_IDENT)
+    }
+
+    pub fn options(&self) -> Options {
+        Options {
+            check: self.check,
+            verify: self.verify,
+            allow_missing: self.allow_missing,
+            max_bytes: self.max_bytes,
+            warn_over_limit: self.warn_over_limit,
+            allow_lints: self.allow_lints.clone(),
+            allow_scope: self.allow_scope.as_deref().map_or_else(AllowScope::default, |s| {
+                AllowScope::parse(s).unwrap_or_else(|| {
+                    eprintln!("warning: unknown allow_scope `{s}` in {FILE_NAME}; expected module or crate");
+                    AllowScope::default()
+                })
+            }),
+            fmt: self.fmt.as_deref().map_or_else(Fmt::default, |s| {
+                Fmt::parse(s).unwrap_or_else(|| {
+                    eprintln!("warning: unknown fmt `{s}` in {FILE_NAME}; expected prettyplease or rustfmt");
+                    Fmt::default()
+                })
+            }),
+            strip_tests: self.strip_tests.unwrap_or(true),
+            strip_docs: self.strip_docs,
+            strip_doctests: self.strip_doctests,
+            widen_visibility: self.widen_visibility,
+            placement: self.placement.as_deref().map_or_else(Placement::default, |s| {
+                Placement::parse(s).unwrap_or_else(|| {
+                    eprintln!("warning: unknown placement `{s}` in {FILE_NAME}; expected end, before-main, marker or companion");
+                    Placement::default()
+                })
+            }),
+            attribution_markers: self.attribution_markers.clone().unwrap_or_else(|| Options::default().attribution_markers),
+            tree_shake: self.tree_shake,
+            minify: self.minify,
+            minify_idents: self.minify_idents,
+            features: self.features.as_ref().map(|f| f.iter().cloned().collect()),
+            strip_features: self.strip_features.as_ref()
+                .map_or_else(|| Options::default().strip_features, |f| f.iter().cloned().collect()),
+            vendor: self.vendor.keys().cloned().collect(),
+            strict: self.strict,
+            keep_comments: self.keep_comments,
+            cache: self.cache.unwrap_or(true),
+            stats: self.stats,
+            revision: self.revision.unwrap_or(true),
+            source_map: self.source_map.as_deref().and_then(|s| {
+                let d = Density::parse(s);
+                if d.is_none() {
+                    eprintln!("warning: unknown source_map `{s}` in {FILE_NAME}; expected module, item or trailing");
+                }
+                d
+            }),
+            edition: self.edition.as_deref().map_or_else(Edition::default, |e| {
+                Edition::parse(e).unwrap_or_else(|| {
+                    eprintln!("warning: unknown edition `{e}` in {FILE_NAME}; using {}", Edition::default().as_str());
+                    Edition::default()
+                })
+            }),
+        }
+    }
+}
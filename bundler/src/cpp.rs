@@ -0,0 +1,93 @@
+//! C++ の解答の `#include "…"` を展開して 1 つの .cpp にする (--cpp)
+//!
+//! `"…"` の include だけを、include したファイルのディレクトリ → include_dirs の順に探して中身に置き換える。
+//! `<…>` の標準ヘッダはそのまま残す。`#pragma once` かインクルードガードのあるファイルは最初の 1 度だけ展開する。
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::read_source;
+
+/// `#include "mylib/segtree.hpp"` の中身
+fn quoted_include(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+    Some(&rest[..rest.find('"')?])
+}
+
+fn is_pragma_once(line: &str) -> bool {
+    line.split_whitespace().collect::<Vec<_>>() == ["#pragma", "once"]
+}
+
+/// `#ifndef X` / `#define X` で始まり `#endif` で終わるか
+fn has_guard(src: &str) -> bool {
+    let mut lines = src.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with("//"));
+    let (Some(a), Some(b)) = (lines.next(), lines.next()) else { return false };
+    let guard = a.strip_prefix("#ifndef").map(str::trim);
+    guard.is_some_and(|g| !g.is_empty() && b.strip_prefix("#define").map(str::trim) == Some(g))
+        && src.lines().map(str::trim).rfind(|l| !l.is_empty()).is_some_and(|l| l.starts_with("#endif"))
+}
+
+/// name を including のディレクトリ → include_dirs の順に探す
+fn find(name: &str, including: &Path, include_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let here = including.parent().unwrap_or(Path::new("."));
+    std::iter::once(here).chain(include_dirs.iter().map(PathBuf::as_path))
+        .map(|d| d.join(name))
+        .find(|p| p.is_file())
+}
+
+struct Expander<'a> {
+    include_dirs: &'a [PathBuf],
+    /// pragma once / ガードがあって、もう展開したファイル
+    once: BTreeSet<PathBuf>,
+    /// 展開中のファイル (ガードの無いファイルが自分を include していないか)
+    stack: Vec<PathBuf>,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, path: &Path, out: &mut String) -> Result<()> {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.stack.contains(&key) {
+            let chain: Vec<String> = self.stack.iter().chain([&key]).map(|p| p.display().to_string()).collect();
+            bail!("include cycle without a guard: {}", chain.join(" -> "));
+        }
+        let src = read_source(path).with_context(|| format!("read {:?}", path))?;
+        if has_guard(&src) || src.lines().any(is_pragma_once) {
+            self.once.insert(key.clone());
+        }
+        self.stack.push(key);
+        for line in src.lines() {
+            if is_pragma_once(line) {
+                continue;
+            }
+            match quoted_include(line) {
+                Some(name) => match find(name, path, self.include_dirs) {
+                    Some(p) if self.once.contains(&fs::canonicalize(&p).unwrap_or_else(|_| p.clone())) => {}
+                    Some(p) => self.expand(&p, out)?,
+                    None => bail!(
+                        "could not find `{name}` included from {} — looked next to it and in {}",
+                        path.display(),
+                        self.include_dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+                    ),
+                },
+                None => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// main の `#include "…"` を再帰的に展開したソース
+pub fn bundle(main: &Path, include_dirs: &[PathBuf]) -> Result<String> {
+    let mut out = String::new();
+    Expander { include_dirs, once: BTreeSet::new(), stack: Vec::new() }.expand(main, &mut out)?;
+    Ok(out)
+}
@@ -0,0 +1,97 @@
+use std::{env, fs, process::Command};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{edition::Edition, Module};
+
+/// ドキュメント中の ```rust 例
+struct Example {
+    /// library::hash::fenwick:12
+    origin: String,
+    code: String,
+}
+
+/// rustdoc と同じく、言語無指定か rust のフェンスだけを対象にする
+fn is_rust_fence(info: &str) -> bool {
+    info.split(',').map(str::trim).all(|t| {
+        matches!(t, "" | "rust" | "no_run" | "should_panic" | "edition2018" | "edition2021" | "edition2024")
+    })
+}
+
+/// `///` `//!` の連続行をまとめて、その中のコードブロックを取り出す
+fn extract(src: &str, module: &str, out: &mut Vec<Example>) {
+    let mut cur: Option<(usize, Vec<String>)> = None;
+    let mut skipping = false;
+    for (i, line) in src.lines().enumerate() {
+        let t = line.trim_start();
+        let Some(doc) = t.strip_prefix("///").or_else(|| t.strip_prefix("//!")) else {
+            cur = None;
+            skipping = false;
+            continue;
+        };
+        let doc = doc.strip_prefix(' ').unwrap_or(doc);
+        if let Some(info) = doc.trim_start().strip_prefix("```") {
+            match cur.take() {
+                Some((start, lines)) => out.push(Example {
+                    origin: format!("{module}:{}", start + 1),
+                    code: lines.join("\n"),
+                }),
+                None if skipping => skipping = false,
+                None if is_rust_fence(info) => cur = Some((i, Vec::new())),
+                None => skipping = true,
+            }
+        } else if let Some((_, lines)) = cur.as_mut() {
+            // `# ` で始まる行は rustdoc の隠し行
+            let l = if doc.trim() == "#" { "" } else { doc.strip_prefix("# ").unwrap_or(doc) };
+            lines.push(l.to_string());
+        }
+    }
+}
+
+fn collect(m: &Module, path: &mut Vec<String>, out: &mut Vec<Example>) {
+    if let Some(src) = &m.code {
+        extract(src, &path.join("::"), out);
+    }
+    for (name, child) in &m.children {
+        path.push(name.clone());
+        collect(child, path, out);
+        path.pop();
+    }
+}
+
+/// 束ねたライブラリと一緒に各例を rustc に通す
+pub fn check(tree: &Module, lib_src: &str, edition: Edition) -> Result<()> {
+    let mut examples = Vec::new();
+    collect(tree, &mut Vec::new(), &mut examples);
+    if examples.is_empty() { return Ok(()); }
+
+    let dir = env::temp_dir().join(format!("bundler-doctest-{}", std::process::id()));
+    fs::create_dir_all(&dir).with_context(|| format!("create {:?}", dir))?;
+    let mut failed = 0;
+    for (i, ex) in examples.iter().enumerate() {
+        let body = if ex.code.contains("fn main") {
+            ex.code.clone()
+        } else {
+            format!("fn main() {{\n{}\n}}", ex.code)
+        };
+        let file = dir.join(format!("ex{i}.rs"));
+        fs::write(&file, format!("#![allow(unused)]\n{body}\n\n{lib_src}"))?;
+        let out = Command::new("rustc")
+            .args(["--edition", edition.as_str(), "--crate-type", "bin", "--emit=metadata", "--out-dir"])
+            .arg(&dir)
+            .arg(&file)
+            .output()
+            .context("failed to run rustc")?;
+        if !out.status.success() {
+            failed += 1;
+            eprintln!("doc example failed: {}\n{}", ex.origin, String::from_utf8_lossy(&out.stderr));
+        }
+    }
+    let _ = fs::remove_dir_all(&dir);
+    if failed > 0 {
+        bail!("{failed}/{} doc examples failed to compile", examples.len());
+    }
+    eprintln!("{} doc examples ok", examples.len());
+    Ok(())
+}
+
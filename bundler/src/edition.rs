@@ -0,0 +1,98 @@
+//! 提出先のコンパイラの edition に出力を合わせる
+//!
+//! - 2018: `TryFrom` / `TryInto` / `FromIterator` が prelude に無いので、使っているモジュールに use を足す
+//! - 2024: `gen` が予約語になったので `r#gen` にする
+use std::collections::BTreeSet;
+
+use syn::{visit::Visit, visit_mut::VisitMut, File, Item, ItemMod};
+
+use crate::exported_names;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Edition {
+    E2018,
+    #[default]
+    E2021,
+    E2024,
+}
+
+impl Edition {
+    pub fn parse(s: &str) -> Option<Edition> {
+        match s {
+            "2018" => Some(Edition::E2018),
+            "2021" => Some(Edition::E2021),
+            "2024" => Some(Edition::E2024),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Edition::E2018 => "2018",
+            Edition::E2021 => "2021",
+            Edition::E2024 => "2024",
+        }
+    }
+}
+
+/// 2021 で prelude に入ったもの: (使っていると分かる名前, 足す use)
+const PRELUDE_2021: &[(&[&str], &str, &str)] = &[
+    (&["TryFrom", "try_from"], "TryFrom", "std::convert::TryFrom"),
+    (&["TryInto", "try_into"], "TryInto", "std::convert::TryInto"),
+    (&["FromIterator", "from_iter"], "FromIterator", "std::iter::FromIterator"),
+];
+
+/// モジュール直下 (子のモジュールは除く) の識別子
+fn idents_in(items: &[Item]) -> BTreeSet<String> {
+    struct V(BTreeSet<String>);
+    impl<'ast> Visit<'ast> for V {
+        fn visit_item_mod(&mut self, _: &'ast ItemMod) {}
+        fn visit_ident(&mut self, i: &'ast proc_macro2::Ident) {
+            self.0.insert(i.to_string());
+        }
+    }
+    let mut v = V(BTreeSet::new());
+    items.iter().for_each(|it| v.visit_item(it));
+    v.0
+}
+
+fn add_prelude_2018(items: &mut Vec<Item>) {
+    let used = idents_in(items);
+    let mut imported = Vec::new();
+    for it in items.iter() {
+        match it {
+            Item::Use(u) => exported_names(&u.tree, &mut imported),
+            Item::Trait(t) => imported.push(t.ident.to_string()),
+            _ => {}
+        }
+    }
+    for (marks, name, path) in PRELUDE_2021 {
+        if marks.iter().any(|m| used.contains(*m)) && !imported.iter().any(|i| i == name) {
+            let path: syn::Path = syn::parse_str(path).expect("path");
+            items.insert(0, syn::parse_quote!(use #path;));
+        }
+    }
+    for it in items.iter_mut() {
+        if let Item::Mod(ItemMod { content: Some((_, inner)), .. }) = it {
+            add_prelude_2018(inner);
+        }
+    }
+}
+
+struct RawGen;
+impl VisitMut for RawGen {
+    fn visit_ident_mut(&mut self, i: &mut proc_macro2::Ident) {
+        if i == "gen" {
+            *i = proc_macro2::Ident::new_raw("gen", i.span());
+        }
+    }
+}
+
+/// 束ねたライブラリを edition に合わせて直す
+pub(crate) fn adapt(f: &mut File, edition: Edition) {
+    match edition {
+        Edition::E2018 => add_prelude_2018(&mut f.items),
+        Edition::E2021 => {}
+        Edition::E2024 => RawGen.visit_file_mut(f),
+    }
+}
@@ -0,0 +1,274 @@
+//! --features: `#[cfg(feature = "…")]` を評価して、無効な側を落とす
+//!
+//! `feature` 以外の述語 (target_os など) はここでは決められないので、それを含む cfg はそのまま残す。
+//! strip_features (既定は `local`) は --features が無くても常に無効として評価する。他の feature は決めない。
+use std::collections::BTreeSet;
+
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, visit_mut::{self, VisitMut}, Attribute, Expr, File, Lit,
+    Meta, Token,
+};
+
+use crate::splice::Offsets;
+
+/// どの feature が有効か分かっているか
+#[derive(Clone, Copy)]
+enum Known<'a> {
+    /// ここにあるものだけが有効 (--features)
+    On(&'a BTreeSet<String>),
+    /// ここにあるものは無効。他は分からない (strip_features)
+    Off(&'a BTreeSet<String>),
+}
+
+impl Known<'_> {
+    fn feature(self, name: &str) -> Option<bool> {
+        match self {
+            Known::On(on) => Some(on.contains(name)),
+            Known::Off(off) => off.contains(name).then_some(false),
+        }
+    }
+}
+
+/// Some(真偽) か、決められなければ None
+fn eval(meta: &Meta, features: Known) -> Option<bool> {
+    match meta {
+        Meta::NameValue(nv) if nv.path.is_ident("feature") => match &nv.value {
+            Expr::Lit(l) => match &l.lit {
+                Lit::Str(s) => features.feature(&s.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        Meta::List(list) => {
+            let args = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).ok()?;
+            let vals: Vec<Option<bool>> = args.iter().map(|m| eval(m, features)).collect();
+            if list.path.is_ident("not") {
+                return match vals.as_slice() {
+                    [v] => v.map(|b| !b),
+                    _ => None,
+                };
+            }
+            if list.path.is_ident("all") {
+                if vals.contains(&Some(false)) { return Some(false); }
+                return vals.iter().all(|v| *v == Some(true)).then_some(true);
+            }
+            if list.path.is_ident("any") {
+                if vals.contains(&Some(true)) { return Some(true); }
+                return vals.iter().all(|v| *v == Some(false)).then_some(false);
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// 属性を評価する。false ならその項目ごと落とす。true になった cfg は消し、cfg_attr は中身に置き換える
+fn apply(attrs: &mut Vec<Attribute>, features: Known) -> bool {
+    let mut out = Vec::new();
+    for a in attrs.drain(..) {
+        if a.path().is_ident("cfg") {
+            match a.parse_args::<Meta>().ok().and_then(|m| eval(&m, features)) {
+                Some(true) => {}
+                Some(false) => return false,
+                None => out.push(a),
+            }
+        } else if a.path().is_ident("cfg_attr") {
+            let Ok(args) = a.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+                out.push(a);
+                continue;
+            };
+            let mut it = args.into_iter();
+            match it.next().and_then(|pred| eval(&pred, features)) {
+                Some(true) => out.extend(it.map(|m| -> Attribute { parse_quote!(#[#m]) })),
+                Some(false) => {}
+                None => out.push(a),
+            }
+        } else {
+            out.push(a);
+        }
+    }
+    *attrs = out;
+    true
+}
+
+struct Resolver<'a> {
+    features: Known<'a>,
+}
+
+trait HasAttrs {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>>;
+}
+
+fn enabled<T: HasAttrs>(x: &mut T, features: Known) -> bool {
+    x.attrs_mut().is_none_or(|attrs| apply(attrs, features))
+}
+
+/// attrs を持つ要素の列から、無効なものを落とす
+fn retain_enabled<T: HasAttrs>(list: &mut Vec<T>, features: Known) {
+    list.retain_mut(|x| enabled(x, features));
+}
+
+impl HasAttrs for syn::Item {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        use syn::Item::*;
+        Some(match self {
+            Const(i) => &mut i.attrs,
+            Enum(i) => &mut i.attrs,
+            ExternCrate(i) => &mut i.attrs,
+            Fn(i) => &mut i.attrs,
+            ForeignMod(i) => &mut i.attrs,
+            Impl(i) => &mut i.attrs,
+            Macro(i) => &mut i.attrs,
+            Mod(i) => &mut i.attrs,
+            Static(i) => &mut i.attrs,
+            Struct(i) => &mut i.attrs,
+            Trait(i) => &mut i.attrs,
+            TraitAlias(i) => &mut i.attrs,
+            Type(i) => &mut i.attrs,
+            Union(i) => &mut i.attrs,
+            Use(i) => &mut i.attrs,
+            _ => return None,
+        })
+    }
+}
+
+impl HasAttrs for syn::ImplItem {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        use syn::ImplItem::*;
+        Some(match self {
+            Const(i) => &mut i.attrs,
+            Fn(i) => &mut i.attrs,
+            Type(i) => &mut i.attrs,
+            Macro(i) => &mut i.attrs,
+            _ => return None,
+        })
+    }
+}
+
+impl HasAttrs for syn::TraitItem {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        use syn::TraitItem::*;
+        Some(match self {
+            Const(i) => &mut i.attrs,
+            Fn(i) => &mut i.attrs,
+            Type(i) => &mut i.attrs,
+            Macro(i) => &mut i.attrs,
+            _ => return None,
+        })
+    }
+}
+
+impl HasAttrs for syn::Stmt {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        match self {
+            syn::Stmt::Local(l) => Some(&mut l.attrs),
+            syn::Stmt::Item(i) => i.attrs_mut(),
+            syn::Stmt::Macro(m) => Some(&mut m.attrs),
+            syn::Stmt::Expr(..) => None,
+        }
+    }
+}
+
+impl HasAttrs for syn::Field {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+}
+
+impl HasAttrs for syn::Variant {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+}
+
+impl HasAttrs for syn::Arm {
+    fn attrs_mut(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+}
+
+/// Punctuated は retain_mut が無いので作り直す
+fn retain_punctuated<T: HasAttrs, P: Default>(list: &mut Punctuated<T, P>, features: Known) {
+    let items: Vec<T> = std::mem::take(list).into_iter()
+        .filter_map(|mut x| enabled(&mut x, features).then_some(x))
+        .collect();
+    list.extend(items);
+}
+
+impl VisitMut for Resolver<'_> {
+    fn visit_file_mut(&mut self, f: &mut File) {
+        retain_enabled(&mut f.items, self.features);
+        visit_mut::visit_file_mut(self, f);
+    }
+    fn visit_item_mod_mut(&mut self, m: &mut syn::ItemMod) {
+        if let Some((_, items)) = &mut m.content {
+            retain_enabled(items, self.features);
+        }
+        visit_mut::visit_item_mod_mut(self, m);
+    }
+    fn visit_item_impl_mut(&mut self, i: &mut syn::ItemImpl) {
+        retain_enabled(&mut i.items, self.features);
+        visit_mut::visit_item_impl_mut(self, i);
+    }
+    fn visit_item_trait_mut(&mut self, t: &mut syn::ItemTrait) {
+        retain_enabled(&mut t.items, self.features);
+        visit_mut::visit_item_trait_mut(self, t);
+    }
+    fn visit_block_mut(&mut self, b: &mut syn::Block) {
+        retain_enabled(&mut b.stmts, self.features);
+        visit_mut::visit_block_mut(self, b);
+    }
+    fn visit_expr_match_mut(&mut self, m: &mut syn::ExprMatch) {
+        retain_enabled(&mut m.arms, self.features);
+        visit_mut::visit_expr_match_mut(self, m);
+    }
+    fn visit_fields_named_mut(&mut self, f: &mut syn::FieldsNamed) {
+        retain_punctuated(&mut f.named, self.features);
+        visit_mut::visit_fields_named_mut(self, f);
+    }
+    fn visit_fields_unnamed_mut(&mut self, f: &mut syn::FieldsUnnamed) {
+        retain_punctuated(&mut f.unnamed, self.features);
+        visit_mut::visit_fields_unnamed_mut(self, f);
+    }
+    fn visit_item_enum_mut(&mut self, e: &mut syn::ItemEnum) {
+        retain_punctuated(&mut e.variants, self.features);
+        visit_mut::visit_item_enum_mut(self, e);
+    }
+}
+
+/// features に無い feature の cfg の側を落とす
+pub(crate) fn resolve(f: &mut File, features: &BTreeSet<String>) {
+    Resolver { features: Known::On(features) }.visit_file_mut(f);
+}
+
+/// off の feature の cfg の側だけを落とす (`#[cfg(feature = "local")]` のデバッグ用の項目など)
+pub(crate) fn strip_off(f: &mut File, off: &BTreeSet<String>) {
+    if !off.is_empty() {
+        Resolver { features: Known::Off(off) }.visit_file_mut(f);
+    }
+}
+
+/// 解答 (f は src を読んだもの) のトップレベルから off の feature の側の項目を文字列のまま抜く。
+/// 解答は書いたとおりに出力するので構文木からは作り直さない。抜くものが無ければ None
+pub(crate) fn strip_off_source(src: &str, f: &File, off: &BTreeSet<String>) -> Option<String> {
+    if off.is_empty() {
+        return None;
+    }
+    let offsets = Offsets::new(src);
+    let cut: Vec<(usize, usize)> = f.items.iter()
+        .filter(|it| !enabled(&mut (*it).clone(), Known::Off(off)))
+        .filter_map(|it| {
+            let (a, b) = (offsets.of(it.span().start())?, offsets.of(it.span().end())?);
+            // 項目の後ろの改行までを消す
+            Some((a, b + src[b..].find('\n').map_or(src.len() - b, |i| i + 1)))
+        })
+        .collect();
+    if cut.is_empty() {
+        return None;
+    }
+    let mut out = src.to_string();
+    for (a, b) in cut.iter().rev() {
+        out.replace_range(a..b, "");
+    }
+    Some(out)
+}
@@ -0,0 +1,122 @@
+//! 解答からどのモジュールを束ねたかの依存グラフ (--emit-graph)
+//!
+//! 束ねた後のモジュール木の各ファイルを読み直して、`crate::` / `super::` の use・呼んでいるマクロ・
+//! `mod x;`・埋め込んだ外部クレートへの参照を辺にする。解答は `target` という 1 つの頂点。
+use std::{collections::BTreeSet, path::PathBuf};
+
+use anyhow::Result;
+use quote::ToTokens;
+
+use crate::{deps_of, macros, paths_in_tokens, strip, verbatim, Module, Options, Resolved};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dot,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "dot" => Some(Format::Dot),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+const TARGET: &str = "target";
+
+#[derive(Default)]
+struct Graph {
+    nodes: BTreeSet<String>,
+    edges: BTreeSet<(String, String)>,
+}
+
+/// コードを持つモジュールのパス
+fn modules(m: &Module, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, String)>) {
+    if let Some(src) = &m.code {
+        out.push((path.clone(), src.clone()));
+    }
+    for (name, c) in &m.children {
+        path.push(name.clone());
+        modules(c, path, out);
+        path.pop();
+    }
+}
+
+fn build(r: &Resolved, lib_roots: &[(String, PathBuf)], opts: &Options) -> Result<Graph> {
+    let mut g = Graph::default();
+    g.nodes.insert(TARGET.to_string());
+    let mut all = Vec::new();
+    for (_, tree) in &r.trees {
+        modules(tree, &mut Vec::new(), &mut all);
+    }
+    let names: BTreeSet<Vec<String>> = all.iter().map(|(p, _)| p.clone()).collect();
+    g.nodes.extend(names.iter().map(|p| p.join("::")));
+    let mut edge = |from: &str, to: &[String]| {
+        if names.contains(to) && from != to.join("::") {
+            g.edges.insert((from.to_string(), to.join("::")));
+        }
+    };
+    for m in &r.direct {
+        edge(TARGET, m);
+    }
+    for (path, src) in &all {
+        let Some((_, lib_root)) = lib_roots.iter().find(|(n, _)| *n == path[0]) else { continue };
+        let Ok(mut ast) = verbatim::parse_file(src) else { continue };
+        strip(&mut ast, opts);
+        let from = path.join("::");
+        for m in deps_of(lib_root, &ast, path)? {
+            edge(&from, &m);
+        }
+        let called = macros::invoked_in(&ast);
+        if !called.is_empty() {
+            let index = macros::index(lib_root, &path[0], opts.cache)?;
+            for m in called.iter().filter_map(|c| index.get(c)) {
+                edge(&from, m);
+            }
+        }
+        for it in &ast.items {
+            if let syn::Item::Mod(syn::ItemMod { content: None, ident, .. }) = it {
+                let mut child = path.clone();
+                child.push(ident.to_string());
+                edge(&from, &child);
+            }
+        }
+        for (name, _) in r.trees.iter().filter(|(n, _)| opts.vendor.contains(*n) && **n != path[0]) {
+            let mut found = Vec::new();
+            paths_in_tokens(ast.to_token_stream(), name, &mut found);
+            if !found.is_empty() {
+                edge(&from, std::slice::from_ref(*name));
+            }
+        }
+    }
+    Ok(g)
+}
+
+fn render(g: &Graph, format: Format) -> String {
+    match format {
+        Format::Dot => {
+            let mut out = String::from("digraph bundle {\n");
+            out += &format!("    \"{TARGET}\" [shape=box];\n");
+            for n in g.nodes.iter().filter(|n| *n != TARGET) {
+                out += &format!("    \"{n}\";\n");
+            }
+            for (a, b) in &g.edges {
+                out += &format!("    \"{a}\" -> \"{b}\";\n");
+            }
+            out + "}\n"
+        }
+        Format::Json => {
+            let edges: Vec<_> = g.edges.iter().map(|(a, b)| serde_json::json!({ "from": a, "to": b })).collect();
+            let obj = serde_json::json!({ "nodes": g.nodes, "edges": edges });
+            serde_json::to_string_pretty(&obj).unwrap_or_default() + "\n"
+        }
+    }
+}
+
+/// 解答から束ねるモジュールの依存グラフを format で書く
+pub(crate) fn emit(r: &Resolved, lib_roots: &[(String, PathBuf)], format: Format, opts: &Options) -> Result<String> {
+    Ok(render(&build(r, lib_roots, opts)?, format))
+}
@@ -0,0 +1,38 @@
+//! 内容のハッシュ (FNV-1a 64bit)。Rust のバージョンに依らず同じ値になる
+const OFFSET: u64 = 0xcbf29ce484222325;
+const PRIME: u64 = 0x100000001b3;
+
+pub struct Hasher(u64);
+
+impl Hasher {
+    pub fn new() -> Hasher {
+        Hasher(OFFSET)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+        // 区切りも混ぜて ["ab", "c"] と ["a", "bc"] を区別する
+        self.0 ^= 0xff;
+        self.0 = self.0.wrapping_mul(PRIME);
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Hasher::new()
+    }
+}
+
+/// bytes のハッシュを 16 進で
+pub fn hex(bytes: &[u8]) -> String {
+    let mut h = Hasher::new();
+    h.write(bytes);
+    h.hex()
+}
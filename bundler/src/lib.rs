@@ -0,0 +1,1521 @@
+//! 提出用に library を解答と同じ 1 ファイルへ束ねる (acsub からも使う)
+//!
+//! 入口は [`bundle`] (ソース文字列から) と [`bundle_file`] (ファイルから)。
+//! ライブラリは `(use で書く名前, src ディレクトリ)` の組で渡し、束ね方は [`Options`] で決める。
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use quote::{format_ident, quote};
+use syn::{parse_file, visit::Visit, visit_mut::VisitMut, File, Item, ItemMod, ItemUse, UseTree};
+
+mod cache;
+pub mod config;
+pub mod cpp;
+mod doctest;
+pub mod edition;
+mod features;
+pub mod graph;
+pub mod hash;
+mod macros;
+mod minify;
+pub mod notebook;
+mod proc_macros;
+pub mod py;
+pub mod report;
+mod revision;
+mod rustfmt;
+mod shake;
+pub mod snippets;
+pub mod source_map;
+mod splice;
+mod stats;
+mod verbatim;
+pub mod verify;
+
+//------------------------------------------------------------
+// 1. ユーティリティ
+//------------------------------------------------------------
+
+fn collect_leaves(t: &UseTree,
+                  prefix: &mut Vec<String>,
+                  out: &mut Vec<Vec<String>>) {
+    match t {
+        UseTree::Path(p) => { prefix.push(p.ident.to_string());
+            collect_leaves(&p.tree, prefix, out);
+            prefix.pop(); }
+        UseTree::Group(g) => {
+            for item in &g.items { collect_leaves(item, prefix, out); }
+        }
+        UseTree::Name(n) => {
+            let mut full = prefix.clone(); full.push(n.ident.to_string()); out.push(full);
+        }
+        UseTree::Rename(n) => {
+            // 別名は解答側の use にそのまま残るので、束ねる側は元の名前で持っておけばよい
+            // (`fenwick as fw` のようなモジュールの別名は modules_of が子孫ごと拾う)
+            let mut full = prefix.clone(); full.push(n.ident.to_string()); out.push(full);
+        }
+        UseTree::Glob(_) => {
+            // `m::*` は m の中身すべて。modules_of で m 以下のモジュールに広げる
+            let mut full = prefix.clone(); full.push(GLOB.to_string()); out.push(full);
+        }
+    }
+}
+
+const GLOB: &str = "*";
+
+/// dir にちょうど name という名前 (大文字小文字も区別) のエントリがあればそのパス。
+/// 大文字小文字を区別しないファイルシステムでも `Gcd` で gcd.rs を拾わないように、
+/// exists() ではなくディレクトリの中身と比べる
+fn entry_named(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir).ok()?
+        .flatten()
+        .find(|e| e.file_name().to_str() == Some(name))
+        .map(|e| e.path())
+}
+
+/// ["adry_library","hash","fenwick"] → <root>/hash/fenwick.rs (無ければ <root>/hash/fenwick/mod.rs)
+fn lib_file(root: &Path, segs: &[String]) -> PathBuf {
+    let Some((last, dirs)) = segs[1..].split_last() else { return root.to_path_buf() };
+    let mut dir = root.to_path_buf();
+    for s in dirs {
+        match entry_named(&dir, s).filter(|p| p.is_dir()) {
+            Some(p) => dir = p,
+            None => return dir.join(s).join(last),
+        }
+    }
+    let flat = entry_named(&dir, &format!("{last}.rs")).filter(|p| p.is_file());
+    // hash/fenwick/mod.rs の形
+    let nested = entry_named(&dir, last)
+        .filter(|p| p.is_dir())
+        .and_then(|d| entry_named(&d, "mod.rs"))
+        .filter(|p| p.is_file());
+    match (flat, nested) {
+        (Some(flat), Some(nested)) => {
+            // rustc なら E0761。.rs の方を使う (警告は 1 度だけ)
+            thread_local!(static WARNED: std::cell::RefCell<BTreeSet<PathBuf>> = Default::default());
+            if WARNED.with(|w| w.borrow_mut().insert(flat.clone())) {
+                eprintln!("warning: both {} and {} exist; using the former", flat.display(), nested.display());
+            }
+            flat
+        }
+        (Some(p), None) | (None, Some(p)) => p,
+        (None, None) => dir.join(last),
+    }
+}
+
+/// `#[path = "..."]` の値
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find(|a| a.path().is_ident("path")).and_then(|a| match &a.meta {
+        syn::Meta::NameValue(syn::MetaNameValue { value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }), .. }) => Some(s.value()),
+        _ => None,
+    })
+}
+
+/// lib_file に、親のファイルの `#[path = "impl/fenwick_impl.rs"] mod fenwick;` を加味したもの
+fn module_file(root: &Path, segs: &[String]) -> PathBuf {
+    let Some((last, parent)) = segs.split_last().filter(|(_, p)| !p.is_empty()) else { return lib_file(root, segs) };
+    let (parent_file, standard) = if parent.len() == 1 {
+        (root.join("lib.rs"), true)
+    } else {
+        let f = module_file(root, parent);
+        let standard = f == lib_file(root, parent);
+        (f, standard)
+    };
+    let dir = parent_file.parent().unwrap_or(root);
+    if let Some(f) = cache::parsed(&parent_file) {
+        for it in &f.items {
+            if let Item::Mod(ItemMod { content: None, ident, attrs, .. }) = it
+                && ident == last
+                && let Some(p) = path_attr(attrs)
+            {
+                // 宣言したファイルのあるディレクトリから数える
+                return dir.join(p);
+            }
+        }
+    }
+    if standard {
+        return lib_file(root, segs);
+    }
+    // #[path] で読んだファイルの子は mod.rs と同じくそのディレクトリから探す
+    let flat = dir.join(format!("{last}.rs"));
+    if flat.is_file() { flat } else { dir.join(last).join("mod.rs") }
+}
+
+/// segs のファイルとして探した場所 (見つからなかったときに知らせる)
+fn looked_at(lib_root: &Path, segs: &[String]) -> Vec<PathBuf> {
+    let fp = module_file(lib_root, segs);
+    let Some(last) = segs.last() else { return vec![fp] };
+    let name = fp.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let dir = if fp.ends_with(Path::new(last).join("mod.rs")) {
+        fp.parent().and_then(Path::parent)
+    } else if name == *last || name == format!("{last}.rs") {
+        fp.parent()
+    } else {
+        // #[path] で決まったファイル
+        None
+    };
+    match dir {
+        Some(dir) => vec![dir.join(format!("{last}.rs")), dir.join(last).join("mod.rs")],
+        None => vec![fp],
+    }
+}
+
+/// ライブラリにファイルが見つからないモジュール
+#[derive(Debug)]
+pub struct MissingModule {
+    pub path: Vec<String>,
+    pub looked: Vec<PathBuf>,
+    /// このモジュールを使っていたライブラリのモジュール (解答から直接なら None)
+    pub from: Option<Vec<String>>,
+}
+
+impl std::fmt::Display for MissingModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let looked: Vec<String> = self.looked.iter().map(|p| p.display().to_string()).collect();
+        write!(f, "could not resolve `{}` — looked at {}", self.path.join("::"), looked.join(", "))?;
+        if let Some(from) = &self.from {
+            write!(f, " (used by `{}`)", from.join("::"))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingModule {}
+
+/// syn のエラーを `src/ds/seg.rs:12:5: expected …` と、その行と位置の印にする
+fn located(e: syn::Error, origin: &str, src: &str) -> anyhow::Error {
+    let at = e.span().start();
+    // 字句の誤り (閉じていない括弧など) は位置が取れないことがある
+    if at.line == 0 {
+        return anyhow::anyhow!("{origin}: {e}");
+    }
+    let line = src.lines().nth(at.line - 1).unwrap_or("");
+    let pad = line.chars().take(at.column).map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+    anyhow::anyhow!("{origin}:{}:{}: {e}\n    {line}\n    {pad}^", at.line, at.column + 1)
+}
+
+/// ソースを読む。CRLF は LF にして、どの環境でも同じ出力にする
+fn read_source(path: &Path) -> std::io::Result<String> {
+    Ok(fs::read_to_string(path)?.replace("\r\n", "\n"))
+}
+
+/// <root> 以下の .rs を列挙して ["library", ..] のモジュールパスにする
+fn lib_modules(root: &Path) -> Result<Vec<Vec<String>>> {
+    modules_under(root, vec!["library".to_string()])
+}
+
+/// dir 以下の .rs を prefix の下のモジュールパスにする
+fn modules_under(dir: &Path, mut prefix: Vec<String>) -> Result<Vec<Vec<String>>> {
+    fn walk(dir: &Path, prefix: &mut Vec<String>, out: &mut Vec<Vec<String>>) -> Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("read_dir {:?}", dir))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for e in entries {
+            let p = e.path();
+            let Some(stem) = p.file_stem().and_then(|s| s.to_str()) else { continue };
+            if p.is_dir() {
+                prefix.push(stem.to_string());
+                walk(&p, prefix, out)?;
+                prefix.pop();
+            } else if p.extension().is_some_and(|x| x == "rs") {
+                // crate root は モジュールではない
+                if prefix.len() == 1 && (stem == "lib" || stem == "main") { continue; }
+                // foo/mod.rs は foo そのもの (foo.rs もあればそちらと同じモジュール)
+                if stem == "mod" {
+                    if prefix.len() > 1 && !out.contains(prefix) { out.push(prefix.clone()); }
+                    continue;
+                }
+                let mut full = prefix.clone(); full.push(stem.to_string());
+                if !out.contains(&full) { out.push(full); }
+            }
+        }
+        Ok(())
+    }
+    let mut out = Vec::new();
+    walk(dir, &mut prefix, &mut out)?;
+    Ok(out)
+}
+
+/// segs がライブラリのモジュール (.rs かディレクトリ) を指すか
+fn is_module(lib_root: &Path, segs: &[String]) -> bool {
+    let p = module_file(lib_root, segs);
+    p.is_dir() || (p.is_file() && p.extension().is_some_and(|x| x == "rs"))
+}
+
+/// use の leaf (`[library, a, Foo]` / `[library, a, *]`) → 束ねるモジュール。
+/// グロブやモジュールそのものならそのモジュールと、ディレクトリにある子孫のモジュールすべて
+fn modules_of(lib_root: &Path, mut leaf: Vec<String>) -> Result<Vec<Vec<String>>> {
+    // leaf 自体がモジュール (`use library::hash as h;`) なら、h:: の先で何を使うか分からないのでグロブと同じに扱う
+    let glob = if leaf.last().is_some_and(|l| l == GLOB) {
+        leaf.pop();
+        true
+    } else if leaf.len() > 1 && is_module(lib_root, &leaf) {
+        true
+    } else {
+        leaf.pop();
+        false
+    };
+    if leaf.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !glob {
+        return Ok(vec![leaf]);
+    }
+    let mut dir = lib_root.to_path_buf();
+    for s in &leaf[1..] {
+        match entry_named(&dir, s).filter(|p| p.is_dir()) {
+            Some(p) => dir = p,
+            None => return Ok(vec![leaf]),
+        }
+    }
+    let mut out = modules_under(&dir, leaf.clone())?;
+    out.push(leaf);
+    Ok(out)
+}
+
+/// use の木が外へ出す名前 (別名ならその別名)
+fn exported_names(t: &UseTree, out: &mut Vec<String>) {
+    match t {
+        UseTree::Path(p)   => exported_names(&p.tree, out),
+        UseTree::Group(g)  => g.items.iter().for_each(|t| exported_names(t, out)),
+        UseTree::Name(n)   => out.push(n.ident.to_string()),
+        UseTree::Rename(r) => out.push(r.rename.to_string()),
+        UseTree::Glob(_)   => {}
+    }
+}
+
+/// トークン列の中の `root::a::b` の形のパス
+fn paths_in_tokens(ts: proc_macro2::TokenStream, root: &str, out: &mut Vec<Vec<String>>) {
+    use proc_macro2::TokenTree;
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let is_colon = |i: usize| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            // 前が `::` なら `::library` や `x::library` なので別物
+            TokenTree::Ident(id) if id == root && !(i > 0 && is_colon(i - 1)) => {
+                let mut segs = vec![id.to_string()];
+                while is_colon(i + 1) && is_colon(i + 2)
+                    && let Some(TokenTree::Ident(next)) = tokens.get(i + 3)
+                {
+                    segs.push(next.to_string());
+                    i += 3;
+                }
+                if segs.len() > 1 { out.push(segs); }
+            }
+            TokenTree::Group(g) => paths_in_tokens(g.stream(), root, out),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// lib.rs の `pub use ds::segtree::SegTree;` のような根での再公開のうち、item を出すもの。
+/// lib.rs は丸ごとは入れられない (全モジュールの宣言を持つ) ので、該当する use だけ拾う
+fn root_reexports(lib_root: &Path, item: &str) -> Vec<ItemUse> {
+    let Some(ast) = cache::parsed(&lib_root.join("lib.rs")) else { return Vec::new() };
+    ast.items.into_iter().filter_map(|it| match it {
+        Item::Use(u) if !matches!(u.vis, syn::Visibility::Inherited) => {
+            let mut names = Vec::new();
+            exported_names(&u.tree, &mut names);
+            names.iter().any(|n| n == item).then_some(u)
+        }
+        _ => None,
+    }).collect()
+}
+
+/// 根での use の leaf。`crate::` / `self::` も相対パスも根 (name) から数える
+fn root_use_leaves(u: &ItemUse, name: &str) -> Vec<Vec<String>> {
+    let mut out = Vec::new();
+    let tree = match &u.tree {
+        UseTree::Path(p) if p.ident == "crate" || p.ident == "self" => &p.tree,
+        t => t,
+    };
+    collect_leaves(tree, &mut vec![name.to_string()], &mut out);
+    out
+}
+
+/// 属性が `#[cfg(test)]` / `#[test]` / `#[should_panic]` を含むか
+fn is_test_attr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|a| {
+        a.path().is_ident("test")
+            || a.path().is_ident("should_panic")
+            || (a.path().is_ident("cfg")
+                && a.parse_args::<syn::Ident>().is_ok_and(|i| i == "test"))
+    })
+}
+
+fn item_attrs(it: &Item) -> &[syn::Attribute] {
+    match it {
+        Item::Const(i) => &i.attrs,
+        Item::Enum(i) => &i.attrs,
+        Item::ExternCrate(i) => &i.attrs,
+        Item::Fn(i) => &i.attrs,
+        Item::ForeignMod(i) => &i.attrs,
+        Item::Impl(i) => &i.attrs,
+        Item::Macro(i) => &i.attrs,
+        Item::Mod(i) => &i.attrs,
+        Item::Static(i) => &i.attrs,
+        Item::Struct(i) => &i.attrs,
+        Item::Trait(i) => &i.attrs,
+        Item::TraitAlias(i) => &i.attrs,
+        Item::Type(i) => &i.attrs,
+        Item::Union(i) => &i.attrs,
+        Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+/// `#[cfg(test)] mod tests` や `#[test] fn` を取り除く (インラインの mod の中も)
+fn strip_test_items(items: &mut Vec<Item>) {
+    items.retain(|it| !is_test_attr(item_attrs(it)));
+    for it in items {
+        if let Item::Mod(ItemMod { content: Some((_, inner)), .. }) = it {
+            strip_test_items(inner);
+        }
+    }
+}
+
+fn strip_tests(f: &mut File) {
+    strip_test_items(&mut f.items);
+}
+
+/// `///` `//!` (= `#[doc]` 属性) をすべて取り除く
+fn strip_docs(f: &mut File) {
+    struct V;
+    impl VisitMut for V {
+        fn visit_attributes_mut(&mut self, attrs: &mut Vec<syn::Attribute>) {
+            attrs.retain(|a| !a.path().is_ident("doc"));
+        }
+    }
+    V.visit_file_mut(f);
+}
+
+/// doc の 1 行が ``` / ~~~ (コードブロックの開き・閉じ) か
+fn doc_fence(line: &str) -> bool {
+    let t = line.trim_start();
+    t.starts_with("```") || t.starts_with("~~~")
+}
+
+/// ドキュメントのコードブロック (```) だけを取り除く。説明の文は残す
+fn strip_doctests(f: &mut File) {
+    struct V;
+    impl VisitMut for V {
+        fn visit_attributes_mut(&mut self, attrs: &mut Vec<syn::Attribute>) {
+            // `///` は 1 行ずつ別の属性なので、フェンスの中かどうかは属性をまたいで持つ
+            let mut fenced = false;
+            attrs.retain_mut(|a| {
+                let syn::Meta::NameValue(nv) = &mut a.meta else { return true };
+                let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value else { return true };
+                if !nv.path.is_ident("doc") { return true; }
+                let text = s.value();
+                let mut kept = Vec::new();
+                for line in text.split('\n') {
+                    if doc_fence(line) {
+                        fenced = !fenced;
+                    } else if !fenced {
+                        kept.push(line);
+                    }
+                }
+                if kept.len() == text.split('\n').count() { return true; }
+                if kept.iter().all(|l| l.trim().is_empty()) { return false; }
+                let lit = syn::LitStr::new(&kept.join("\n"), s.span());
+                nv.value = syn::parse_quote!(#lit);
+                true
+            });
+        }
+    }
+    V.visit_file_mut(f);
+}
+
+/// `pub(crate)` / `pub(super)` / `pub(in …)` を `pub` にする (`pub mod library` の下に移すと届かなくなることがある)。
+/// use は広げると元の項目より公開範囲が広くなって通らないので (`pub(crate) use` したマクロなど) そのまま
+fn widen_visibility(f: &mut File) {
+    struct V;
+    impl VisitMut for V {
+        fn visit_visibility_mut(&mut self, v: &mut syn::Visibility) {
+            if let syn::Visibility::Restricted(r) = v
+                && !r.path.is_ident("self")
+            {
+                *v = syn::Visibility::Public(Default::default());
+            }
+        }
+        fn visit_item_use_mut(&mut self, _: &mut ItemUse) {}
+    }
+    V.visit_file_mut(f);
+}
+
+/// opts で落とすもの (テストなど) を落とす。依存を辿る前にも、出力する前にも同じものを使う
+fn strip(f: &mut File, opts: &Options) {
+    if opts.strip_tests {
+        strip_tests(f);
+    }
+    if opts.strip_docs {
+        strip_docs(f);
+    } else if opts.strip_doctests {
+        strip_doctests(f);
+    }
+    features::strip_off(f, &opts.strip_features);
+    if let Some(features) = &opts.features {
+        features::resolve(f, features);
+    }
+    if opts.widen_visibility {
+        widen_visibility(f);
+    }
+}
+
+//------------------------------------------------------------
+// 2. モジュール木
+//------------------------------------------------------------
+
+#[derive(Default)]
+struct Module {
+    code: Option<String>,
+    children: BTreeMap<String, Module>,
+}
+
+impl Module {
+    fn insert(&mut self, segs: &[String], code: String) {
+        match segs.split_first() {
+            Some((head, [])) => {
+                self.children.entry(head.clone()).or_default().code = Some(code)
+            }
+            Some((head, rest)) => self.children.entry(head.clone()).or_default().insert(rest, code),
+            None => {}
+        }
+    }
+    fn strip_decls(f: &File, child_names: &BTreeMap<String, Module>) -> Vec<Item> {
+        f.items.iter().filter(|it| match it {
+            Item::Mod(ItemMod { content: None, ident, .. })
+                => !child_names.contains_key(&ident.to_string()),
+            _ => true
+        }).cloned().collect()
+    }
+    fn to_tokens(&self, name: Option<&str>, opts: &Options) -> Result<proc_macro2::TokenStream> {
+        self.tokens_at(name, &mut Vec::new(), opts)
+    }
+    /// path は読めなかったときに示すモジュールパス
+    fn tokens_at(&self, name: Option<&str>, path: &mut Vec<String>, opts: &Options) -> Result<proc_macro2::TokenStream> {
+        path.extend(name.map(str::to_string));
+        let own_tokens = match &self.code {
+            Some(src) => {
+                // 読めない項目は verbatim の置き換えになっているので、字句から壊れていない限り通る
+                let mut f: File = verbatim::parse_file(src).map_err(|e| located(e, &path.join("::"), src))?;
+                strip(&mut f, opts);
+                let mut filtered = Self::strip_decls(&f, &self.children);
+                if let Some((_, header)) = attribution(src, &opts.attribution_markers) {
+                    filtered.insert(0, verbatim::placeholder(&header));
+                }
+                Some(quote! { #(#filtered)* })
+            }
+            None => None,
+        };
+        let kids = self.children.iter().map(|(n, m)| m.tokens_at(Some(n), path, opts)).collect::<Result<Vec<_>>>()?;
+        if name.is_some() { path.pop(); }
+        Ok(match name {
+            Some(n) => { let ident = format_ident!("{n}");
+                quote! { pub mod #ident { #own_tokens #(#kids)* } } }
+            None    => quote! { #own_tokens #(#kids)* },
+        })
+    }
+}
+
+//------------------------------------------------------------
+// 3. 内部 use 探索 (crate:: / super::)
+//------------------------------------------------------------
+
+/// `crate::` / `super::super::` / `self::` で始まる use の、指す先のモジュールと残りの木。
+/// crate:: はそのファイルがあるライブラリの根、super は 1 つごとに 1 段上へ
+fn internal_base<'t>(tree: &'t UseTree, cur: &[String]) -> Option<(Vec<String>, &'t UseTree)> {
+    let mut base: Option<Vec<String>> = None;
+    let mut t = tree;
+    while let UseTree::Path(p) = t {
+        if p.ident == "crate" && base.is_none() {
+            base = Some(vec![cur.first()?.clone()]);
+        } else if p.ident == "super" {
+            let b = base.get_or_insert_with(|| cur.to_vec());
+            // 根より上には行けない
+            if b.len() <= 1 { return None; }
+            b.pop();
+        } else if p.ident == "self" {
+            base.get_or_insert_with(|| cur.to_vec());
+        } else {
+            break;
+        }
+        t = &p.tree;
+    }
+    base.map(|b| (b, t))
+}
+
+fn internal_deps(ast: &File, cur_path: &[String]) -> Vec<Vec<String>> {
+    struct V<'a> { out: &'a mut Vec<Vec<String>>, cur: &'a [String] }
+    impl<'ast,'a> Visit<'ast> for V<'a> {
+        fn visit_item_use(&mut self, i: &'ast ItemUse) {
+            if let Some((mut base, rest)) = internal_base(&i.tree, self.cur) {
+                collect_leaves(rest, &mut base, self.out);
+            }
+            syn::visit::visit_item_use(self, i);
+        }
+    }
+    let mut v = Vec::new();
+    V { out: &mut v, cur: cur_path }.visit_file(ast);
+    v
+}
+
+/// 式・型の中の `crate::math::gcd(…)` / `super::mint::Mint` のような、use を通さずに書いたパス (末尾は項目の名前のまま)。
+/// 根の決め方は internal_base と同じ
+fn path_deps(ast: &File, cur_path: &[String]) -> Vec<Vec<String>> {
+    struct V<'a> { out: &'a mut Vec<Vec<String>>, cur: &'a [String] }
+    impl<'ast> Visit<'ast> for V<'_> {
+        fn visit_path(&mut self, p: &'ast syn::Path) {
+            let segs: Vec<String> = p.segments.iter().map(|s| s.ident.to_string()).collect();
+            let mut base: Option<Vec<String>> = None;
+            let mut rest = &segs[..];
+            while let Some((first, tail)) = rest.split_first().filter(|_| p.leading_colon.is_none()) {
+                if first == "crate" && base.is_none() && !tail.is_empty() {
+                    base = self.cur.first().map(|r| vec![r.clone()]);
+                } else if first == "super" {
+                    let b = base.get_or_insert_with(|| self.cur.to_vec());
+                    if b.len() <= 1 { base = None; break; }
+                    b.pop();
+                } else if first == "self" && !tail.is_empty() {
+                    base.get_or_insert_with(|| self.cur.to_vec());
+                } else {
+                    break;
+                }
+                rest = tail;
+            }
+            if let Some(mut base) = base.filter(|_| !rest.is_empty()) {
+                base.extend(rest.iter().cloned());
+                self.out.push(base);
+            }
+            syn::visit::visit_path(self, p);
+        }
+    }
+    let mut v = Vec::new();
+    V { out: &mut v, cur: cur_path }.visit_file(ast);
+    v
+}
+
+/// ast (lib_root のモジュール path のファイル) が使うライブラリのモジュール。
+/// use の先は modules_of で広げ、式・型のパスはいちばん長いモジュールの接頭辞にする (`Mint::new` の `new` まで読まないように)
+fn deps_of(lib_root: &Path, ast: &File, path: &[String]) -> Result<Vec<Vec<String>>> {
+    let mut deps = Vec::new();
+    for dep in internal_deps(ast, path) {
+        deps.extend(modules_of(lib_root, dep)?);
+    }
+    for mut dep in path_deps(ast, path) {
+        dep.pop();
+        while dep.len() > 1 && !is_module(lib_root, &dep) {
+            dep.pop();
+        }
+        if !dep.is_empty() {
+            deps.push(dep);
+        }
+    }
+    Ok(deps)
+}
+
+//------------------------------------------------------------
+// 4. 束ねる
+//------------------------------------------------------------
+
+/// 1 つのモジュールのファイルを読んで調べたもの。構文木 (Span) はスレッドをまたげないので、辿るのに要るものだけ
+#[derive(Debug, Clone)]
+struct Scanned {
+    code: String,
+    /// `use crate::…` で使うモジュール
+    deps: Vec<Vec<String>>,
+    /// 呼んでいるマクロの名前
+    called: BTreeSet<String>,
+    /// `mod x;` で宣言した子
+    children: Vec<String>,
+}
+
+/// fp を読んで調べる。読めなければ None
+fn scan(lib_root: &Path, path: &[String], fp: &Path, opts: &Options) -> Result<Option<Scanned>> {
+    cache::scanned(fp, path, opts, || {
+        let Ok(code) = read_source(fp) else { return Ok(None) };
+        let started = Instant::now();
+        let mut ast: File = verbatim::parse_file(&code).map_err(|e| located(e, &fp.display().to_string(), &code))?;
+        stats::parsed(started.elapsed());
+        strip(&mut ast, opts);
+        let deps = deps_of(lib_root, &ast, path)?;
+        let children = ast.items.iter().filter_map(|it| match it {
+            Item::Mod(ItemMod { content: None, ident, attrs, .. }) if !is_test_attr(attrs) => Some(ident.to_string()),
+            _ => None,
+        }).collect();
+        Ok(Some(Scanned { called: macros::invoked_in(&ast), code, deps, children }))
+    })
+}
+
+/// items それぞれに f を、いくつかのスレッドに分けて掛ける。結果は items の順
+fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let n = std::thread::available_parallelism().map_or(1, |n| n.get()).min(items.len());
+    if n <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let f = &f;
+    std::thread::scope(|s| {
+        let handles: Vec<_> = items.chunks(items.len().div_ceil(n))
+            .map(|chunk| s.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().expect("scan thread panicked")).collect()
+    })
+}
+
+/// roots (モジュールパス) から内部依存を辿ってモジュール木を作る
+/// 落とす部分 (opts) の中にしか無い use は辿らない。同じ深さで見つかったファイルはまとめて並列に読む
+fn bundle_modules(lib_root: &Path, roots: Vec<Vec<String>>, opts: &Options) -> Result<Module> {
+    let mut root_mod  = Module::default();
+    let mut visited   = BTreeSet::<Vec<String>>::new();
+    let root_name     = roots.first().map(|r| r[0].clone());
+    // (モジュール, それを使っていたモジュール)
+    let mut queue: Vec<(Vec<String>, Option<Vec<String>>)> = roots.into_iter().map(|r| (r, None)).collect();
+    // macro_export したマクロ → 定義したモジュール (マクロを呼ぶモジュールがあったときに作る)
+    let mut macro_index: Option<BTreeMap<String, Vec<String>>> = None;
+    // 読んだモジュールのファイル。`use crate::` で互いに使い合うのは visited で 1 度ずつになるが、
+    // `#[path]` の mod が祖先のファイルを指すと、モジュールが際限なく入れ子になる
+    let mut files = BTreeMap::<Vec<String>, PathBuf>::new();
+
+    while !queue.is_empty() {
+        let frontier: Vec<(Vec<String>, Option<Vec<String>>, PathBuf)> = std::mem::take(&mut queue).into_iter()
+            .filter(|(path, _)| visited.insert(path.clone()))
+            .map(|(path, from)| {
+                // 外部クレートは lib.rs の中身も要る (ライブラリの lib.rs は mod の宣言だけなので読まない)
+                let fp = if path.len() == 1 && opts.vendor.contains(&path[0]) {
+                    lib_root.join("lib.rs")
+                } else {
+                    module_file(lib_root, &path)
+                };
+                (path, from, fp)
+            })
+            .collect();
+        let scanned = par_map(&frontier, |(path, _, fp)| scan(lib_root, path, fp, opts));
+
+        for ((path, from, fp), scanned) in frontier.into_iter().zip(scanned) {
+            let Some(sc) = scanned? else {
+                if path.len() > 1 || opts.vendor.contains(&path[0]) {
+                    // 根 (library) は読まないので無くてよい。ほかは束ねてもコンパイルできない
+                    let missing = MissingModule { looked: looked_at(lib_root, &path), path, from };
+                    if !opts.allow_missing {
+                        return Err(missing.into());
+                    }
+                    eprintln!("warning: {missing}");
+                }
+                continue;
+            };
+            let canon = fs::canonicalize(&fp).unwrap_or_else(|_| fp.clone());
+            if let Some(k) = (1..path.len()).find(|&k| files.get(&path[..k]) == Some(&canon)) {
+                let chain: Vec<String> = (k..=path.len()).map(|n| format!("{} ({})", path[..n].join("::"), files.get(&path[..n]).unwrap_or(&canon).display())).collect();
+                anyhow::bail!("module cycle: `{}` includes its own file again as `{}`\n  {}", path[..k].join("::"), path.join("::"), chain.join("\n  -> "));
+            }
+            files.insert(path.clone(), canon);
+            root_mod.insert(&path, sc.code);
+
+            for m in sc.deps {
+                if !visited.contains(&m) { queue.push((m, Some(path.clone()))); }
+            }
+            // 他のファイルで macro_export したマクロを呼んでいれば、そのファイルも入れる
+            if !sc.called.is_empty() && let Some(name) = &root_name {
+                let index = match &mut macro_index {
+                    Some(i) => i,
+                    None => macro_index.insert(macros::index(lib_root, name, opts.cache)?),
+                };
+                for m in sc.called.iter().filter_map(|c| index.get(c)) {
+                    if !visited.contains(m) { queue.push((m.clone(), Some(path.clone()))); }
+                }
+            }
+            // `mod internal;` で宣言した子モジュールはファイルを辿って入れ子にする
+            for c in sc.children {
+                let mut child = path.clone();
+                child.push(c);
+                if !visited.contains(&child) { queue.push((child, Some(path.clone()))); }
+            }
+        }
+    }
+    Ok(root_mod)
+}
+
+/// モジュール木を整形済みのソースにする。name はライブラリの根の名前 (`library` など)
+fn render_library(root_mod: &Module, name: &str, opts: &Options) -> Result<String> {
+    let lib_ts = root_mod.to_tokens(None, opts)?;
+    Ok(match syn::parse2::<File>(lib_ts.clone()) {
+        Ok(mut ast) => {
+            rewrite_crate_paths(&mut ast, name, &opts.vendor);
+            edition::adapt(&mut ast, opts.edition);
+            if let Some(d) = opts.source_map {
+                source_map::annotate(&mut ast, d, !opts.vendor.contains(name));
+            }
+            let texts = verbatim::take(&mut ast);
+            verbatim::restore(&prettyplease::unparse(&ast), &texts, name)
+        }
+        Err(e) => { eprintln!("prettyplease failed: {e}"); lib_ts.to_string() }
+    })
+}
+
+/// ライブラリの中の `crate::hoge` は 1 ファイルにすると `crate::library::hoge`。
+/// 文字列やコメントには触らず、パス (use・式・型・`pub(in crate::…)`・マクロの中の `crate::` / `$crate::`) だけ直す。
+/// 埋め込んだ外部クレート (vendor) を指す `itertools::…` も、根に置いたモジュールなので `crate::itertools::…` にする
+fn rewrite_crate_paths(f: &mut File, name: &str, vendor: &BTreeSet<String>) {
+    struct R<'a> { name: &'a str, vendor: &'a BTreeSet<String> }
+    impl VisitMut for R<'_> {
+        fn visit_item_use_mut(&mut self, i: &mut ItemUse) {
+            if let UseTree::Path(p) = &i.tree
+                && self.vendor.contains(&p.ident.to_string())
+            {
+                let rest = std::mem::replace(&mut i.tree, UseTree::Glob(syn::UseGlob { star_token: Default::default() }));
+                i.tree = UseTree::Path(syn::UsePath { ident: format_ident!("crate"), colon2_token: Default::default(), tree: Box::new(rest) });
+                i.leading_colon = None;
+                return;
+            }
+            fn fix(t: &mut UseTree, name: &str) {
+                match t {
+                    UseTree::Path(p) if p.ident == "crate" => {
+                        let rest = std::mem::replace(&mut *p.tree, UseTree::Glob(syn::UseGlob { star_token: Default::default() }));
+                        *p.tree = UseTree::Path(syn::UsePath {
+                            ident: format_ident!("{name}"),
+                            colon2_token: Default::default(),
+                            tree: Box::new(rest),
+                        });
+                    }
+                    UseTree::Group(g) => g.items.iter_mut().for_each(|t| fix(t, name)),
+                    _ => {}
+                }
+            }
+            fix(&mut i.tree, self.name);
+            syn::visit_mut::visit_item_use_mut(self, i);
+        }
+        fn visit_path_mut(&mut self, p: &mut syn::Path) {
+            // `pub(crate)` のような 1 語のものはそのまま
+            if p.leading_colon.is_none() && p.segments.len() > 1 && p.segments[0].ident == "crate" {
+                p.segments.insert(1, format_ident!("{}", self.name).into());
+            } else if p.segments.len() > 1 && self.vendor.contains(&p.segments[0].ident.to_string()) {
+                p.leading_colon = None;
+                p.segments.insert(0, format_ident!("crate").into());
+            }
+            syn::visit_mut::visit_path_mut(self, p);
+        }
+        fn visit_macro_mut(&mut self, m: &mut syn::Macro) {
+            m.tokens = rewrite_crate_tokens(std::mem::take(&mut m.tokens), self.name);
+            syn::visit_mut::visit_macro_mut(self, m);
+        }
+    }
+    R { name, vendor }.visit_file_mut(f);
+}
+
+/// トークン列の `crate ::` (`$crate ::` も) の後ろに `name ::` を入れる
+fn rewrite_crate_tokens(ts: proc_macro2::TokenStream, name: &str) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, Punct, Spacing, TokenTree};
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let is_colon = |i: usize| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    let mut out = Vec::with_capacity(tokens.len());
+    for (i, t) in tokens.iter().enumerate() {
+        match t {
+            TokenTree::Group(g) => {
+                let mut ng = Group::new(g.delimiter(), rewrite_crate_tokens(g.stream(), name));
+                ng.set_span(g.span());
+                out.push(TokenTree::Group(ng));
+            }
+            TokenTree::Ident(id) if id == "crate" && !(i > 0 && is_colon(i - 1)) && is_colon(i + 1) && is_colon(i + 2) => {
+                out.push(t.clone());
+                out.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+                out.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+                out.push(TokenTree::Ident(format_ident!("{name}")));
+            }
+            _ => out.push(t.clone()),
+        }
+    }
+    out.into_iter().collect()
+}
+
+//------------------------------------------------------------
+// 5. 入口
+//------------------------------------------------------------
+
+/// 束ね方の設定
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// 束ねたライブラリの doc の例もコンパイルして確かめる
+    pub check: bool,
+    /// `#[cfg(test)] mod` / `#[test] fn` を出力から除く
+    pub strip_tests: bool,
+    /// ドキュメントコメントと `#[doc]` を落とす
+    pub strip_docs: bool,
+    /// ドキュメントのうちコードブロック (```) だけを落とす
+    pub strip_doctests: bool,
+    /// 制限つきの可視性 (`pub(crate)` など) を `pub` に広げる
+    pub widen_visibility: bool,
+    /// 束ねたライブラリを解答のどこに置くか
+    pub placement: Placement,
+    /// ファイルの先頭のコメントにこれがあれば、ライセンス表記としてコメントを落とすときも出力に残す
+    pub attribution_markers: Vec<String>,
+    /// 解答から辿れる項目 (fn / struct / impl / const …) だけを残す
+    pub tree_shake: bool,
+    /// ライブラリ部分のコメント・余分な空白を落として詰める
+    pub minify: bool,
+    /// minify に加えて、ライブラリの中だけで使う非公開の名前を短くする
+    pub minify_idents: bool,
+    /// 有効な feature。Some なら `#[cfg(feature = "…")]` を評価して無効な側を落とす
+    pub features: Option<BTreeSet<String>>,
+    /// features が無くても常に無効として `#[cfg(feature = "…")]` の側を落とす feature (既定は local)
+    pub strip_features: BTreeSet<String>,
+    /// 丸ごと埋め込む外部クレート (lib_roots の名前)。lib.rs も含めて全モジュールを入れる
+    pub vendor: BTreeSet<String>,
+    /// 組み込みでない derive / 属性マクロ (ジャッジに無い proc-macro クレートのもの) を警告でなくエラーにする
+    pub strict: bool,
+    /// 提出先の edition。prelude の違いや予約語を出力で吸収し、--check もこの edition で通す
+    pub edition: edition::Edition,
+    /// 各ファイルの元のソースを繋いで `//` コメントを残す (minify のときは使わない)
+    pub keep_comments: bool,
+    /// 束ねた項目に元のモジュールと行を書き添える (minify のときは付けない)
+    pub source_map: Option<source_map::Density>,
+    /// ライブラリのファイルごとに調べたものを ~/.cache/cp-assists に覚えておく
+    pub cache: bool,
+    /// 束ねたものを cargo check して確かめる (bundle_file のときだけ。解答の Cargo.toml の依存も使う)
+    pub verify: bool,
+    /// ファイルが見つからないモジュールがあっても警告だけで束ねる
+    pub allow_missing: bool,
+    /// 出力の長さの上限 (バイト)。超えたらエラー
+    pub max_bytes: Option<usize>,
+    /// max_bytes を超えても警告だけにする
+    pub warn_over_limit: bool,
+    /// 出力に `#[allow(…)]` で黙らせる lint (dead_code など)。空なら付けない
+    pub allow_lints: Vec<String>,
+    pub allow_scope: AllowScope,
+    /// 出力の整形
+    pub fmt: Fmt,
+    /// 読む・辿る・整形するのにかかった時間と、入れたモジュール・項目の数を標準エラーに出す
+    pub stats: bool,
+    /// ライブラリの git のコミットを出力に書き、コミットしていない変更があれば警告する
+    pub revision: bool,
+}
+
+/// allow_lints をどこに付けるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AllowScope {
+    /// 束ねたライブラリの `pub mod` ごと (解答の警告はそのまま)
+    #[default]
+    Module,
+    /// ファイルの先頭に `#![allow(…)]`
+    Crate,
+}
+
+/// 出力をどう整形するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fmt {
+    #[default]
+    Prettyplease,
+    /// 最後に rustfmt に通す (解答の rustfmt.toml を使う)。通らなければ prettyplease のまま
+    Rustfmt,
+}
+
+impl Fmt {
+    pub fn parse(s: &str) -> Option<Fmt> {
+        match s {
+            "prettyplease" => Some(Fmt::Prettyplease),
+            "rustfmt" => Some(Fmt::Rustfmt),
+            _ => None,
+        }
+    }
+}
+
+impl AllowScope {
+    pub fn parse(s: &str) -> Option<AllowScope> {
+        match s {
+            "module" => Some(AllowScope::Module),
+            "crate" => Some(AllowScope::Crate),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Options {
+    /// テストは提出に要らないので落とす
+    fn default() -> Self {
+        Options {
+            check: false,
+            strip_tests: true,
+            strip_docs: false,
+            strip_doctests: false,
+            widen_visibility: false,
+            placement: Placement::default(),
+            attribution_markers: ["LICENSE:", "SPDX-License-Identifier:", "Copyright"].map(String::from).to_vec(),
+            tree_shake: false,
+            minify: false,
+            minify_idents: false,
+            features: None,
+            strip_features: BTreeSet::from(["local".to_string()]),
+            vendor: BTreeSet::new(),
+            strict: false,
+            edition: edition::Edition::default(),
+            keep_comments: false,
+            source_map: None,
+            cache: true,
+            verify: false,
+            allow_missing: false,
+            max_bytes: None,
+            warn_over_limit: false,
+            allow_lints: Vec::new(),
+            allow_scope: AllowScope::default(),
+            fmt: Fmt::default(),
+            stats: false,
+            revision: true,
+        }
+    }
+}
+
+/// target_src が使う `<name>::…` を lib_roots から束ねて、提出できる 1 ファイルのソースにする。
+/// lib_roots は (use で書く名前, src) の組 (`use library::…` と `use teamlib::…` を同じ解答で使える)
+pub fn bundle(lib_roots: &[(String, PathBuf)], target_src: &str, opts: &Options) -> Result<String> {
+    bundle_named(lib_roots, target_src, "<target>", opts)
+}
+
+/// 解答から辿って、束ねるものを決めた結果
+struct Resolved<'a> {
+    /// 解答 (`mod` の中の `library::` は `crate::library::` にしたもの)
+    target_src: String,
+    target_ast: File,
+    /// (ライブラリの名前, モジュール木) を名前の順に
+    trees: Vec<(&'a String, Module)>,
+    /// 解答が直接使うモジュール
+    direct: Vec<Vec<String>>,
+}
+
+/// origin は解答が読めなかったときに示す名前 (ファイルのパス)
+fn bundle_named(lib_roots: &[(String, PathBuf)], target_src: &str, origin: &str, opts: &Options) -> Result<String> {
+    stats::reset();
+    let started = Instant::now();
+    let Resolved { target_src, target_ast, trees, .. } = resolve(lib_roots, target_src, origin, opts)?;
+    let resolved = started.elapsed();
+    if trees.is_empty() {
+        let started = Instant::now();
+        let out = if opts.fmt == Fmt::Rustfmt { format_output(target_src, origin, opts) } else { target_src };
+        let out = with_hash(out);
+        if opts.stats {
+            stats::print(resolved, started.elapsed(), &[], "", &out);
+        }
+        return check_limit(out, opts);
+    }
+    for (_, m) in &trees {
+        proc_macros::check(m, opts)?;
+    }
+
+    // --------------------- prettyprint ------------------------
+    let started = Instant::now();
+    let lib_pretty: String = trees.iter()
+        .map(|(name, m)| {
+            if opts.minify || opts.minify_idents {
+                // doc コメントもコメントなので落とす
+                let opts = Options { strip_docs: true, ..opts.clone() };
+                minify::render(m, name, &opts, &target_ast)
+            } else if opts.keep_comments {
+                Ok(splice::render(m, name, opts))
+            } else {
+                render_library(m, name, opts)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .map(|lib| match allow_attr(opts) {
+            Some(lints) if opts.allow_scope == AllowScope::Module => format!("#[allow({lints})]\n{lib}"),
+            _ => lib,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut rendered = started.elapsed();
+    if opts.check {
+        for (_, m) in &trees {
+            doctest::check(m, &lib_pretty, opts.edition)?;
+        }
+    }
+
+    let started = Instant::now();
+    let lib_placed = if opts.revision {
+        let roots: Vec<(&str, &Path)> = trees.iter()
+            .filter_map(|(name, _)| lib_roots.iter().find(|(n, _)| n == *name))
+            .map(|(n, p)| (n.as_str(), p.as_path()))
+            .collect();
+        format!("{}{lib_pretty}", revision::header(&roots))
+    } else {
+        lib_pretty.clone()
+    };
+    let mut out = place(&target_src, &target_ast, &lib_placed, opts.placement);
+    if let Some(lints) = allow_attr(opts).filter(|_| opts.allow_scope == AllowScope::Crate) {
+        // 内側の属性は項目より前に置く
+        out = format!("#![allow({lints})]\n{out}");
+    }
+    if opts.fmt == Fmt::Rustfmt {
+        // 対応表の行番号がずれないよう trailing より前に
+        out = format_output(out, origin, opts);
+    }
+    if opts.source_map == Some(source_map::Density::Trailing) {
+        // 先頭に bundle-hash の行が入る
+        out = source_map::trailing(&out, 1);
+    }
+    let out = with_hash(out);
+    rendered += started.elapsed();
+    if opts.stats {
+        let modules: Vec<&Module> = trees.iter().map(|(_, m)| m).collect();
+        stats::print(resolved, rendered, &modules, &lib_pretty, &out);
+    }
+    check_limit(out, opts)
+}
+
+/// out を rustfmt に通す。minify したものは詰めたままにする
+fn format_output(out: String, origin: &str, opts: &Options) -> String {
+    if opts.minify || opts.minify_idents {
+        eprintln!("warning: --fmt rustfmt would undo --minify; not formatting");
+        return out;
+    }
+    rustfmt::format(&out, Path::new(origin).parent(), opts.edition).unwrap_or(out)
+}
+
+/// allow_lints の `dead_code, unused_imports`。lint の名前として読めないものは落とす
+fn allow_attr(opts: &Options) -> Option<String> {
+    let lints: Vec<&str> = opts.allow_lints.iter().map(|l| l.trim())
+        .filter(|l| {
+            let ok = syn::parse_str::<syn::Path>(l).is_ok();
+            if !ok { eprintln!("warning: `{l}` is not a lint name; not adding it to #[allow]"); }
+            ok
+        })
+        .collect();
+    (!lints.is_empty()).then(|| lints.join(", "))
+}
+
+/// ファイルの先頭のコメント (`//` `//!` の連続行か `/* … */`) が attribution_markers のどれかを含めば、
+/// その長さ (バイト) と、どこに置いても doc にならない `//` `/*` に直したもの。
+/// 他のライブラリから持ってきたモジュールのライセンス表記を、コメントを落としても出力に残すため
+fn attribution(src: &str, markers: &[String]) -> Option<(usize, String)> {
+    let len = leading_comment(src);
+    let header = src[..len].trim_end();
+    if header.is_empty() || !markers.iter().any(|m| header.contains(m.as_str())) {
+        return None;
+    }
+    let text = header.lines()
+        .map(|l| match l.strip_prefix("//!").or_else(|| l.strip_prefix("/*!")) {
+            Some(rest) => format!("{}{rest}", &l[..2]),
+            None => l.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some((len, text))
+}
+
+/// ファイルの先頭のコメントの長さ (バイト)。最初の項目の `///` は含めない
+fn leading_comment(src: &str) -> usize {
+    if src.starts_with("/*") && !src.starts_with("/**") {
+        return src.find("*/").map_or(0, |i| i + 2);
+    }
+    src.split_inclusive('\n')
+        .take_while(|l| l.starts_with("//") && (l.starts_with("////") || !l.starts_with("///")))
+        .map(str::len)
+        .sum()
+}
+
+/// 書き換えた f (src を読んだもの) をソースに戻す。先頭のコメント (ライセンス表記など) は syn を通すと消えるので元のまま付ける
+fn unparse_with_header(src: &str, f: &File) -> String {
+    let len = leading_comment(src);
+    if len == 0 {
+        return verbatim::unparse(f);
+    }
+    // 先頭のコメントの `//!` は付け直すほうに任せる
+    let lines = src[..len].lines().count();
+    let mut f = f.clone();
+    f.attrs.retain(|a| syn::spanned::Spanned::span(a).start().line > lines);
+    format!("{}\n{}", src[..len].trim_end(), verbatim::unparse(&f))
+}
+
+/// 出力が max_bytes を超えていればエラー (warn_over_limit なら警告) にして、縮める方法を挙げる
+fn check_limit(out: String, opts: &Options) -> Result<String> {
+    let Some(max) = opts.max_bytes.filter(|m| out.len() > *m) else { return Ok(out) };
+    let hints: Vec<&str> = [
+        (!opts.tree_shake, "--tree-shake"),
+        (!opts.minify && !opts.minify_idents, "--minify"),
+        (opts.minify && !opts.minify_idents, "--minify-idents"),
+        (!opts.strip_docs && !opts.strip_doctests && !opts.minify && !opts.minify_idents, "--strip-doctests"),
+        (!opts.strip_docs && !opts.minify && !opts.minify_idents, "--strip-docs"),
+    ].into_iter().filter_map(|(off, flag)| off.then_some(flag)).collect();
+    let mut msg = format!("the bundled source is {} bytes, over the {max}-byte limit", out.len());
+    if !hints.is_empty() {
+        msg += &format!("; try {}", hints.join(" / "));
+    }
+    if !opts.warn_over_limit {
+        anyhow::bail!(msg);
+    }
+    eprintln!("warning: {msg}");
+    Ok(out)
+}
+
+/// 束ねたライブラリの前に置く行
+const LIBRARY_MARKER: &str = "// ===== bundled library =====";
+/// 解答の途中に置いたときの、束ねたライブラリの終わりの行
+const LIBRARY_END: &str = "// ===== end of bundled library =====";
+/// Placement::Marker で束ねたライブラリを置く行
+const PLACE_MARKER: &str = "// @library";
+
+/// 束ねたライブラリを解答のどこに置くか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// 解答の後ろ
+    #[default]
+    End,
+    /// トップレベルの `fn main` の前
+    BeforeMain,
+    /// 解答の `// @library` の行
+    Marker,
+    /// 別のファイル (出力は解答だけ。分けるのは [`split_library`] で)
+    Companion,
+}
+
+impl Placement {
+    pub fn parse(s: &str) -> Option<Placement> {
+        match s {
+            "end" => Some(Placement::End),
+            "before-main" => Some(Placement::BeforeMain),
+            "marker" => Some(Placement::Marker),
+            "companion" => Some(Placement::Companion),
+            _ => None,
+        }
+    }
+}
+
+/// 解答 (target_ast は target_src を読んだもの。prefix_crate で行は変わらない) の placement の位置に lib_pretty を置く。
+/// 置き場所が見つからなければ後ろに置く
+fn place(target_src: &str, target_ast: &File, lib_pretty: &str, placement: Placement) -> String {
+    let at = match placement {
+        Placement::BeforeMain => {
+            let main = target_ast.items.iter().find(|it| matches!(it, Item::Fn(f) if f.sig.ident == "main"));
+            // 属性・doc も含めて fn main の行の頭
+            let line = main.map(|it| syn::spanned::Spanned::span(it).start().line);
+            let at = line.and_then(|l| splice::Offsets::new(target_src).of(proc_macro2::LineColumn { line: l, column: 0 }));
+            if at.is_none() { eprintln!("warning: no top-level `fn main` in the target; putting the library at the end"); }
+            at
+        }
+        Placement::Marker => {
+            let at = target_src.match_indices(PLACE_MARKER)
+                .find(|&(i, _)| {
+                    let line_start = target_src[..i].rfind('\n').map_or(0, |n| n + 1);
+                    let line_end = target_src[i..].find('\n').map_or(target_src.len(), |n| i + n);
+                    target_src[line_start..line_end].trim() == PLACE_MARKER
+                })
+                // 次の束ね直しでも見つかるように印の行は残す
+                .map(|(i, _)| target_src[i..].find('\n').map_or(target_src.len(), |n| i + n + 1));
+            if at.is_none() { eprintln!("warning: no `{PLACE_MARKER}` line in the target; putting the library at the end"); }
+            at
+        }
+        Placement::End | Placement::Companion => None,
+    };
+    match at {
+        Some(at) => {
+            let (head, tail) = target_src.split_at(at);
+            let head = if head.is_empty() || head.ends_with('\n') { head.to_string() } else { format!("{head}\n") };
+            format!("{head}{LIBRARY_MARKER}\n\n{lib_pretty}\n{LIBRARY_END}\n\n{tail}")
+        }
+        None => format!("{target_src}\n\n{LIBRARY_MARKER}\n\n{lib_pretty}\n"),
+    }
+}
+
+/// 束ねた出力を (解答, 束ねたライブラリ) に分ける (Placement::Companion で別のファイルに書くため)。
+/// ライブラリを束ねていなければ None
+pub fn split_library(bundled: &str) -> Option<(String, String)> {
+    let at = library_at(bundled)?;
+    let head = bundled[..at].strip_suffix("\n\n").unwrap_or_else(|| bundled[..at].trim_end());
+    Some((format!("{head}\n"), bundled[at..].to_string()))
+}
+
+/// 行の頭にある LIBRARY_MARKER の位置
+fn library_at(src: &str) -> Option<usize> {
+    src.match_indices(LIBRARY_MARKER).map(|(i, _)| i)
+        .find(|&i| (i == 0 || src[..i].ends_with('\n')) && src[i + LIBRARY_MARKER.len()..].starts_with('\n'))
+}
+
+/// 束ねた出力をもう一度束ねるときのために、前に束ねた分 (LIBRARY_MARKER から後ろと、先頭に付けた行) を外す。
+/// 解答の `mod` の中で足した `crate::` (prefix_crate) も names について戻す。束ねたものでなければ None
+fn unbundle(src: &str, names: &[&str], opts: &Options) -> Option<String> {
+    let at = library_at(src)?;
+    // 解答の途中に置いたものは LIBRARY_END とその後の空行までを抜く。末尾の source map の表も要らない
+    let end = src[at..].find(&format!("\n{LIBRARY_END}\n")).map(|i| at + i + LIBRARY_END.len() + 2);
+    let joined;
+    let mut head = match end {
+        Some(end) => {
+            let tail = src[end..].strip_prefix('\n').unwrap_or(&src[end..]);
+            let tail = tail.rfind(&format!("\n\n{}", source_map::TABLE_MARKER)).map_or(tail, |i| &tail[..=i]);
+            joined = format!("{}{tail}", &src[..at]);
+            joined.as_str()
+        }
+        // 束ねるときに解答の後ろに足した空行
+        None => src[..at].strip_suffix("\n\n").unwrap_or_else(|| src[..at].trim_end()),
+    };
+    if let Some(rest) = head.strip_prefix("// bundle-hash: ") {
+        head = rest.split_once('\n').map_or("", |(_, r)| r);
+    }
+    if let Some(lints) = allow_attr(opts).filter(|_| opts.allow_scope == AllowScope::Crate)
+        && let Some(rest) = head.strip_prefix(&format!("#![allow({lints})]\n"))
+    {
+        head = rest;
+    }
+    let mut head = head.to_string();
+    for name in names {
+        head = head.replace(&format!("crate::{name}::"), &format!("{name}::"));
+    }
+    Some(head)
+}
+
+/// 解答が使うライブラリのモジュールを集めて、ライブラリごとのモジュール木にする
+fn resolve<'a>(lib_roots: &'a [(String, PathBuf)], target_src: &str, origin: &str, opts: &Options) -> Result<Resolved<'a>> {
+    let target_src = &target_src.replace("\r\n", "\n");
+    // 束ねた出力を渡されたら、前のライブラリを捨てて束ね直す
+    let names: Vec<&str> = lib_roots.iter().map(|(n, _)| n.as_str()).collect();
+    let unbundled = unbundle(target_src, &names, opts);
+    let mut target_src = unbundled.as_ref().unwrap_or(target_src);
+    let mut target_ast: File = parse_file(target_src).map_err(|e| located(e, origin, target_src))?;
+    // `#[cfg(feature = "local")]` のデバッグ用の項目は解答からも抜く
+    let stripped = features::strip_off_source(target_src, &target_ast, &opts.strip_features);
+    if let Some(s) = &stripped {
+        target_src = s;
+        target_ast = parse_file(target_src).map_err(|e| located(e, origin, target_src))?;
+    }
+
+    // ----------- use <name>::… の leaf を集める ----------
+    // 関数の中・ブロックの中・`mod` の中の use も、visit_file から辿れるものはすべて拾う
+    struct Collector<'a> {
+        out: Vec<Vec<String>>,
+        root: &'a str,
+        lib_root: &'a Path,
+        /// 解答の中の `mod` の深さ
+        depth: usize,
+        /// `crate::` を前に足す `library::` の位置。解答の `mod` の中 (束ねると `crate::library::` でないと届かない) と、
+        /// すべての `use library::…` (外部クレートでなく束ねた `crate::library` を指すと分かるように)
+        nested: Vec<proc_macro2::LineColumn>,
+        /// out のそれぞれを書いた位置 (ライブラリに見つからなかったときに知らせる)
+        sites: Vec<proc_macro2::LineColumn>,
+    }
+    impl<'ast,'a> Visit<'ast> for Collector<'a> {
+        fn visit_item_use(&mut self, i: &'ast ItemUse) {
+            if let UseTree::Path(p) = &i.tree
+                && p.ident == self.root
+            {
+                let mut pre = vec![p.ident.to_string()];
+                collect_leaves(&p.tree, &mut pre, &mut self.out);
+                self.sites.resize(self.out.len(), p.ident.span().start());
+                self.nested.push(p.ident.span().start());
+            }
+            syn::visit::visit_item_use(self, i);
+        }
+        fn visit_item_mod(&mut self, m: &'ast ItemMod) {
+            self.depth += 1;
+            syn::visit::visit_item_mod(self, m);
+            self.depth -= 1;
+        }
+        // use せずに `library::math::modpow(..)` / `library::ds::SegTree::new()` と書いたもの
+        fn visit_path(&mut self, p: &'ast syn::Path) {
+            if p.leading_colon.is_none() && p.segments.len() > 1 && p.segments[0].ident == self.root {
+                self.push_path(p.segments.iter().map(|s| s.ident.to_string()).collect(), p.segments[0].ident.span().start());
+                if self.depth > 0 { self.nested.push(p.segments[0].ident.span().start()); }
+            }
+            syn::visit::visit_path(self, p);
+        }
+        // マクロの中身は項目か文として読めればそのまま辿り、読めなければ (`println!("{}", library::a::f(x))` など)
+        // トークンからパスを探す
+        fn visit_macro(&mut self, m: &'ast syn::Macro) {
+            use syn::parse::Parser;
+            if let Ok(f) = syn::parse2::<File>(m.tokens.clone()) {
+                self.visit_file(&f);
+            } else if let Ok(stmts) = syn::Block::parse_within.parse2(m.tokens.clone()) {
+                stmts.iter().for_each(|s| self.visit_stmt(s));
+            } else {
+                let mut found = Vec::new();
+                paths_in_tokens(m.tokens.clone(), self.root, &mut found);
+                let at = syn::spanned::Spanned::span(&m.path).start();
+                found.into_iter().for_each(|segs| self.push_path(segs, at));
+            }
+            syn::visit::visit_macro(self, m);
+        }
+    }
+    impl Collector<'_> {
+        /// モジュールである限り奥へ進み、その次の 1 つ (項目の名前) までを leaf にする
+        fn push_path(&mut self, segs: Vec<String>, at: proc_macro2::LineColumn) {
+            let mut n = 1;
+            while n < segs.len() && is_module(self.lib_root, &segs[..=n]) { n += 1; }
+            self.out.push(segs[..(n + 1).min(segs.len())].to_vec());
+            self.sites.push(at);
+        }
+    }
+
+    // -------------- 再帰的にライブラリを束ねる ------------------
+    let mut trees: Vec<(&String, Module)> = Vec::new();
+    let mut direct = Vec::new();
+    let mut nested = Vec::new();
+    let called = macros::invoked_in(&target_ast);
+    for (name, lib_root) in lib_roots {
+        let mut c = Collector { out: Vec::new(), root: name, lib_root, depth: 0, nested: Vec::new(), sites: Vec::new() };
+        c.visit_file(&target_ast);
+        // このライブラリを使っていなければ、ライブラリのファイルを見に行かない
+        if c.out.is_empty() && !macros::any_non_std(&called) {
+            continue;
+        }
+        // `use library::chmin;` や `chmin!` は macro_export したマクロかもしれない
+        let macro_index = macros::index(lib_root, name, opts.cache)?;
+        let mut mods: Vec<Vec<String>> = called.iter().filter_map(|m| macro_index.get(m).cloned()).collect();
+        if c.out.is_empty() && mods.is_empty() {
+            continue;
+        }
+        nested.append(&mut c.nested);
+        if opts.vendor.contains(name) {
+            trees.push((name, vendor_tree(lib_root, name, opts)?));
+            direct.push(vec![name.clone()]);
+            continue;
+        }
+        // lib.rs で再公開されたもの (`use library::SegTree;`) は、その use を根に置いて先を辿る
+        let mut reexports: Vec<ItemUse> = Vec::new();
+        let sites: Vec<(Vec<String>, proc_macro2::LineColumn)> = c.out.iter().cloned().zip(c.sites).collect();
+        for leaf in c.out {
+            if let Some(m) = leaf.last().and_then(|l| macro_index.get(l)) {
+                mods.push(m.clone());
+            } else if leaf.len() == 2 && leaf[1] != GLOB && !is_module(lib_root, &leaf) {
+                for u in root_reexports(lib_root, &leaf[1]) {
+                    if reexports.contains(&u) { continue; }
+                    for l in root_use_leaves(&u, name) {
+                        mods.extend(modules_of(lib_root, l)?);
+                    }
+                    reexports.push(u);
+                }
+            }
+            mods.extend(modules_of(lib_root, leaf)?);
+        }
+        direct.extend(mods.iter().filter(|m| m.len() > 1).cloned());
+        let mut tree = bundle_modules(lib_root, mods, opts).map_err(|e| with_use_site(e, &sites, target_src))?;
+        if !reexports.is_empty() {
+            let lib = tree.children.entry(name.clone()).or_default();
+            let mut code = lib.code.take().unwrap_or_default();
+            // 字句をそのまま並べると `ds :: fenwick :: Fenwick ;` になるので整形して足す
+            let uses = File { shebang: None, attrs: Vec::new(), items: reexports.into_iter().map(Item::Use).collect() };
+            code += &format!("\n{}", prettyplease::unparse(&uses));
+            lib.code = Some(code);
+        }
+        if opts.tree_shake {
+            shake::shake(&mut tree, &target_ast, opts);
+        }
+        macros::hoist(&mut tree, name);
+        trees.push((name, tree));
+    }
+    // 束ねたものが使う外部クレート (itertools が使う either など) も、増えなくなるまで埋め込む
+    loop {
+        let mut added = false;
+        for (name, lib_root) in lib_roots.iter().filter(|(n, _)| opts.vendor.contains(n)) {
+            if trees.iter().any(|(n, _)| *n == name) { continue; }
+            let mut found = Vec::new();
+            for (_, m) in &trees {
+                paths_in_tokens(m.to_tokens(None, opts)?, name, &mut found);
+            }
+            if !found.is_empty() {
+                trees.push((name, vendor_tree(lib_root, name, opts)?));
+                added = true;
+            }
+        }
+        if !added { break; }
+    }
+    // `--lib` の順番に依らず同じ出力にする
+    trees.sort_by(|a, b| a.0.cmp(b.0));
+    let target_src = prefix_crate(target_src, nested);
+    Ok(Resolved { target_src, target_ast, trees, direct })
+}
+
+/// 先頭に `// bundle-hash: …` を付ける。同じ入力なら同じ出力なので、前の提出と比べられる
+fn with_hash(src: String) -> String {
+    format!("// bundle-hash: {}\n{src}", hash::hex(src.as_bytes()))
+}
+
+/// 解答から直接使ったモジュールが見つからなければ、解答のどこで使ったかを添える
+fn with_use_site(e: anyhow::Error, sites: &[(Vec<String>, proc_macro2::LineColumn)], target_src: &str) -> anyhow::Error {
+    let Some(missing) = e.downcast_ref::<MissingModule>().filter(|m| m.from.is_none()) else { return e };
+    let Some((_, at)) = sites.iter().find(|(leaf, _)| leaf.starts_with(&missing.path)) else { return e };
+    let line = target_src.lines().nth(at.line.saturating_sub(1)).unwrap_or("").trim();
+    anyhow::anyhow!("{missing}\n  used at line {}: {line}", at.line)
+}
+
+/// 外部クレートを丸ごと 1 つのモジュール木にする。どこから使われるか追い切れないので tree_shake はしない
+fn vendor_tree(lib_root: &Path, name: &str, opts: &Options) -> Result<Module> {
+    let mut tree = bundle_modules(lib_root, vec![vec![name.to_string()]], opts)?;
+    macros::hoist(&mut tree, name);
+    Ok(tree)
+}
+
+/// 解答の at の位置 (`mod` の中の `library::`) の前に `crate::` を入れる
+fn prefix_crate(src: &str, mut at: Vec<proc_macro2::LineColumn>) -> String {
+    at.sort_by_key(|p| (p.line, p.column));
+    at.dedup();
+    let line_starts: Vec<usize> = std::iter::once(0).chain(src.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let mut out = src.to_string();
+    // 後ろから入れれば前の位置はずれない
+    for p in at.iter().rev() {
+        let Some(&start) = line_starts.get(p.line - 1) else { continue };
+        // column は文字単位
+        let Some((off, _)) = src[start..].char_indices().nth(p.column) else { continue };
+        out.insert_str(start + off, "crate::");
+    }
+    out
+}
+
+/// 解答のソースと、エラーで示す名前。`-` なら標準入力から読む (エディタのバッファをそのまま流し込む)
+fn read_target(target_rs: &Path) -> Result<(String, String)> {
+    if target_rs == Path::new("-") {
+        let mut src = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut src).context("read the target from stdin")?;
+        return Ok((src.replace("\r\n", "\n"), "<stdin>".to_string()));
+    }
+    let src = read_source(target_rs).with_context(|| format!("read {:?}", target_rs))?;
+    Ok((src, target_rs.display().to_string()))
+}
+
+/// 束ねる代わりに、解答から束ねるモジュールの依存グラフを書く
+pub fn graph_file(lib_roots: &[(String, PathBuf)], target_rs: &Path, format: graph::Format, opts: &Options) -> Result<String> {
+    let (target_src, origin) = read_target(target_rs)?;
+    let r = resolve(lib_roots, &target_src, &origin, opts)?;
+    graph::emit(&r, lib_roots, format, opts)
+}
+
+/// 解答が束ねるライブラリのファイル (束ねる中身が変わったかを、束ねずに確かめるため)
+pub fn library_files(lib_roots: &[(String, PathBuf)], target_rs: &Path, opts: &Options) -> Result<Vec<PathBuf>> {
+    fn walk(m: &Module, root: &Path, path: &mut Vec<String>, out: &mut Vec<PathBuf>) {
+        if m.code.is_some() {
+            let fp = if path.len() == 1 { root.join("lib.rs") } else { module_file(root, path) };
+            if fp.is_file() && !out.contains(&fp) {
+                out.push(fp);
+            }
+        }
+        for (name, c) in &m.children {
+            path.push(name.clone());
+            walk(c, root, path, out);
+            path.pop();
+        }
+    }
+    let (target_src, origin) = read_target(target_rs)?;
+    let r = resolve(lib_roots, &target_src, &origin, opts)?;
+    let mut out = Vec::new();
+    for (name, tree) in &r.trees {
+        let Some((_, root)) = lib_roots.iter().find(|(n, _)| n == *name) else { continue };
+        for (top, m) in &tree.children {
+            walk(m, root, &mut vec![top.clone()], &mut out);
+        }
+    }
+    Ok(out)
+}
+
+/// bundle の、解答をファイルから読む版
+pub fn bundle_file(lib_roots: &[(String, PathBuf)], target_rs: &Path, opts: &Options) -> Result<String> {
+    let (target_src, origin) = read_target(target_rs)?;
+    let out = bundle_named(lib_roots, &target_src, &origin, opts)?;
+    if opts.verify {
+        let roots: Vec<PathBuf> = lib_roots.iter().map(|(_, p)| p.clone()).collect();
+        verify::cargo_check(&out, target_rs.parent(), &roots, opts.edition)?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,153 @@
+//! `#[macro_export]` した macro_rules! の扱い
+//!
+//! macro_export したマクロは定義したモジュールではなくクレートの根に出るので、
+//! 解答の `use library::chmin;` や `chmin!(…)` からは定義したファイルが分からない。
+//! ライブラリ全体から探して束ねる。
+//!
+//! 1 ファイルにすると macro_export のマクロは解答と同じクレートの根に出て、解答の `use library::chmin;` と
+//! ぶつかる。そこで macro_export を外して `pub(crate) use chmin;` で定義したモジュールから出し、
+//! `pub mod library` の根で re-export する。ライブラリの中の `crate::chmin!` / `$crate::chmin!` は
+//! ほかの `crate::` パスと一緒に `crate::library::chmin!` に直る。
+use std::{collections::{BTreeMap, BTreeSet}, path::Path};
+
+use anyhow::Result;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Attribute, File, Item, ItemMacro};
+
+use crate::{cache, lib_file, modules_under, read_source, unparse_with_header, verbatim, Module};
+
+fn is_exported(m: &ItemMacro) -> bool {
+    m.mac.path.is_ident("macro_rules") && m.attrs.iter().any(|a| a.path().is_ident("macro_export"))
+}
+
+/// ファイルの中で macro_export しているマクロ名
+fn exported_in(f: &File) -> Vec<String> {
+    f.items.iter().filter_map(|it| match it {
+        Item::Macro(m) if is_exported(m) => m.ident.as_ref().map(ToString::to_string),
+        _ => None,
+    }).collect()
+}
+
+/// マクロ名 → 定義しているモジュール
+/// use_cache なら変わっていないファイルはキャッシュから (ファイルを開かない)
+/// lib_root が無ければ空 (ライブラリを設定していない解答でも束ねられるように)
+pub(crate) fn index(lib_root: &Path, root_name: &str, use_cache: bool) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut out = BTreeMap::new();
+    if !lib_root.is_dir() {
+        return Ok(out);
+    }
+    for path in modules_under(lib_root, vec![root_name.to_string()])? {
+        let file = lib_file(lib_root, &path);
+        let names = cache::exported_macros(&file, use_cache, || {
+            let Ok(src) = read_source(&file) else { return Vec::new() };
+            // パースは重いので macro_export を含むファイルだけ
+            if !src.contains("macro_export") { return Vec::new(); }
+            verbatim::parse_file(&src).map(|f| exported_in(&f)).unwrap_or_default()
+        });
+        for name in names {
+            out.entry(name).or_insert_with(|| path.clone());
+        }
+    }
+    cache::flush();
+    Ok(out)
+}
+
+/// 標準ライブラリのマクロ。これしか呼んでいなければライブラリのマクロを探さない
+const STD_MACROS: &[&str] = &[
+    "assert", "assert_eq", "assert_ne", "cfg", "column", "compile_error", "concat", "dbg", "debug_assert",
+    "debug_assert_eq", "debug_assert_ne", "env", "eprint", "eprintln", "file", "format", "format_args",
+    "include", "include_bytes", "include_str", "line", "macro_rules", "matches", "module_path", "panic",
+    "print", "println", "stringify", "thread_local", "todo", "unimplemented", "unreachable", "vec", "write",
+    "writeln",
+];
+
+/// called にライブラリのものかもしれないマクロがあるか
+pub(crate) fn any_non_std(called: &BTreeSet<String>) -> bool {
+    called.iter().any(|m| !STD_MACROS.contains(&m.as_str()))
+}
+
+/// 呼んでいるマクロの名前 (`foo!` の foo。`a::b!` なら b)
+pub(crate) fn invoked(ts: TokenStream, out: &mut BTreeSet<String>) {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    for (i, t) in tokens.iter().enumerate() {
+        match t {
+            TokenTree::Ident(id) if matches!(tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '!') => {
+                out.insert(id.to_string());
+            }
+            TokenTree::Group(g) => invoked(g.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn invoked_in(f: &File) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    invoked(f.to_token_stream(), &mut out);
+    out
+}
+
+/// macro_export を外して `pub(crate) use name;` を添える。外したマクロの名前と、付いていた cfg を返す。
+/// `#[cfg(feature = "local")]` と `#[cfg(not(…))]` で同じ名前を 2 度定義していても、use は片方だけが残るように cfg を写す
+fn unexport(f: &mut File) -> Vec<(String, Vec<Attribute>)> {
+    let mut names = Vec::new();
+    let mut items = Vec::new();
+    for mut it in f.items.drain(..) {
+        let name = match &mut it {
+            Item::Macro(m) if is_exported(m) => {
+                m.attrs.retain(|a| !a.path().is_ident("macro_export"));
+                let cfgs: Vec<Attribute> = m.attrs.iter().filter(|a| a.path().is_ident("cfg")).cloned().collect();
+                m.ident.clone().map(|n| (n, cfgs))
+            }
+            _ => None,
+        };
+        items.push(it);
+        if let Some((name, cfgs)) = name {
+            items.push(syn::parse_quote!(#(#cfgs)* pub(crate) use #name;));
+            names.push((name.to_string(), cfgs));
+        }
+    }
+    f.items = items;
+    names
+}
+
+/// macro_export を外したマクロの (名前, 定義したモジュール)
+fn unexport_all(m: &mut Module, path: &mut Vec<String>, out: &mut Vec<(String, Vec<Attribute>, Vec<String>)>) {
+    if let Some(mut f) = m.code.as_deref().filter(|src| src.contains("macro_export")).and_then(|src| verbatim::parse_file(src).ok()) {
+        let names = unexport(&mut f);
+        if !names.is_empty() {
+            m.code = Some(unparse_with_header(m.code.as_deref().unwrap_or_default(), &f));
+            out.extend(names.into_iter().map(|(n, cfgs)| (n, cfgs, path.clone())));
+        }
+    }
+    for (name, c) in m.children.iter_mut() {
+        path.push(name.clone());
+        unexport_all(c, path, out);
+        path.pop();
+    }
+}
+
+/// 束ねたマクロを `<root_name>::name` で呼べるようにする
+pub(crate) fn hoist(tree: &mut Module, root_name: &str) {
+    let mut names = Vec::new();
+    let Some(lib) = tree.children.get_mut(root_name) else { return };
+    unexport_all(lib, &mut Vec::new(), &mut names);
+    if names.is_empty() { return; }
+    let mut code = lib.code.take().unwrap_or_default();
+    // 根で定義したものは unexport の `pub(crate) use name;` がそのまま根にある
+    // `#[cfg(…)] ` を prettyplease の書き方で
+    let pretty = |a: &Attribute| prettyplease::unparse(&syn::parse_quote!(#a fn f() {})).lines().next().unwrap_or("").to_string() + " ";
+    let mut uses: Vec<(String, String)> = names.iter()
+        .filter(|(_, _, p)| !p.is_empty())
+        .map(|(n, cfgs, path)| {
+            // `crate::` は後で `crate::library::` に書き換わるので self から指す
+            let path: String = path.iter().map(|p| format!("{p}::")).collect();
+            (cfgs.iter().map(pretty).collect(), format!("pub(crate) use self::{path}{n};"))
+        })
+        .collect();
+    uses.dedup();
+    for (cfgs, u) in uses {
+        code += &format!("\n{cfgs}{u}");
+    }
+    lib.code = Some(code);
+}
@@ -1,208 +1,401 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    env, fs,
-    path::{Path, PathBuf},
-};
+use std::{collections::BTreeSet, env, fs, path::{Path, PathBuf}, time::{Duration, SystemTime}};
 
-use anyhow::{Context, Result};
-use quote::{format_ident, quote};
-use syn::{parse_file, visit::Visit, File, Item, ItemMod, ItemUse, UseTree};
+use anyhow::{bail, Context, Result};
 
-///------------------------------------------------------------
-/// 1. ユーティリティ
-///------------------------------------------------------------
+use bundler::{config, cpp, edition, graph, notebook, py, snippets, source_map, AllowScope, Fmt, Placement};
 
-fn collect_leaves(t: &UseTree,
-                  prefix: &mut Vec<String>,
-                  out: &mut Vec<Vec<String>>) {
-    match t {
-        UseTree::Path(p) => { prefix.push(p.ident.to_string());
-            collect_leaves(&*p.tree, prefix, out);
-            prefix.pop(); }
-        UseTree::Group(g) => {
-            for item in &g.items { collect_leaves(item, prefix, out); }
-        }
-        UseTree::Name(n) => {
-            let mut full = prefix.clone(); full.push(n.ident.to_string()); out.push(full);
-        }
-        UseTree::Rename(n) => {
-            let mut full = prefix.clone(); full.push(n.ident.to_string()); out.push(full);
-        }
-        UseTree::Glob(_) => {} // グロブは無視
-    }
+/// `--name value` を取り出して args から消す
+fn take_opt(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    if i + 1 >= args.len() { usage(); }
+    let v = args.remove(i + 1);
+    args.remove(i);
+    Some(v)
 }
 
-/// ["adry_library","hash","fenwick"] → <root>/hash/fenwick.rs
-fn lib_file(root: &Path, segs: &[String]) -> PathBuf {
-    let mut p = root.to_path_buf();
-    for s in &segs[1..] { 
-        p.push(s);
-    }
-    let mut cand = p.clone();
-    cand.set_extension("rs");
-    if cand.is_file() {
-        return cand;
-    }
-    p
+/// 同じディレクトリの一時ファイルに書いてから rename する (途中で落ちても半端なファイルを残さない)
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, content).with_context(|| format!("write {:?}", tmp))?;
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("write {:?}", path));
+    }
+    Ok(())
 }
 
-///------------------------------------------------------------
-/// 2. モジュール木
-///------------------------------------------------------------
-
-#[derive(Default)]
-struct Module {
-    code: Option<String>,
-    children: BTreeMap<String, Module>,
+/// paths (ファイルかディレクトリ) 以下の .rs と更新時刻。変わったかを見るのに使う
+fn snapshot(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    fn walk(p: &Path, out: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+        if p.is_dir() {
+            let Ok(entries) = fs::read_dir(p) else { return };
+            let mut entries: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            entries.sort();
+            entries.iter().for_each(|e| walk(e, out));
+        } else if p.extension().is_some_and(|x| x == "rs") {
+            out.push((p.to_path_buf(), fs::metadata(p).and_then(|m| m.modified()).ok()));
+        }
+    }
+    let mut out = Vec::new();
+    paths.iter().for_each(|p| walk(p, &mut out));
+    out
 }
 
-impl Module {
-    fn insert(&mut self, segs: &[String], code: String) {
-        match segs.split_first() {
-            Some((head, rest)) if rest.is_empty() => {
-                self.children.entry(head.clone()).or_default().code = Some(code)
-            }
-            Some((head, rest)) => self.children.entry(head.clone()).or_default().insert(rest, code),
-            None => {}
-        }
+/// ライブラリを引数で渡さなかったとき: bundler.toml の [libraries]、無ければ target の Cargo.toml の path 依存
+fn default_roots(cfg: &config::Config, target: &Path, vendored: &[(String, PathBuf)]) -> Vec<(String, PathBuf)> {
+    let mut roots = cfg.roots();
+    // 解答の Cargo.toml の `library = { path = "…" }` から
+    if roots.is_empty() {
+        roots = config::cargo_roots(target);
+        roots.retain(|(n, _)| !vendored.iter().any(|(v, _)| v == n));
     }
-    fn strip_decls(f: &File, child_names: &BTreeMap<String, Module>) -> Vec<Item> {
-        f.items.iter().filter(|it| match it {
-            Item::Mod(ItemMod { content: None, ident, .. })
-                => !child_names.contains_key(&ident.to_string()),
-            _ => true
-        }).cloned().collect()
-    }
-    fn to_tokens(&self, name: Option<&str>) -> proc_macro2::TokenStream {
-        let own_tokens = self.code.as_ref().map(|src| {
-            let f: File = parse_file(src).expect("parse"); // ライブラリ内はほぼパース通る前提
-            let filtered = Self::strip_decls(&f, &self.children);
-            quote! { #(#filtered)* }
-        });
-        let kids: Vec<_> = self.children.iter().map(|(n, m)| m.to_tokens(Some(n))).collect();
-        match name {
-            Some(n) => { let ident = format_ident!("{n}");
-                quote! { pub mod #ident { #own_tokens #(#kids)* } } }
-            None    => quote! { #own_tokens #(#kids)* },
-        }
+    if roots.is_empty() {
+        eprintln!("no library given: pass <adry_library/src> or --lib, write [libraries] in {}, or add the library as a path dependency in Cargo.toml", config::FILE_NAME);
+        usage();
     }
+    roots
 }
 
-///------------------------------------------------------------
-/// 3. 内部 use 探索 (crate:: / super::)
-///------------------------------------------------------------
-
-fn internal_deps(ast: &File, cur_path: &[String]) -> Vec<Vec<String>> {
-    struct V<'a> { out: &'a mut Vec<Vec<String>>, cur: &'a [String] }
-    impl<'ast,'a> Visit<'ast> for V<'a> {
-        fn visit_item_use(&mut self, i: &'ast ItemUse) {
-            match &i.tree {
-                UseTree::Path(p) if p.ident == "crate" => {
-                    let mut segs = vec!["library".into()];
-                    collect_leaves(&*p.tree, &mut segs, self.out);
-                    if segs.len() > 1 {
-                        segs.pop();
-                    }
-                }
-                UseTree::Path(p) if p.ident == "super" && !self.cur.is_empty() => {
-                    let mut base = self.cur[..self.cur.len()-1].to_vec(); // 1段上へ
-                    collect_leaves(&*p.tree, &mut base, self.out);
-                    if base.len() > 1 {
-                        base.pop();
-                    }
-                }
-                _ => {}
+/// targets をそれぞれ束ねて out_dir/<ファイル名> に書く。ライブラリを調べた結果は問題をまたいで使い回す。
+/// 束ねられない問題があっても残りは続け、最後にまとめて失敗にする
+fn bundle_all(roots: &[(String, PathBuf)], targets: &[PathBuf], out_dir: &Path, opts: &bundler::Options, report: bool) -> Result<()> {
+    let mut names = BTreeSet::new();
+    for t in targets {
+        let Some(name) = t.file_name() else { bail!("{} is not a file", t.display()) };
+        if !names.insert(name) {
+            bail!("two targets are named {}; they would overwrite each other in {}", name.to_string_lossy(), out_dir.display());
+        }
+    }
+    fs::create_dir_all(out_dir).with_context(|| format!("create {:?}", out_dir))?;
+    let mut failed = 0;
+    for t in targets {
+        let out_path = out_dir.join(t.file_name().unwrap_or_default());
+        let out = match bundler::bundle_file(roots, t, opts) {
+            Ok(out) => out,
+            Err(e) => {
+                eprintln!("{}: {e:#}", t.display());
+                failed += 1;
+                continue;
+            }
+        };
+        if report {
+            match bundler::report::report(&out) {
+                Ok(table) => eprint!("{}:\n{table}", t.display()),
+                Err(e) => eprintln!("warning: {e:#}"),
             }
-            syn::visit::visit_item_use(self, i);
         }
+        let out = match bundler::split_library(&out).filter(|_| opts.placement == Placement::Companion) {
+            Some((main, lib)) => {
+                write_atomic(&out_path.with_extension("library.rs"), &lib)?;
+                main
+            }
+            None => out,
+        };
+        write_atomic(&out_path, &out)?;
+        eprintln!("{} -> {} ({} bytes)", t.display(), out_path.display(), out.len());
+    }
+    if failed > 0 {
+        bail!("{failed} of {} targets could not be bundled", targets.len());
     }
-    let mut v = Vec::new();
-    V { out: &mut v, cur: cur_path }.visit_file(ast);
-    v
+    Ok(())
 }
 
-///------------------------------------------------------------
-/// 4. Main
-///------------------------------------------------------------
+//------------------------------------------------------------
+// Main
+//------------------------------------------------------------
+
+fn usage() -> ! {
+    eprintln!("Usage: bundler [--watch] [--report] [--stats] [--no-revision] [--check] [--verify] [--keep-tests] [--strip-docs] [--strip-doctests] [--widen-visibility] [--keep-comments] [--tree-shake] [--no-cache] [--minify[-idents]]");
+    eprintln!("               [--features a,b] [--strip-features a,b] [--edition 2018|2021|2024] [--strict] [--allow-missing] [--judge <name> | --max-bytes <n>] [--warn-over-limit] [--allow-lints a,b] [--allow-scope module|crate] [--fmt prettyplease|rustfmt] [--source-map module|item|trailing] [--placement end|before-main|marker|companion] [--companion <file>] [--root-ident <name>] [-o <out.rs> | --stdout] <adry_library/src> <target.rs>");
+    eprintln!("       bundler [--check] <target.rs>  (ライブラリなどは bundler.toml か解答の Cargo.toml の path 依存から)");
+    eprintln!("       bundler <adry_library/src> -  (解答を標準入力から読んで標準出力に書く)");
+    eprintln!("       bundler [--check] --lib <name>=<src> [--lib <name>=<src> ...] [--vendor <crate>=<src> ...] <target.rs>");
+    eprintln!("       bundler --all [--lib <name>=<src> ... | <adry_library/src>] --out-dir <dir> <target.rs> ...");
+    eprintln!("       bundler --emit-graph <dot|json> [--lib <name>=<src> ...] <target.rs>");
+    eprintln!("       bundler --cpp [-I <include dir> ...] [-o <out.cpp>] <main.cpp>");
+    eprintln!("       bundler --py [-L <library dir> ...] [-o <out.py>] <main.py>");
+    eprintln!("       bundler --snippets <vscode|luasnip|ultisnips> <adry_library/src> <out>");
+    eprintln!("       bundler --notebook <md|tex> [--paper a4|letter] <adry_library/src> <out> [module::path ...]");
+    std::process::exit(1);
+}
 
 fn main() -> Result<()> {
     // ------------------------ 引数 ---------------------------
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: bundler <adry_library/src> <target.rs>");
-        std::process::exit(1);
+    let mut args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|a| a == "--notebook") {
+        let paper = take_opt(&mut args, "--paper").unwrap_or_else(|| "a4".into());
+        if args.len() < 5 { usage(); }
+        let Some(format) = notebook::Format::parse(&args[2]) else { usage() };
+        let Some(paper) = notebook::Paper::parse(&paper) else { usage() };
+        return notebook::export(format, paper, Path::new(&args[3]), Path::new(&args[4]), &args[5..]);
     }
-    let lib_root = PathBuf::from(&args[1]);
-    let target_rs = PathBuf::from(&args[2]);
-
-    // --------------------- ターゲット読み ---------------------
-    let target_src = fs::read_to_string(&target_rs)
-        .with_context(|| format!("read {:?}", target_rs))?;
-    let target_ast: File = parse_file(&target_src)?;
-
-    // ----------- use library::… の leaf を集める ----------
-    struct Collector<'a> { out: Vec<Vec<String>>, root: &'a str }
-    impl<'ast,'a> Visit<'ast> for Collector<'a> {
-        fn visit_item_use(&mut self, i: &'ast ItemUse) {
-            if let UseTree::Path(p) = &i.tree {
-                if p.ident == self.root {
-                    let mut pre = vec![p.ident.to_string()];
-                    collect_leaves(&*p.tree, &mut pre, &mut self.out);
-                }
-            }
-            syn::visit::visit_item_use(self, i);
+    // --cpp: C++ の #include "…" を展開する
+    if args.get(1).is_some_and(|a| a == "--cpp") {
+        let mut dirs = Vec::new();
+        while let Some(d) = take_opt(&mut args, "-I") {
+            dirs.push(PathBuf::from(d));
         }
+        let output = take_opt(&mut args, "-o");
+        if args.len() != 3 { usage(); }
+        let out = cpp::bundle(Path::new(&args[2]), &dirs)?;
+        return match output {
+            Some(path) => write_atomic(Path::new(&path), &out),
+            None => { print!("{out}"); Ok(()) }
+        };
     }
-    let mut c = Collector { out: Vec::new(), root: "library" };
-    c.visit_file(&target_ast);
-
-    if c.out.is_empty() {
-        print!("{target_src}");
-        return Ok(())
+    // --py: Python の from mylib.x import y を展開する
+    if args.get(1).is_some_and(|a| a == "--py") {
+        let mut dirs = Vec::new();
+        while let Some(d) = take_opt(&mut args, "-L") {
+            dirs.push(PathBuf::from(d));
+        }
+        let output = take_opt(&mut args, "-o");
+        if args.len() != 3 { usage(); }
+        let out = py::bundle(Path::new(&args[2]), &dirs)?;
+        return match output {
+            Some(path) => write_atomic(Path::new(&path), &out),
+            None => { print!("{out}"); Ok(()) }
+        };
     }
-
-    // -------------- 再帰的にライブラリを束ねる ------------------
-    let mut root_mod  = Module::default();
-    let mut visited   = BTreeSet::<Vec<String>>::new();
-    let mut queue: Vec<Vec<String>> = c
-        .out
-        .into_iter()
-        .filter_map(|mut path| {
-            if path.len() > 1 { path.pop(); Some(path) } else { None }
-        })
-        .collect();
-
-    while let Some(path) = queue.pop() {
-        if !visited.insert(path.clone()) { continue; }
-
-        let fp = lib_file(&lib_root, &path);
-        if let Ok(code) = fs::read_to_string(&fp)
-            .with_context(|| format!("read {:?}", fp)) 
-        {
-            root_mod.insert(&path, code.clone());
-
-            let ast: File = parse_file(&code)?;
-            for dep in internal_deps(&ast, &path) {
-                let mut dep = dep.clone();
-                dep.pop();
-                if !visited.contains(&dep) { queue.push(dep); }
-            }
-        } else {
-            continue;
+    if args.get(1).is_some_and(|a| a == "--snippets") {
+        if args.len() != 5 { usage(); }
+        let Some(format) = snippets::Format::parse(&args[2]) else { usage() };
+        return snippets::export(format, Path::new(&args[3]), Path::new(&args[4]));
+    }
+    // bundler.toml があればその設定を既定にする (引数が優先)
+    let cfg = config::find()?.unwrap_or_default();
+    let mut opts = cfg.options();
+    // --check: ライブラリの doc example をコンパイルして確かめる
+    opts.check |= args.iter().any(|a| a == "--check");
+    args.retain(|a| a != "--check");
+    // --verify: 束ねたものを一時的な cargo プロジェクトで cargo check する
+    opts.verify |= args.iter().any(|a| a == "--verify");
+    args.retain(|a| a != "--verify");
+    // --keep-tests: #[cfg(test)] / #[test] も出力に残す
+    if args.iter().any(|a| a == "--keep-tests") {
+        opts.strip_tests = false;
+    }
+    args.retain(|a| a != "--keep-tests");
+    // --strip-docs: ドキュメントコメントを落とす
+    opts.strip_docs |= args.iter().any(|a| a == "--strip-docs");
+    args.retain(|a| a != "--strip-docs");
+    // --widen-visibility: pub(crate) / pub(super) / pub(in …) を pub にする
+    opts.widen_visibility |= args.iter().any(|a| a == "--widen-visibility");
+    args.retain(|a| a != "--widen-visibility");
+    // --strip-doctests: ドキュメントの ``` のコードブロックだけを落とす
+    opts.strip_doctests |= args.iter().any(|a| a == "--strip-doctests");
+    args.retain(|a| a != "--strip-doctests");
+    // --keep-comments: 元のソースを繋いで // のコメントも残す
+    opts.keep_comments |= args.iter().any(|a| a == "--keep-comments");
+    args.retain(|a| a != "--keep-comments");
+    // --no-cache: ~/.cache/cp-assists のキャッシュを使わない
+    if args.iter().any(|a| a == "--no-cache") {
+        opts.cache = false;
+    }
+    args.retain(|a| a != "--no-cache");
+    // --source-map module|item|trailing: 束ねた項目に元のモジュールと行を書き添える
+    if let Some(d) = take_opt(&mut args, "--source-map") {
+        let Some(d) = source_map::Density::parse(&d) else { usage() };
+        opts.source_map = Some(d);
+    }
+    // --minify: ライブラリ部分を詰める。--minify-idents なら非公開の名前も短くする
+    opts.minify |= args.iter().any(|a| a == "--minify");
+    opts.minify_idents |= args.iter().any(|a| a == "--minify-idents");
+    args.retain(|a| a != "--minify" && a != "--minify-idents");
+    // --features a,b: cfg(feature) を評価する (空なら全部無効として評価)
+    if let Some(list) = take_opt(&mut args, "--features") {
+        opts.features = Some(list.split(',').map(str::trim).filter(|f| !f.is_empty()).map(str::to_string).collect());
+    }
+    // --strip-features local,dev: 常に無効として落とす feature (空なら落とさない)
+    if let Some(list) = take_opt(&mut args, "--strip-features") {
+        opts.strip_features = list.split(',').map(str::trim).filter(|f| !f.is_empty()).map(str::to_string).collect();
+    }
+    // --edition 2018: 提出先の edition に出力を合わせる
+    if let Some(e) = take_opt(&mut args, "--edition") {
+        let Some(e) = edition::Edition::parse(&e) else { usage() };
+        opts.edition = e;
+    }
+    // --strict: ジャッジに無い proc-macro の derive / 属性があればエラーにする
+    opts.strict |= args.iter().any(|a| a == "--strict");
+    args.retain(|a| a != "--strict");
+    // --emit-graph dot|json: 束ねる代わりに、束ねるモジュールの依存グラフを標準出力に書く
+    let graph = take_opt(&mut args, "--emit-graph").map(|f| graph::Format::parse(&f).unwrap_or_else(|| usage()));
+    // --no-revision: ライブラリの git のコミットを書かない
+    if args.iter().any(|a| a == "--no-revision") {
+        opts.revision = false;
+    }
+    args.retain(|a| a != "--no-revision");
+    // --stats: かかった時間と入れたモジュール・項目の数を標準エラーに出す
+    opts.stats |= args.iter().any(|a| a == "--stats");
+    args.retain(|a| a != "--stats");
+    // --report: モジュールごとの大きさを標準エラーに出す
+    let report = args.iter().any(|a| a == "--report");
+    args.retain(|a| a != "--report");
+    // --watch: 解答かライブラリが変わるたびに束ね直す
+    let watch = args.iter().any(|a| a == "--watch");
+    args.retain(|a| a != "--watch");
+    // --allow-missing: ファイルが見つからないモジュールは警告だけにする
+    opts.allow_missing |= args.iter().any(|a| a == "--allow-missing");
+    args.retain(|a| a != "--allow-missing");
+    // --judge codeforces: bundler.toml の [judge_limits] の上限を使う。--max-bytes が優先
+    if let Some(judge) = take_opt(&mut args, "--judge") {
+        match cfg.judge_limits.get(&judge) {
+            Some(max) => opts.max_bytes = Some(*max),
+            None => eprintln!("warning: no [judge_limits] entry for `{judge}` in {}", config::FILE_NAME),
         }
     }
-
-    // --------------------- prettyprint ------------------------
-    let lib_ts = root_mod.to_tokens(None);
-    let lib_pretty = match syn::parse2::<File>(lib_ts.clone()) {
-        Ok(ast) => prettyplease::unparse(&ast),
-        Err(e)  => { eprintln!("prettyplease failed: {e}"); lib_ts.to_string() }
+    if let Some(max) = take_opt(&mut args, "--max-bytes") {
+        let Ok(max) = max.parse() else { usage() };
+        opts.max_bytes = Some(max);
+    }
+    // --warn-over-limit: 上限を超えても警告だけにする
+    opts.warn_over_limit |= args.iter().any(|a| a == "--warn-over-limit");
+    args.retain(|a| a != "--warn-over-limit");
+    // --allow-lints dead_code,unused_imports: 束ねたライブラリに #[allow(…)] を付ける。--allow-scope crate なら先頭に #![allow(…)]
+    if let Some(list) = take_opt(&mut args, "--allow-lints") {
+        opts.allow_lints = list.split(',').map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect();
+    }
+    if let Some(scope) = take_opt(&mut args, "--allow-scope") {
+        let Some(scope) = AllowScope::parse(&scope) else { usage() };
+        opts.allow_scope = scope;
+    }
+    // --fmt rustfmt: 出力を rustfmt に通す
+    if let Some(fmt) = take_opt(&mut args, "--fmt") {
+        let Some(fmt) = Fmt::parse(&fmt) else { usage() };
+        opts.fmt = fmt;
+    }
+    // --tree-shake: 解答から辿れる項目だけを残す
+    opts.tree_shake |= args.iter().any(|a| a == "--tree-shake");
+    args.retain(|a| a != "--tree-shake");
+    // --lib teamlib=../team/src: use teamlib::… をそこから束ねる (何度でも)
+    let mut roots = Vec::new();
+    while let Some(spec) = take_opt(&mut args, "--lib") {
+        let Some((name, path)) = spec.split_once('=') else { usage() };
+        roots.push((name.to_string(), PathBuf::from(path)));
+    }
+    // --vendor itertools=~/vendor/itertools/src: 外部クレートを丸ごと埋め込む (何度でも)
+    // bundler.toml の [vendor] は --lib などの指定があっても使う (同じ名前なら引数が優先)
+    let mut vendored = cfg.vendor_roots();
+    while let Some(spec) = take_opt(&mut args, "--vendor") {
+        let Some((name, path)) = spec.split_once('=') else { usage() };
+        vendored.retain(|(n, _)| n != name);
+        vendored.push((name.to_string(), PathBuf::from(path)));
+        opts.vendor.insert(name.to_string());
+    }
+    // -o/--output > bundler.toml の output。--stdout ならどちらがあっても標準出力
+    let output = take_opt(&mut args, "-o")
+        .or_else(|| take_opt(&mut args, "--output"))
+        .map(PathBuf::from);
+    // 解答が `-` なら標準入力から読む。パイプで使うので bundler.toml の output は見ない
+    let from_stdin = args.iter().skip(1).any(|a| a == "-");
+    if from_stdin && watch {
+        eprintln!("--watch needs a target file, not `-`");
+        usage();
+    }
+    let output = output.or(cfg.output.clone().filter(|_| !from_stdin));
+    let to_stdout = args.iter().any(|a| a == "--stdout");
+    args.retain(|a| a != "--stdout");
+    // --placement end|before-main|marker|companion: 束ねたライブラリの置き場所。companion なら --companion <file> に分けて書く
+    if let Some(p) = take_opt(&mut args, "--placement") {
+        let Some(p) = Placement::parse(&p) else { usage() };
+        opts.placement = p;
+    }
+    // --all a.rs b.rs … --out-dir bundled: 何問分もまとめて束ねる
+    let all = args.iter().any(|a| a == "--all");
+    args.retain(|a| a != "--all");
+    let out_dir = take_opt(&mut args, "--out-dir").map(PathBuf::from);
+    let companion = take_opt(&mut args, "--companion").map(PathBuf::from).or(cfg.companion.clone())
+        .or_else(|| output.as_ref().filter(|_| !to_stdout).map(|o| o.with_extension("library.rs")))
+        .filter(|_| opts.placement == Placement::Companion);
+    if opts.placement == Placement::Companion && companion.is_none() && !all {
+        eprintln!("--placement companion needs --companion <file> (or -o to put it next to)");
+        usage();
+    }
+    // --root-ident kyopro: `bundler <src> <target>` で use kyopro::… を束ねる
+    let root_ident = take_opt(&mut args, "--root-ident").unwrap_or_else(|| cfg.root_ident().to_string());
+    if all {
+        let Some(out_dir) = out_dir else { usage() };
+        let mut targets: Vec<PathBuf> = args[1..].iter().map(PathBuf::from).collect();
+        // bundler --all <lib> a.rs b.rs …
+        if roots.is_empty() && targets.first().is_some_and(|p| p.is_dir()) {
+            roots.push((root_ident, targets.remove(0)));
+        }
+        let Some(first) = targets.first() else { usage() };
+        if roots.is_empty() {
+            roots = default_roots(&cfg, first, &vendored);
+        }
+        roots.extend(vendored);
+        return bundle_all(&roots, &targets, &out_dir, &opts, report);
+    }
+    let target_rs = match args.len() {
+        // bundler <lib> <target>
+        3 if roots.is_empty() => {
+            roots.push((root_ident, PathBuf::from(&args[1])));
+            PathBuf::from(&args[2])
+        }
+        2 => {
+            if roots.is_empty() {
+                roots = default_roots(&cfg, Path::new(&args[1]), &vendored);
+            }
+            PathBuf::from(&args[1])
+        }
+        _ => usage(),
     };
+    roots.extend(vendored);
 
-    // lib_prettyのuse crate::hogeをcrate::library::hogeに変換
-    let lib_pretty = lib_pretty.replace("use crate::", "use crate::library::");
-        
-    // ------------------------ 出力 ---------------------------
-    println!("{target_src}\n\n// ===== bundled library =====\n\n{lib_pretty}");
-    Ok(())
+    if let Some(format) = graph {
+        print!("{}", bundler::graph_file(&roots, &target_rs, format, &opts)?);
+        return Ok(());
+    }
+    let emit = |out: &str| -> Result<()> {
+        if report {
+            match bundler::report::report(out) {
+                Ok(table) => eprint!("{table}"),
+                Err(e) => eprintln!("warning: {e:#}"),
+            }
+        }
+        let split = companion.as_ref().and_then(|c| bundler::split_library(out).map(|parts| (c, parts)));
+        let out = match &split {
+            Some((path, (main, lib))) => {
+                write_atomic(path, lib)?;
+                main.as_str()
+            }
+            None => out,
+        };
+        match &output {
+            Some(path) if !to_stdout => write_atomic(path, out),
+            _ => { print!("{out}"); Ok(()) }
+        }
+    };
+    if !watch {
+        return emit(&bundler::bundle_file(&roots, &target_rs, &opts)?);
+    }
+    // 書き出したファイル自身は見ない (ライブラリの中に出力していても回り続けない)
+    let mut watched: Vec<PathBuf> = vec![target_rs.clone()];
+    watched.extend(roots.iter().map(|(_, p)| p.clone()));
+    let mut last = None;
+    let mut last_out = String::new();
+    loop {
+        let out_path = output.as_ref().filter(|_| !to_stdout).and_then(|p| fs::canonicalize(p).ok());
+        let mut now = snapshot(&watched);
+        now.retain(|(p, _)| out_path.is_none() || fs::canonicalize(p).ok() != out_path);
+        if last.as_ref() != Some(&now) {
+            last = Some(now);
+            // 書きかけで読めないことがあるので、失敗しても止めずに次の変更を待つ
+            match bundler::bundle_file(&roots, &target_rs, &opts) {
+                Ok(out) if out == last_out => {}
+                Ok(out) => {
+                    emit(&out)?;
+                    if let Some(path) = output.as_ref().filter(|_| !to_stdout) {
+                        eprintln!("[watch] wrote {}", path.display());
+                    }
+                    last_out = out;
+                }
+                Err(e) => eprintln!("[watch] {e:#}"),
+            }
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
 }
@@ -0,0 +1,205 @@
+//! --minify: 束ねたライブラリ部分を詰めて出す (解答はそのまま)
+//!
+//! トークンを、くっつけると別のトークンになるところにだけ空白を入れて並べる。
+//! --minify-idents では、ライブラリの中だけで使う非公開の fn / const / static の名前を短くする。
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{visit_mut::{self, VisitMut}, File, Item, ItemMod, Visibility};
+
+use crate::{edition, rewrite_crate_paths, verbatim, Module, Options};
+
+/// 空白を最小にしてトークンを並べる
+fn write(ts: TokenStream, out: &mut String) {
+    // 直前のトークンが識別子・リテラルか、連結する Punct か
+    #[derive(PartialEq)]
+    enum Prev { Word, Punct, Open }
+    let mut prev = Prev::Open;
+    for t in ts {
+        match t {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if prev == Prev::Word { out.push(' '); }
+                out.push_str(&t.to_string());
+                prev = Prev::Word;
+            }
+            TokenTree::Punct(p) => {
+                // Alone の Punct の後に Punct が続くと 1 つの演算子に読まれかねない (`- -` → `--`, `/ /` → コメント)
+                if prev == Prev::Punct { out.push(' '); }
+                out.push(p.as_char());
+                prev = if p.spacing() == Spacing::Joint { Prev::Open } else { Prev::Punct };
+            }
+            TokenTree::Group(g) => {
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                if g.delimiter() == Delimiter::None && prev == Prev::Word { out.push(' '); }
+                out.push_str(open);
+                write(g.stream(), out);
+                out.push_str(close);
+                prev = if g.delimiter() == Delimiter::None { Prev::Word } else { Prev::Open };
+            }
+        }
+    }
+}
+
+fn idents(ts: TokenStream, out: &mut Vec<String>) {
+    for t in ts {
+        match t {
+            TokenTree::Ident(i) => out.push(i.to_string()),
+            TokenTree::Group(g) => idents(g.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+/// format!("{name}") のような、文字列の中で名前を使っているか
+fn in_literals(ts: TokenStream, name: &str) -> bool {
+    ts.into_iter().any(|t| match t {
+        TokenTree::Literal(l) => l.to_string().contains(name),
+        TokenTree::Group(g) => in_literals(g.stream(), name),
+        _ => false,
+    })
+}
+
+/// モジュールの直下の非公開の fn / const / static の (名前, 定義したモジュール)
+fn private_items(items: &[Item], path: &mut Vec<String>, out: &mut Vec<(String, Vec<String>)>) {
+    for it in items {
+        let (vis, ident) = match it {
+            Item::Fn(i) => (&i.vis, &i.sig.ident),
+            Item::Const(i) => (&i.vis, &i.ident),
+            Item::Static(i) => (&i.vis, &i.ident),
+            Item::Mod(ItemMod { ident, content: Some((_, inner)), .. }) => {
+                path.push(ident.to_string());
+                private_items(inner, path, out);
+                path.pop();
+                continue;
+            }
+            _ => continue,
+        };
+        if matches!(vis, Visibility::Inherited) {
+            out.push((ident.to_string(), path.clone()));
+        }
+    }
+}
+
+/// `_a`, `_b`, …, `_aa`, …
+fn short_name(mut i: usize) -> String {
+    let mut s = String::new();
+    loop {
+        s.insert(0, (b'a' + (i % 26) as u8) as char);
+        i /= 26;
+        if i == 0 { break; }
+        i -= 1;
+    }
+    format!("_{s}")
+}
+
+/// 定義したモジュールの中の、定義そのものと 1 語のパス (`name(…)` / `NAME`) だけを付け替える。
+/// `std::mem::swap` のような途中や末尾の区切り、フィールド・メソッド名、マクロの中には触らない
+struct Renamer<'a> {
+    /// 名前 → (定義したモジュール, 新しい名前)
+    map: &'a BTreeMap<String, (Vec<String>, String)>,
+    path: Vec<String>,
+    /// 名前ごとに付け替えた数
+    renamed: BTreeMap<String, usize>,
+}
+
+impl Renamer<'_> {
+    fn rename(&mut self, ident: &mut proc_macro2::Ident) {
+        let name = ident.to_string();
+        if let Some((_, to)) = self.map.get(&name).filter(|(def, _)| *def == self.path) {
+            *ident = proc_macro2::Ident::new(to, ident.span());
+            *self.renamed.entry(name).or_default() += 1;
+        }
+    }
+
+    fn items(&mut self, items: &mut [Item]) {
+        for it in items.iter_mut() {
+            match it {
+                Item::Fn(i) => self.rename(&mut i.sig.ident),
+                Item::Const(i) => self.rename(&mut i.ident),
+                Item::Static(i) => self.rename(&mut i.ident),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl VisitMut for Renamer<'_> {
+    fn visit_file_mut(&mut self, f: &mut File) {
+        self.items(&mut f.items);
+        visit_mut::visit_file_mut(self, f);
+    }
+    fn visit_item_mod_mut(&mut self, m: &mut ItemMod) {
+        self.path.push(m.ident.to_string());
+        if let Some((_, items)) = &mut m.content {
+            self.items(items);
+        }
+        visit_mut::visit_item_mod_mut(self, m);
+        self.path.pop();
+    }
+    fn visit_path_mut(&mut self, p: &mut syn::Path) {
+        if p.leading_colon.is_none() && p.segments.len() == 1 {
+            self.rename(&mut p.segments[0].ident);
+        }
+        visit_mut::visit_path_mut(self, p);
+    }
+}
+
+/// ライブラリの中だけで使われていて、ライブラリに 1 つしか無い名前を短くする。
+/// 付け替えた数がその名前の出現の数と合わないもの (別の意味の同じ名前がある) は残す
+fn shorten(f: &mut File, target: &File) {
+    let mut taken = Vec::new();
+    idents(f.to_token_stream(), &mut taken);
+    let mut target_idents = Vec::new();
+    idents(target.to_token_stream(), &mut target_idents);
+    let target_idents: BTreeSet<String> = target_idents.into_iter().collect();
+    let taken: BTreeSet<String> = taken.into_iter().chain(target_idents.iter().cloned()).collect();
+
+    let mut defs = Vec::new();
+    private_items(&f.items, &mut Vec::new(), &mut defs);
+    let mut counts = BTreeMap::<String, usize>::new();
+    for (n, _) in &defs {
+        *counts.entry(n.clone()).or_default() += 1;
+    }
+    let ts = f.to_token_stream();
+    let mut fresh = (0..).map(short_name).filter(|n| !taken.contains(n));
+    let mut map = BTreeMap::new();
+    for (name, def) in defs {
+        // main は残す。短くならないものもそのまま
+        if counts[&name] > 1 || name == "main" || name.len() <= 2 || target_idents.contains(&name) || in_literals(ts.clone(), &name) {
+            continue;
+        }
+        map.insert(name, (def, fresh.next().unwrap()));
+    }
+    // まず数えるだけ
+    let mut dry = Renamer { map: &map, path: Vec::new(), renamed: BTreeMap::new() };
+    dry.visit_file_mut(&mut f.clone());
+    let mut all = Vec::new();
+    idents(ts, &mut all);
+    map.retain(|name, _| dry.renamed.get(name).copied() == Some(all.iter().filter(|i| *i == name).count()));
+    Renamer { map: &map, path: Vec::new(), renamed: BTreeMap::new() }.visit_file_mut(f);
+}
+
+/// render_library の詰めた版
+pub(crate) fn render(root_mod: &Module, name: &str, opts: &Options, target: &File) -> Result<String> {
+    let mut ts = root_mod.to_tokens(None, opts)?;
+    let mut texts = Vec::new();
+    if let Ok(mut f) = syn::parse2::<File>(ts.clone()) {
+        rewrite_crate_paths(&mut f, name, &opts.vendor);
+        edition::adapt(&mut f, opts.edition);
+        if opts.minify_idents {
+            shorten(&mut f, target);
+        }
+        texts = verbatim::take(&mut f);
+        ts = f.into_token_stream();
+    }
+    let mut out = String::new();
+    write(ts, &mut out);
+    Ok(verbatim::restore(&out, &texts, name))
+}
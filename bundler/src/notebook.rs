@@ -0,0 +1,141 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use syn::parse_file;
+
+use crate::{bundle_modules, lib_modules, located, strip_tests, Module, Options};
+
+/// チームノートの出力形式
+#[derive(Clone, Copy)]
+pub enum Format {
+    Markdown,
+    Latex,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "md" => Some(Format::Markdown),
+            "tex" => Some(Format::Latex),
+            _ => None,
+        }
+    }
+}
+
+/// 用紙サイズ (LaTeX のみ効く)
+#[derive(Clone, Copy)]
+pub enum Paper {
+    A4,
+    Letter,
+}
+
+impl Paper {
+    pub fn parse(s: &str) -> Option<Paper> {
+        match s {
+            "a4" => Some(Paper::A4),
+            "letter" => Some(Paper::Letter),
+            _ => None,
+        }
+    }
+    fn latex_option(self) -> &'static str {
+        match self {
+            Paper::A4 => "a4paper",
+            Paper::Letter => "letterpaper",
+        }
+    }
+}
+
+struct Section {
+    /// library::hash::fenwick
+    title: String,
+    code: String,
+}
+
+/// モジュール木を辿って、コードを持つモジュールごとに 1 節にする (子の宣言とテストを除いて何も残らなければ節にしない)
+fn sections(m: &Module, path: &mut Vec<String>, out: &mut Vec<Section>) -> Result<()> {
+    if let Some(src) = &m.code {
+        let mut f = parse_file(src).map_err(|e| located(e, &path.join("::"), src))?;
+        strip_tests(&mut f);
+        // 子モジュールは別の節になるので宣言は消す
+        f.items.retain(|it| !matches!(it, syn::Item::Mod(syn::ItemMod { content: None, .. })));
+        // `mod a; mod b;` だけのファイルは見出しだけの節になるので出さない
+        if !f.items.is_empty() {
+            out.push(Section { title: path.join("::"), code: prettyplease::unparse(&f) });
+        }
+    }
+    for (name, child) in &m.children {
+        path.push(name.clone());
+        sections(child, path, out)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+fn render_markdown(secs: &[Section]) -> String {
+    let mut out = String::from("# Team Reference\n\n");
+    for s in secs {
+        out += &format!("- [{0}](#{1})\n", s.title, s.title.replace("::", ""));
+    }
+    for s in secs {
+        out += &format!("\n## {}\n\n````rust\n{}````\n", s.title, s.code);
+    }
+    out
+}
+
+fn render_latex(secs: &[Section], paper: Paper) -> String {
+    let mut out = format!(
+        r#"\documentclass[8pt,{},landscape]{{extarticle}}
+\usepackage[margin=1cm]{{geometry}}
+\usepackage{{multicol}}
+\usepackage{{listings}}
+\usepackage[T1]{{fontenc}}
+\lstdefinelanguage{{Rust}}{{
+  morekeywords={{as,break,const,continue,crate,else,enum,fn,for,if,impl,in,let,loop,match,mod,move,mut,pub,ref,return,self,Self,static,struct,super,trait,type,unsafe,use,where,while,dyn}},
+  sensitive=true, morecomment=[l]{{//}}, morecomment=[s]{{/*}}{{*/}}, morestring=[b]",
+}}
+\lstset{{language=Rust,basicstyle=\ttfamily\scriptsize,keywordstyle=\bfseries,commentstyle=\itshape,
+  breaklines=true,columns=fullflexible,tabsize=4,showstringspaces=false}}
+\begin{{document}}
+\begin{{multicols*}}{{3}}
+\tableofcontents
+"#,
+        paper.latex_option()
+    );
+    for s in secs {
+        out += &format!(
+            "\\section{{{}}}\n\\begin{{lstlisting}}\n{}\\end{{lstlisting}}\n",
+            s.title.replace('_', r"\_"),
+            s.code
+        );
+    }
+    out += "\\end{multicols*}\n\\end{document}\n";
+    out
+}
+
+/// selected (`hash::fenwick` 形式) のモジュールと依存をノートにする。空なら全モジュール
+pub fn export(format: Format, paper: Paper, lib_root: &Path, out: &Path, selected: &[String]) -> Result<()> {
+    let all = lib_modules(lib_root)?;
+    let roots = if selected.is_empty() {
+        all
+    } else {
+        let mut roots = Vec::new();
+        for sel in selected {
+            let mut path = vec!["library".to_string()];
+            path.extend(sel.split("::").map(str::to_string));
+            if !all.contains(&path) { bail!("module {sel} not found in {:?}", lib_root); }
+            roots.push(path);
+        }
+        roots
+    };
+    let tree = bundle_modules(lib_root, roots, &Options::default())?;
+
+    let mut secs = Vec::new();
+    sections(&tree, &mut Vec::new(), &mut secs)?;
+    let text = match format {
+        Format::Markdown => render_markdown(&secs),
+        Format::Latex => render_latex(&secs, paper),
+    };
+    fs::write(out, text).with_context(|| format!("write {:?}", out))?;
+    eprintln!("wrote {} sections to {:?}", secs.len(), out);
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! 手元では通るがジャッジで CE になる derive / 属性マクロを見つける
+//!
+//! derive や属性マクロは proc-macro クレートが定義していて、1 ファイルに束ねても一緒には入らない。
+//! 組み込みのもの以外を使っていたら知らせる (--strict ならエラー)。
+use anyhow::{bail, Result};
+use syn::{parse::Parser, punctuated::Punctuated, visit::Visit, Attribute, File, ItemMod, Token};
+
+use crate::{Module, Options};
+
+/// 組み込みの derive
+const BUILTIN_DERIVES: &[&str] = &["Debug", "Clone", "Copy", "PartialEq", "Eq", "PartialOrd", "Ord", "Hash", "Default"];
+
+/// 組み込みの属性 (と、属性の名前空間として使えるツール)
+const BUILTIN_ATTRS: &[&str] = &[
+    "cfg", "cfg_attr", "test", "ignore", "should_panic", "derive", "automatically_derived",
+    "macro_export", "macro_use", "allow", "warn", "deny", "forbid", "expect", "deprecated", "must_use",
+    "link", "link_name", "link_section", "no_link", "repr", "export_name", "no_mangle", "used",
+    "inline", "cold", "target_feature", "track_caller", "doc", "path", "non_exhaustive",
+    "rustfmt", "clippy", "diagnostic",
+];
+
+struct Finder {
+    /// 今いるモジュール (library::ds::seg)
+    path: Vec<String>,
+    /// (モジュール, 見つけたもの)
+    found: Vec<(String, String)>,
+}
+
+impl<'ast> Visit<'ast> for Finder {
+    fn visit_item_mod(&mut self, m: &'ast ItemMod) {
+        self.path.push(m.ident.to_string());
+        syn::visit::visit_item_mod(self, m);
+        self.path.pop();
+    }
+    fn visit_attribute(&mut self, a: &'ast Attribute) {
+        let Some(head) = a.path().segments.first().map(|s| s.ident.to_string()) else { return };
+        if a.path().is_ident("derive") {
+            let Ok(paths) = Punctuated::<syn::Path, Token![,]>::parse_terminated.parse2(
+                a.meta.require_list().map(|l| l.tokens.clone()).unwrap_or_default(),
+            ) else { return };
+            for p in paths {
+                let name = p.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+                // `core::fmt::Debug` のような書き方も組み込み
+                let std_path = p.segments.len() > 1 && ["std", "core"].contains(&p.segments[0].ident.to_string().as_str());
+                if (p.segments.len() == 1 && BUILTIN_DERIVES.contains(&name.as_str())) || std_path {
+                    continue;
+                }
+                self.found.push((self.path.join("::"), format!("#[derive({})]", quote::quote!(#p).to_string().replace(' ', ""))));
+            }
+        } else if !BUILTIN_ATTRS.contains(&head.as_str()) {
+            let p = a.path();
+            self.found.push((self.path.join("::"), format!("#[{}]", quote::quote!(#p).to_string().replace(' ', ""))));
+        }
+    }
+}
+
+/// 束ねたモジュール木で、組み込みでない derive / 属性マクロを使っているところを知らせる
+pub(crate) fn check(tree: &Module, opts: &Options) -> Result<()> {
+    let Ok(f) = syn::parse2::<File>(tree.to_tokens(None, opts)?) else { return Ok(()) };
+    let mut finder = Finder { path: Vec::new(), found: Vec::new() };
+    finder.visit_file(&f);
+    finder.found.dedup();
+    for (module, what) in &finder.found {
+        eprintln!("warning: {module} uses {what}, which comes from a proc-macro crate the judge may not have");
+    }
+    if opts.strict && !finder.found.is_empty() {
+        bail!("{} non-builtin derive/attribute macro(s) in the bundled library (--strict)", finder.found.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Python の解答の `from mylib.x import y` を展開して 1 つの .py にする (--py)
+//!
+//! 解答のディレクトリ → lib_dirs の順に見つかるモジュールだけを展開する (標準ライブラリなどの import はそのまま)。
+//! 展開はトップレベルの import だけで、モジュールごとに最初の 1 度だけ。
+//! ライブラリ側の `if __name__ == "__main__":` ブロックは落とす。
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::read_source;
+
+/// import する名前と `as` の別名
+type Names<'a> = Vec<(&'a str, Option<&'a str>)>;
+
+/// `from a.b import c, d as e` → ("a.b", [("c", None), ("d", Some("e"))])
+fn parse_from(stmt: &str) -> Option<(&str, Names<'_>)> {
+    let rest = stmt.strip_prefix("from ")?;
+    let (module, names) = rest.split_once(" import ")?;
+    let names = names.trim().trim_start_matches('(').trim_end_matches(')');
+    let names = names.split(',').map(str::trim).filter(|n| !n.is_empty())
+        .map(|n| match n.split_once(" as ") {
+            Some((name, alias)) => (name.trim(), Some(alias.trim())),
+            None => (n, None),
+        })
+        .collect();
+    Some((module.trim(), names))
+}
+
+/// `import a.b, c as d` のモジュール名
+fn parse_import(stmt: &str) -> Option<Vec<&str>> {
+    let rest = stmt.strip_prefix("import ")?;
+    Some(rest.split(',').map(|m| m.split(" as ").next().unwrap_or(m).trim()).collect())
+}
+
+fn is_main_guard(line: &str) -> bool {
+    let l: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    matches!(l.as_str(), "if__name__==\"__main__\":" | "if__name__=='__main__':")
+}
+
+/// 行の中の `"""` / `'''` の数 (奇数なら文字列の内外が入れ替わる)
+fn triple_quotes(line: &str) -> usize {
+    line.matches("\"\"\"").count() + line.matches("'''").count()
+}
+
+struct Inliner {
+    roots: Vec<PathBuf>,
+    done: BTreeSet<PathBuf>,
+    stack: Vec<PathBuf>,
+}
+
+impl Inliner {
+    /// `a.b` → a/b.py か a/b/__init__.py。`.x` のような相対 import は from のパッケージから探す
+    fn find(&self, module: &str, from: &Path) -> Option<PathBuf> {
+        let dots = module.len() - module.trim_start_matches('.').len();
+        let rel: PathBuf = module[dots..].split('.').filter(|s| !s.is_empty()).collect();
+        let candidates = |dir: &Path| [dir.join(&rel).with_extension("py"), dir.join(&rel).join("__init__.py")];
+        if dots > 0 {
+            let mut dir = from.parent()?.to_path_buf();
+            for _ in 1..dots {
+                dir = dir.parent()?.to_path_buf();
+            }
+            return candidates(&dir).into_iter().find(|p| p.is_file());
+        }
+        self.roots.iter().flat_map(|d| candidates(d)).find(|p| p.is_file())
+    }
+
+    fn expand(&mut self, path: &Path, is_main: bool, out: &mut String) -> Result<()> {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.stack.contains(&key) {
+            let chain: Vec<String> = self.stack.iter().chain([&key]).map(|p| p.display().to_string()).collect();
+            bail!("import cycle: {}", chain.join(" -> "));
+        }
+        if !self.done.insert(key.clone()) {
+            return Ok(());
+        }
+        let src = read_source(path).with_context(|| format!("read {:?}", path))?;
+        self.stack.push(key);
+        let mut lines = src.lines();
+        let mut in_string = false;
+        let mut in_guard = false;
+        while let Some(line) = lines.next() {
+            let top = !line.starts_with([' ', '\t']) && !line.trim().is_empty();
+            if in_string {
+                in_string = triple_quotes(line).is_multiple_of(2);
+            } else if in_guard && !top {
+                continue;
+            } else if !is_main && is_main_guard(line) {
+                in_guard = true;
+                continue;
+            } else {
+                in_guard = false;
+                in_string = !triple_quotes(line).is_multiple_of(2);
+                // ( … ) で複数行にわたる import をつなぐ
+                let mut stmt = line.trim().to_string();
+                let mut raw = vec![line];
+                if top && stmt.starts_with("from ") && stmt.contains('(') {
+                    while !stmt.contains(')') {
+                        let Some(next) = lines.next() else { break };
+                        stmt.push(' ');
+                        stmt.push_str(next.split('#').next().unwrap_or("").trim());
+                        raw.push(next);
+                    }
+                }
+                if let Some(inlined) = self.import(&stmt, top, path)? {
+                    out.push_str(&inlined);
+                    continue;
+                }
+                for l in raw {
+                    out.push_str(l);
+                    out.push('\n');
+                }
+                continue;
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// ローカルのモジュールの import なら展開した中身 (と `as` の別名の代入)
+    fn import(&mut self, stmt: &str, top: bool, path: &Path) -> Result<Option<String>> {
+        let stmt = stmt.split('#').next().unwrap_or("").trim();
+        if let Some((module, names)) = parse_from(stmt) {
+            let Some(file) = self.find(module, path) else { return Ok(None) };
+            if !top {
+                bail!("{}: `{stmt}` is not at the top level; only top-level imports of the library can be inlined", path.display());
+            }
+            for (name, _) in &names {
+                if *name != "*" && self.find(&format!("{module}.{name}"), path).is_some() {
+                    bail!("{}: `{stmt}` imports the module `{name}`; import the names from `{module}.{name}` instead", path.display());
+                }
+            }
+            let mut out = String::new();
+            self.expand(&file, false, &mut out)?;
+            for (name, alias) in names {
+                if let Some(alias) = alias.filter(|a| *a != name) {
+                    out.push_str(&format!("{alias} = {name}\n"));
+                }
+            }
+            return Ok(Some(out));
+        }
+        if let Some(modules) = parse_import(stmt)
+            && let Some(local) = modules.iter().find(|m| self.find(m, path).is_some())
+        {
+            bail!("{}: `import {local}` keeps the module namespace and cannot be inlined; use `from {local} import …`", path.display());
+        }
+        Ok(None)
+    }
+}
+
+/// main のローカルな import を再帰的に展開したソース
+pub fn bundle(main: &Path, lib_dirs: &[PathBuf]) -> Result<String> {
+    let here = main.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+    let roots = std::iter::once(here).chain(lib_dirs.iter().cloned()).collect();
+    let mut out = String::new();
+    Inliner { roots, done: BTreeSet::new(), stack: Vec::new() }.expand(main, true, &mut out)?;
+    Ok(out)
+}
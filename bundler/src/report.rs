@@ -0,0 +1,51 @@
+//! 束ねたソースのどのモジュールがどれだけの大きさかの表 (--report)
+//!
+//! 出力を読み直して、`pub mod` ごとに (子のモジュールを除いた) 項目のバイト数と行数を数える。
+//! 解答のトップレベルの項目は `(target)` にまとめる。
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use syn::{spanned::Spanned, Item, ItemMod};
+
+use crate::splice::Offsets;
+
+const TARGET: &str = "(target)";
+
+#[derive(Default, Clone, Copy)]
+struct Size {
+    bytes: usize,
+    lines: usize,
+}
+
+fn measure(items: &[Item], path: &mut Vec<String>, off: &Offsets, out: &mut BTreeMap<String, Size>) {
+    for it in items {
+        if let Item::Mod(ItemMod { ident, content: Some((_, inner)), .. }) = it {
+            path.push(ident.to_string());
+            measure(inner, path, off, out);
+            path.pop();
+            continue;
+        }
+        let (s, e) = (it.span().start(), it.span().end());
+        let (Some(a), Some(b)) = (off.of(s), off.of(e)) else { continue };
+        let key = if path.is_empty() { TARGET.to_string() } else { path.join("::") };
+        let size = out.entry(key).or_default();
+        size.bytes += b - a;
+        size.lines += e.line - s.line + 1;
+    }
+}
+
+/// 束ねた出力 (bundle の戻り値) のモジュールごとの大きさを、大きい順の表にする
+pub fn report(bundled: &str) -> Result<String> {
+    let f = syn::parse_file(bundled).context("cannot parse the bundled source for --report")?;
+    let mut sizes = BTreeMap::new();
+    measure(&f.items, &mut Vec::new(), &Offsets::new(bundled), &mut sizes);
+    let mut rows: Vec<(String, Size)> = sizes.into_iter().collect();
+    rows.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+
+    let mut out = format!("{:>8} {:>6}  module\n", "bytes", "lines");
+    for (name, s) in &rows {
+        out += &format!("{:>8} {:>6}  {name}\n", s.bytes, s.lines);
+    }
+    out += &format!("{:>8} {:>6}  total (whole file)\n", bundled.len(), bundled.lines().count());
+    Ok(out)
+}
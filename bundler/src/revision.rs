@@ -0,0 +1,39 @@
+//! 束ねたライブラリの git のコミットを出力に書き添える
+//!
+//! 提出したコードを後から作り直せるように、ライブラリのコミットを `// library: <名前> @ <コミット>` の行で残す。
+//! コミットしていない変更があれば `(dirty)` を付けて、その場で大きく警告する (そのコミットからは作り直せない)。
+use std::{path::Path, process::Command};
+
+/// dir で git を走らせた標準出力。git が無い・リポジトリでないなら None
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// (name, src) のライブラリそれぞれの行。git で管理していないものは書かない
+pub(crate) fn header(roots: &[(&str, &Path)]) -> String {
+    let mut out = String::new();
+    for (name, root) in roots {
+        let Some(commit) = git(root, &["rev-parse", "--short=12", "HEAD"]) else { continue };
+        let commit = commit.trim();
+        // src の下だけ見る (同じリポジトリの別の場所の変更は関係ない)
+        let changed: Vec<String> = git(root, &["status", "--porcelain", "--", "."])
+            .map(|s| s.lines().map(|l| l.get(3..).unwrap_or(l).to_string()).collect())
+            .unwrap_or_default();
+        if changed.is_empty() {
+            out += &format!("// library: {name} @ {commit}\n");
+            continue;
+        }
+        out += &format!("// library: {name} @ {commit} (dirty)\n");
+        eprintln!("warning: ============================================================");
+        eprintln!("warning: library `{name}` has uncommitted changes; this bundle cannot be rebuilt from {commit}");
+        for f in changed.iter().take(10) {
+            eprintln!("warning:   {f}");
+        }
+        if changed.len() > 10 {
+            eprintln!("warning:   … and {} more", changed.len() - 10);
+        }
+        eprintln!("warning: ============================================================");
+    }
+    out
+}
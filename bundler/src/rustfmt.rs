@@ -0,0 +1,48 @@
+//! 出力を rustfmt に通す (--fmt rustfmt)
+//!
+//! prettyplease の整形はエディタの rustfmt と違うので、手元の submit.rs と比べると差分が多い。
+//! 解答のディレクトリから上に探した rustfmt.toml / .rustfmt.toml を使う。rustfmt が無いか失敗したら prettyplease のまま。
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::edition::Edition;
+
+/// dir から上へ辿って最初の rustfmt.toml
+fn config_near(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|d| [d.join("rustfmt.toml"), d.join(".rustfmt.toml")])
+        .find(|p| p.is_file())
+}
+
+/// src を rustfmt で整形したもの。できなければ警告して None
+pub(crate) fn format(src: &str, target_dir: Option<&Path>, edition: Edition) -> Option<String> {
+    let dir = target_dir.filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut cmd = Command::new("rustfmt");
+    cmd.args(["--emit", "stdout", "--edition", edition.as_str()]);
+    if let Some(config) = config_near(dir) {
+        cmd.arg("--config-path").arg(config);
+    }
+    let child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("warning: could not run rustfmt ({e}); keeping the prettyplease output");
+            return None;
+        }
+    };
+    // 大きい出力で詰まらないように書くのは別スレッドで
+    let mut stdin = child.stdin.take()?;
+    let input = src.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().ok()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        eprintln!("warning: rustfmt failed; keeping the prettyplease output\n{}", err.lines().take(5).collect::<Vec<_>>().join("\n"));
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
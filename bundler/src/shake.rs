@@ -0,0 +1,141 @@
+//! 項目単位の tree shaking (--tree-shake)
+//!
+//! 名前だけで辿る大まかなもの。解答に出てくる識別子から始めて、同じ名前の fn / struct / const などと、
+//! 残した型の impl を残し、それらの中の識別子をさらに辿る。別のモジュールの同名の項目も残るので、
+//! 消しすぎることはあっても (名前が衝突しない限り) 足りなくなることはない。
+use std::collections::BTreeSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{File, Item, Type, UseTree};
+
+use crate::{strip, unparse_with_header, verbatim, Module, Options};
+
+fn idents(ts: TokenStream, out: &mut BTreeSet<String>) {
+    for t in ts {
+        match t {
+            TokenTree::Ident(i) => { out.insert(i.to_string()); }
+            TokenTree::Group(g) => idents(g.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+/// 名前で参照される項目の名前
+fn item_name(it: &Item) -> Option<String> {
+    let ident = match it {
+        Item::Fn(i) => &i.sig.ident,
+        Item::Struct(i) => &i.ident,
+        Item::Enum(i) => &i.ident,
+        Item::Union(i) => &i.ident,
+        Item::Trait(i) => &i.ident,
+        Item::TraitAlias(i) => &i.ident,
+        Item::Type(i) => &i.ident,
+        Item::Const(i) => &i.ident,
+        Item::Static(i) => &i.ident,
+        Item::Macro(i) => i.ident.as_ref()?,
+        _ => return None,
+    };
+    Some(ident.to_string())
+}
+
+/// `impl … for Foo<T>` / `impl Foo` の Foo
+fn impl_self(it: &Item) -> Option<String> {
+    let Item::Impl(i) = it else { return None };
+    match &*i.self_ty {
+        Type::Path(p) => Some(p.path.segments.last()?.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// use が取り込む名前 (グロブなら None = 常に残す)
+fn use_names(t: &UseTree, out: &mut Vec<String>) -> bool {
+    match t {
+        UseTree::Path(p) => use_names(&p.tree, out),
+        UseTree::Name(n) => { out.push(n.ident.to_string()); true }
+        UseTree::Rename(r) => { out.push(r.rename.to_string()); true }
+        UseTree::Group(g) => g.items.iter().all(|i| use_names(i, out)),
+        UseTree::Glob(_) => false,
+    }
+}
+
+/// 辿った名前と、ライブラリの中で定義されている名前
+struct Reach {
+    names: BTreeSet<String>,
+    defined: BTreeSet<String>,
+}
+
+/// 今の names で残るか
+fn keeps(it: &Item, r: &Reach) -> bool {
+    let names = &r.names;
+    if let Some(n) = item_name(it) {
+        return names.contains(&n);
+    }
+    if let Item::Impl(i) = it {
+        let self_kept = impl_self(it).is_none_or(|n| names.contains(&n));
+        // trait impl は trait も残るとき (ライブラリの外の trait なら names に無くても残す)
+        let trait_kept = i.trait_.as_ref().is_none_or(|(_, path, _)| {
+            path.segments.last().is_none_or(|s| names.contains(&s.ident.to_string()) || !r.defined.contains(&s.ident.to_string()))
+        });
+        return self_kept && trait_kept;
+    }
+    if let Item::Use(u) = it {
+        let mut imported = Vec::new();
+        return !use_names(&u.tree, &mut imported) || imported.iter().any(|n| names.contains(n));
+    }
+    // mod や extern crate などはそのまま
+    true
+}
+
+fn parsed(m: &Module, opts: &Options, out: &mut Vec<File>) {
+    if let Some(src) = &m.code
+        && let Ok(mut f) = verbatim::parse_file(src)
+    {
+        strip(&mut f, opts);
+        out.push(f);
+    }
+    for c in m.children.values() {
+        parsed(c, opts, out);
+    }
+}
+
+/// target から辿れる名前
+fn reachable(target: &File, files: &[File]) -> Reach {
+    let items: Vec<&Item> = files.iter().flat_map(|f| &f.items).collect();
+    let mut r = Reach { names: BTreeSet::new(), defined: items.iter().filter_map(|it| item_name(it)).collect() };
+    idents(target.to_token_stream(), &mut r.names);
+    let mut taken = vec![false; items.len()];
+    loop {
+        let before = r.names.len();
+        for (i, it) in items.iter().enumerate() {
+            if !taken[i] && keeps(it, &r) {
+                taken[i] = true;
+                idents(it.to_token_stream(), &mut r.names);
+            }
+        }
+        if r.names.len() == before {
+            return r;
+        }
+    }
+}
+
+fn prune(m: &mut Module, r: &Reach, opts: &Options) {
+    if let Some(src) = &m.code
+        && let Ok(mut f) = verbatim::parse_file(src)
+    {
+        strip(&mut f, opts);
+        f.items.retain(|it| keeps(it, r));
+        m.code = Some(unparse_with_header(src, &f));
+    }
+    for c in m.children.values_mut() {
+        prune(c, r, opts);
+    }
+}
+
+/// target から辿れない項目をモジュール木から落とす
+pub(crate) fn shake(tree: &mut Module, target: &File, opts: &Options) {
+    let mut files = Vec::new();
+    parsed(tree, opts, &mut files);
+    let r = reachable(target, &files);
+    prune(tree, &r, opts);
+}
@@ -0,0 +1,162 @@
+use std::{collections::BTreeSet, fs, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result};
+use syn::{parse_file, Expr, ExprLit, File, Item, ItemMod, Lit, Meta};
+
+use crate::{bundle_modules, is_test_attr, lib_modules, located, module_file, read_source, render_library, shake, strip, verbatim, Module, Options};
+
+/// スニペットの出力形式
+#[derive(Clone, Copy)]
+pub enum Format {
+    VsCode,
+    LuaSnip,
+    UltiSnips,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "vscode" => Some(Format::VsCode),
+            "luasnip" => Some(Format::LuaSnip),
+            "ultisnips" => Some(Format::UltiSnips),
+            _ => None,
+        }
+    }
+}
+
+struct Snippet {
+    /// library::hash::fenwick
+    name: String,
+    prefix: String,
+    body: String,
+}
+
+/// `//! @snippet <prefix>` があればそれを prefix にする
+fn tagged_prefix(ast: &File) -> Option<String> {
+    ast.attrs.iter().find_map(|a| {
+        let Meta::NameValue(nv) = &a.meta else { return None };
+        if !nv.path.is_ident("doc") { return None; }
+        let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = &nv.value else { return None };
+        s.value().trim().strip_prefix("@snippet").map(|p| p.trim().to_string())
+    })
+}
+
+/// lib.rs から `mod x;` を辿ったモジュール (`#[path]` のものも本当のモジュールパスで) と、
+/// どこからも宣言されていないファイルのモジュール
+fn snippet_modules(lib_root: &Path) -> Result<Vec<Vec<String>>> {
+    fn walk(lib_root: &Path, path: &[String], fp: &Path, out: &mut Vec<Vec<String>>, files: &mut BTreeSet<PathBuf>) {
+        let Some(ast) = read_source(fp).ok().and_then(|code| parse_file(&code).ok()) else { return };
+        for it in &ast.items {
+            let Item::Mod(ItemMod { content: None, ident, attrs, .. }) = it else { continue };
+            if is_test_attr(attrs) { continue; }
+            let mut child = path.to_vec();
+            child.push(ident.to_string());
+            let cfp = module_file(lib_root, &child);
+            if cfp.is_file() && files.insert(fs::canonicalize(&cfp).unwrap_or_else(|_| cfp.clone())) {
+                out.push(child.clone());
+                walk(lib_root, &child, &cfp, out, files);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    let mut files = BTreeSet::new();
+    walk(lib_root, &["library".to_string()], &lib_root.join("lib.rs"), &mut out, &mut files);
+    for path in lib_modules(lib_root)? {
+        let fp = module_file(lib_root, &path);
+        if !out.contains(&path) && !files.contains(&fs::canonicalize(&fp).unwrap_or(fp)) {
+            out.push(path);
+        }
+    }
+    Ok(out)
+}
+
+/// m とその子孫のモジュールの項目
+fn subtree_items(m: &Module, opts: &Options, out: &mut Vec<Item>) {
+    if let Some(mut f) = m.code.as_deref().and_then(|src| verbatim::parse_file(src).ok()) {
+        strip(&mut f, opts);
+        out.extend(f.items);
+    }
+    for c in m.children.values() {
+        subtree_items(c, opts, out);
+    }
+}
+
+/// ライブラリの各モジュールを、依存込みで単体で貼れるスニペットにする。
+/// 依存のモジュールからはそのモジュールが使う項目だけを残す (--tree-shake と同じ)
+fn collect(lib_root: &Path) -> Result<Vec<Snippet>> {
+    let mut out = Vec::new();
+    for path in snippet_modules(lib_root)? {
+        let fp = module_file(lib_root, &path);
+        let code = read_source(&fp).with_context(|| format!("read {:?}", fp))?;
+        let ast = parse_file(&code).map_err(|e| located(e, &fp.display().to_string(), &code))?;
+        let prefix = tagged_prefix(&ast).unwrap_or_else(|| path.last().unwrap().clone());
+        let opts = Options::default();
+        let mut tree = bundle_modules(lib_root, vec![path.clone()], &opts)?;
+        // スニペットのモジュールとその子はまるごと使う
+        let mut items = Vec::new();
+        if let Some(m) = path.iter().try_fold(&tree, |m, seg| m.children.get(seg)) {
+            subtree_items(m, &opts, &mut items);
+        }
+        shake::shake(&mut tree, &File { shebang: None, attrs: Vec::new(), items }, &opts);
+        let body = render_library(&tree, "library", &opts)?;
+        out.push(Snippet { name: path.join("::"), prefix, body });
+    }
+    Ok(out)
+}
+
+fn render_vscode(snippets: &[Snippet]) -> String {
+    let mut obj = serde_json::Map::new();
+    for s in snippets {
+        // `$` は tabstop と解釈されるのでエスケープ
+        let body: Vec<_> = s.body.lines().map(|l| l.replace('$', "\\$")).collect();
+        obj.insert(s.name.clone(), serde_json::json!({
+            "prefix": s.prefix,
+            "body": body,
+            "description": s.name,
+        }));
+    }
+    serde_json::to_string_pretty(&obj).unwrap()
+}
+
+/// luasnip.loaders.from_lua で読める形式
+fn render_luasnip(snippets: &[Snippet]) -> String {
+    fn lua_str(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+    let mut out = String::from("local ls = require(\"luasnip\")\nlocal s, t = ls.snippet, ls.text_node\n\nreturn {\n");
+    for sn in snippets {
+        let lines: Vec<_> = sn.body.lines().map(lua_str).collect();
+        out += &format!(
+            "  s({{ trig = {}, dscr = {} }}, t({{\n    {},\n  }})),\n",
+            lua_str(&sn.prefix), lua_str(&sn.name), lines.join(",\n    "),
+        );
+    }
+    out += "}\n";
+    out
+}
+
+/// UltiSnips の rust.snippets 形式
+fn render_ultisnips(snippets: &[Snippet]) -> String {
+    let mut out = String::from("priority -50\n\n");
+    for sn in snippets {
+        // `\` `$` `` ` `` は UltiSnips の構文なのでエスケープ
+        let body: String = sn.body.chars().map(|c| match c {
+            '\\' | '$' | '`' => format!("\\{c}"),
+            _ => c.to_string(),
+        }).collect();
+        out += &format!("snippet {} \"{}\" b\n{}\nendsnippet\n\n", sn.prefix, sn.name, body.trim_end());
+    }
+    out
+}
+
+pub fn export(format: Format, lib_root: &Path, out: &Path) -> Result<()> {
+    let snippets = collect(lib_root)?;
+    let text = match format {
+        Format::VsCode => render_vscode(&snippets),
+        Format::LuaSnip => render_luasnip(&snippets),
+        Format::UltiSnips => render_ultisnips(&snippets),
+    };
+    fs::write(out, text).with_context(|| format!("write {:?}", out))?;
+    eprintln!("exported {} snippets to {:?}", snippets.len(), out);
+    Ok(())
+}
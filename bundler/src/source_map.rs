@@ -0,0 +1,92 @@
+//! 束ねたファイルの行から元のファイルを辿るためのコメント (--source-map)
+//!
+//! ジャッジの「312 行目でエラー」がライブラリのどこなのか分かるように、項目の前に
+//! `// library::ds::segtree:40` (モジュール:元の行) を置くか、末尾に対応表を付ける。
+//! tree_shake などで書き換えたファイルの行は書き換えた後のもの。minify では付けない。
+use syn::{spanned::Spanned, File, Item, ItemMod};
+
+use crate::verbatim;
+
+/// どれだけ細かく付けるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Density {
+    /// モジュールの最初の項目にだけ
+    Module,
+    /// 項目ごと
+    Item,
+    /// 項目ごとの対応表を末尾にまとめる (本文には何も足さない)
+    Trailing,
+}
+
+impl Density {
+    pub fn parse(s: &str) -> Option<Density> {
+        match s {
+            "module" => Some(Density::Module),
+            "item" => Some(Density::Item),
+            "trailing" => Some(Density::Trailing),
+            _ => None,
+        }
+    }
+}
+
+/// 末尾に付ける対応表の見出し
+pub(crate) const TABLE_MARKER: &str = "// ===== source map =====";
+
+/// Trailing のときに一旦本文に置く印
+const TRAILING_MARK: &str = "//@ ";
+
+/// `// library::ds::segtree:40` (Trailing なら後で表にする印)
+pub(crate) fn comment(path: &[String], line: usize, density: Density) -> String {
+    let prefix = if density == Density::Trailing { TRAILING_MARK } else { "// " };
+    format!("{prefix}{}:{line}", path.join("::"))
+}
+
+fn walk(items: &mut Vec<Item>, path: &mut Vec<String>, density: Density, skip_root: bool) {
+    let mut out = Vec::with_capacity(items.len());
+    let mut first = true;
+    for mut it in std::mem::take(items) {
+        if let Item::Mod(ItemMod { ident, content: Some((_, inner)), .. }) = &mut it {
+            path.push(ident.to_string());
+            walk(inner, path, density, skip_root);
+            path.pop();
+            out.push(it);
+            continue;
+        }
+        // 根のコード (re-export など) は束ねるときに作ったもので、元のファイルが無い
+        let real = !(path.is_empty() || skip_root && path.len() == 1) && it.span().source_text().is_some();
+        if real && (first || density != Density::Module) {
+            out.push(verbatim::placeholder(&comment(path, it.span().start().line, density)));
+            first = false;
+        }
+        out.push(it);
+    }
+    *items = out;
+}
+
+/// 束ねたライブラリの項目の前に元の位置を置く。skip_root なら根 (lib.rs を読まないライブラリ) には付けない
+pub(crate) fn annotate(f: &mut File, density: Density, skip_root: bool) {
+    walk(&mut f.items, &mut Vec::new(), density, skip_root);
+}
+
+/// Trailing の印を本文から外して、`// 312: library::ds::segtree:40` の表を末尾に付ける。
+/// 行番号は後で先頭に付ける skip 行の分ずらす
+pub(crate) fn trailing(src: &str, skip: usize) -> String {
+    let mut body = Vec::new();
+    let mut map = Vec::new();
+    let mut pending = Vec::new();
+    for line in src.lines() {
+        if let Some(origin) = line.trim_start().strip_prefix(TRAILING_MARK) {
+            pending.push(origin.to_string());
+            continue;
+        }
+        body.push(line);
+        // 印の次の行が項目の 1 行目
+        for origin in pending.drain(..) {
+            map.push(format!("// {}: {origin}", body.len() + skip));
+        }
+    }
+    if map.is_empty() {
+        return src.to_string();
+    }
+    format!("{}\n\n{TABLE_MARKER}\n{}\n", body.join("\n"), map.join("\n"))
+}
@@ -0,0 +1,314 @@
+//! コメントを残す書き出し (--keep-comments)
+//!
+//! syn / prettyplease を通すと `//` のコメントが消えるので、各ファイルの元のソースをそのまま繋ぐ。
+//! 落とす項目 (テスト・無効な feature・束ねた子の `mod x;`) は span の範囲ごと消し、
+//! `crate::` は字句の位置に `<name>::` を差し込む。
+//! ファイルを書き換える処理 (tree_shake・macro_export の付け替え) を通ったファイルはそこでコメントが消えている。
+use std::collections::BTreeSet;
+
+use proc_macro2::{LineColumn, TokenStream, TokenTree};
+use syn::{spanned::Spanned, visit::Visit, Attribute, File, ImplItem, Item, ItemMod, TraitItem};
+
+use crate::{attribution, doc_fence, item_attrs, rewrite_crate_paths, source_map::{self, Density}, strip, verbatim, Module, Options};
+
+/// LineColumn → バイト位置
+pub(crate) struct Offsets<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Offsets<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0).chain(src.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        Offsets { src, line_starts }
+    }
+    pub(crate) fn of(&self, p: LineColumn) -> Option<usize> {
+        let start = *self.line_starts.get(p.line.checked_sub(1)?)?;
+        // column は文字単位
+        let line = &self.src[start..];
+        Some(start + line.char_indices().nth(p.column).map_or(line.len(), |(i, _)| i))
+    }
+}
+
+/// 項目 (と impl / trait の中の項目) の span の範囲
+#[derive(Default)]
+struct Units {
+    /// 削る側のファイルで残った項目の終わり (cfg を評価して属性が消えると始まりはずれる)
+    kept: BTreeSet<(usize, usize)>,
+    /// 元のファイルで、残らなかった項目の範囲
+    removed: Vec<(LineColumn, LineColumn)>,
+    collecting: bool,
+}
+
+fn key(p: LineColumn) -> (usize, usize) {
+    (p.line, p.column)
+}
+
+impl Units {
+    /// 残っていれば中も見る
+    fn unit(&mut self, span: proc_macro2::Span) -> bool {
+        let (s, e) = (span.start(), span.end());
+        if self.collecting {
+            self.kept.insert(key(e));
+            return true;
+        }
+        if self.kept.contains(&key(e)) {
+            true
+        } else {
+            self.removed.push((s, e));
+            false
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Units {
+    fn visit_item(&mut self, i: &'ast Item) {
+        if self.unit(i.span()) { syn::visit::visit_item(self, i); }
+    }
+    fn visit_impl_item(&mut self, i: &'ast ImplItem) {
+        if self.unit(i.span()) { syn::visit::visit_impl_item(self, i); }
+    }
+    fn visit_trait_item(&mut self, i: &'ast TraitItem) {
+        if self.unit(i.span()) { syn::visit::visit_trait_item(self, i); }
+    }
+}
+
+/// 書き換え: (始まり, 終わり, 置き換える文字列)。挿入は始まり = 終わり
+type Edit = (usize, usize, String);
+
+/// 字句の上で `crate::` の後ろに `<name>::` を、埋め込んだクレートの `itertools::` の前に `crate::` を入れる
+fn path_edits(ts: TokenStream, name: &str, opts: &Options, off: &Offsets, out: &mut Vec<Edit>) {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let is_colon = |i: usize| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    for (i, t) in tokens.iter().enumerate() {
+        match t {
+            TokenTree::Group(g) => path_edits(g.stream(), name, opts, off, out),
+            TokenTree::Ident(id) if is_colon(i + 1) && is_colon(i + 2) && !(i > 0 && is_colon(i - 1)) => {
+                if id == "crate" {
+                    if let Some(at) = off.of(tokens[i + 2].span().end()) {
+                        out.push((at, at, format!("{name}::")));
+                    }
+                } else if opts.vendor.contains(&id.to_string())
+                    && let Some(at) = off.of(id.span().start())
+                {
+                    out.push((at, at, "crate::".to_string()));
+                }
+            }
+            // `::either::Either` は `crate::either::Either`
+            TokenTree::Ident(id) if i >= 2 && is_colon(i - 1) && is_colon(i - 2) && opts.vendor.contains(&id.to_string())
+                && !matches!(i.checked_sub(3).map(|j| &tokens[j]), Some(TokenTree::Ident(_) | TokenTree::Group(_))) =>
+            {
+                if let Some(at) = off.of(tokens[i - 2].span().start()) {
+                    out.push((at, at, "crate".to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `///` の中のコードブロック (フェンスの行も) の行の範囲。行末の改行まで
+fn doctest_lines(src: &str) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut fenced = false;
+    let mut pos = 0;
+    for line in src.split_inclusive('\n') {
+        let t = line.trim_start();
+        match t.strip_prefix("///").filter(|_| !t.starts_with("////")) {
+            Some(doc) => {
+                let fence = doc_fence(doc);
+                if fence || fenced {
+                    out.push((pos, pos + line.len()));
+                }
+                fenced ^= fence;
+            }
+            None => fenced = false,
+        }
+        pos += line.len();
+    }
+    out
+}
+
+/// `#![no_std]` などクレートの根にしか書けない内側の属性か
+fn crate_level(a: &Attribute) -> bool {
+    const CRATE_ONLY: &[&str] = &[
+        "no_std", "no_main", "no_builtins", "crate_name", "crate_type", "feature",
+        "recursion_limit", "type_length_limit", "windows_subsystem",
+    ];
+    CRATE_ONLY.iter().any(|n| a.path().is_ident(n))
+}
+
+/// 1 ファイル分のソースを、落とすものを落として書き換える。syn で読めなければ None。
+/// marks があれば残った項目の前に元の位置のコメントを置く
+fn own_source(src: &str, m: &Module, root_name: &str, opts: &Options, marks: Option<(&[String], Density)>) -> Option<String> {
+    let orig: File = syn::parse_file(src).ok()?;
+    let mut stripped = orig.clone();
+    strip(&mut stripped, opts);
+
+    let mut units = Units { collecting: true, ..Default::default() };
+    units.visit_file(&stripped);
+    units.collecting = false;
+    units.visit_file(&orig);
+
+    let off = Offsets::new(src);
+    let mut edits: Vec<Edit> = Vec::new();
+    let remove = |s: LineColumn, e: LineColumn, edits: &mut Vec<Edit>| {
+        if let (Some(s), Some(e)) = (off.of(s), off.of(e)) {
+            // 消した後に空行が残らないよう、行末の改行まで
+            let e = if src[e..].starts_with('\n') { e + 1 } else { e };
+            edits.push((s, e, String::new()));
+        }
+    };
+    for (s, e) in std::mem::take(&mut units.removed) {
+        remove(s, e, &mut edits);
+    }
+    // 内側の属性と `//!` は `pub mod x {` の直後に来るのでそのまま残す。クレートにしか付けられないものだけ落とす
+    // ライセンス表記は `//!` も `//` にして残す
+    let header = attribution(src, &opts.attribution_markers);
+    let header_len = header.as_ref().map_or(0, |(len, _)| *len);
+    let dropped = |a: &&Attribute| crate_level(a) || (opts.strip_docs && a.path().is_ident("doc"));
+    for a in orig.attrs.iter().filter(dropped).filter(|a| off.of(a.span().start()).is_some_and(|s| s >= header_len)) {
+        remove(a.span().start(), a.span().end(), &mut edits);
+    }
+    if let Some((len, text)) = header {
+        edits.push((0, src[..len].trim_end().len(), text));
+    }
+    for it in &orig.items {
+        if let Item::Mod(ItemMod { content: None, ident, .. }) = it
+            && m.children.contains_key(&ident.to_string())
+        {
+            remove(it.span().start(), it.span().end(), &mut edits);
+        }
+        if opts.strip_docs {
+            // 項目の外側の doc だけ (中の doc は残る)
+            for a in item_attrs(it).iter().filter(|a| a.path().is_ident("doc")) {
+                remove(a.span().start(), a.span().end(), &mut edits);
+            }
+        }
+    }
+    if opts.widen_visibility {
+        // 元のソースの `pub(crate)` などの範囲を `pub` に置き換える
+        struct Restricted(Vec<(LineColumn, LineColumn)>);
+        impl<'ast> Visit<'ast> for Restricted {
+            fn visit_visibility(&mut self, v: &'ast syn::Visibility) {
+                if let syn::Visibility::Restricted(r) = v
+                    && !r.path.is_ident("self")
+                {
+                    self.0.push((v.span().start(), v.span().end()));
+                }
+            }
+            fn visit_item_use(&mut self, _: &'ast syn::ItemUse) {}
+        }
+        let mut r = Restricted(Vec::new());
+        r.visit_file(&orig);
+        for (s, e) in r.0 {
+            if let (Some(s), Some(e)) = (off.of(s), off.of(e)) {
+                edits.push((s, e, "pub".to_string()));
+            }
+        }
+    }
+    if opts.strip_doctests && !opts.strip_docs {
+        for (s, e) in doctest_lines(src) {
+            edits.push((s, e, String::new()));
+        }
+    }
+    if let Some((path, density)) = marks {
+        let kept = orig.items.iter().filter(|it| units.kept.contains(&key(it.span().end())));
+        let kept = kept.filter(|it| !matches!(it, Item::Mod(ItemMod { content: None, ident, .. }) if m.children.contains_key(&ident.to_string())));
+        let n = if density == Density::Module { 1 } else { usize::MAX };
+        for it in kept.take(n) {
+            let start = it.span().start();
+            if let Some(at) = off.of(start) {
+                let mark = source_map::comment(path, start.line, density);
+                edits.push((at, at, format!("{mark}\n{}", " ".repeat(start.column))));
+            }
+        }
+    }
+    let ts: TokenStream = src.parse().ok()?;
+    let mut inserts = Vec::new();
+    path_edits(ts, root_name, opts, &off, &mut inserts);
+    // 消す範囲の中への挿入は要らない
+    inserts.retain(|(at, _, _)| !edits.iter().any(|(s, e, _)| s < at && at < e));
+    edits.extend(inserts);
+
+    edits.sort_by_key(|(s, e, _)| (*s, *e));
+    let mut out = String::with_capacity(src.len());
+    let mut pos = 0;
+    for (s, e, text) in edits {
+        // 重なった範囲 (消す項目の中の消す項目) は外側で消えている
+        if s < pos { continue; }
+        out.push_str(&src[pos..s]);
+        out.push_str(&text);
+        pos = e;
+    }
+    out.push_str(&src[pos..]);
+    Some(out.trim_matches('\n').to_string())
+}
+
+/// 読めないファイルは普段どおり syn を通して書く (このファイルのコメントは消える)
+fn pretty_source(src: &str, m: &Module, root_name: &str, opts: &Options) -> Option<String> {
+    let mut f = verbatim::parse_file(src).ok()?;
+    strip(&mut f, opts);
+    f.attrs.retain(|a| !crate_level(a));
+    f.items = Module::strip_decls(&f, &m.children);
+    rewrite_crate_paths(&mut f, root_name, &opts.vendor);
+    let texts = verbatim::take(&mut f);
+    Some(verbatim::restore(&prettyplease::unparse(&f), &texts, root_name))
+}
+
+/// 複数行の文字列リテラルの 2 行目以降の行番号 (字下げすると中身が変わる)
+fn literal_lines(ts: TokenStream, out: &mut BTreeSet<usize>) {
+    for t in ts {
+        match t {
+            TokenTree::Literal(l) => {
+                let (s, e) = (l.span().start().line, l.span().end().line);
+                out.extend(s + 1..=e);
+            }
+            TokenTree::Group(g) => literal_lines(g.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+fn indent(src: &str, depth: usize, out: &mut String) {
+    let mut keep = BTreeSet::new();
+    if let Ok(ts) = src.parse::<TokenStream>() {
+        literal_lines(ts, &mut keep);
+    }
+    let pad = "    ".repeat(depth);
+    for (i, line) in src.lines().enumerate() {
+        if !line.is_empty() && !keep.contains(&(i + 1)) {
+            out.push_str(&pad);
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn render_module(m: &Module, name: Option<&str>, path: &mut Vec<String>, root_name: &str, opts: &Options, out: &mut String) {
+    let depth = path.len();
+    if let Some(n) = name {
+        indent(&format!("pub mod {n} {{"), depth, out);
+        path.push(n.to_string());
+    }
+    if let Some(src) = &m.code {
+        // 根のコード (re-export など) は束ねるときに作ったもので、元のファイルが無い
+        let marks = opts.source_map.filter(|_| path.len() > 1 || opts.vendor.contains(root_name)).map(|d| (path.as_slice(), d));
+        let own = own_source(src, m, root_name, opts, marks).or_else(|| pretty_source(src, m, root_name, opts)).unwrap_or_default();
+        indent(&own, path.len(), out);
+    }
+    for (n, c) in &m.children {
+        render_module(c, Some(n), path, root_name, opts, out);
+    }
+    if name.is_some() {
+        path.pop();
+        indent("}", depth, out);
+    }
+}
+
+/// モジュール木を、各ファイルのコメントを残したまま 1 つのソースにする
+pub(crate) fn render(root_mod: &Module, name: &str, opts: &Options) -> String {
+    let mut out = String::new();
+    render_module(root_mod, None, &mut Vec::new(), name, opts, &mut out);
+    out
+}
@@ -0,0 +1,60 @@
+//! --stats: 束ねるのにかかった時間と、入れたものの数を標準エラーに出す
+//!
+//! ライブラリが育つにつれて遅くなっていないかを見るためのもの。
+//! 読むのはスレッドに分けているので、parse はスレッドごとの時間の合計 (resolve の経過時間より長いこともある)。
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use syn::Item;
+
+use crate::Module;
+
+static PARSE_NANOS: AtomicU64 = AtomicU64::new(0);
+static PARSED_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// 1 回の束ねの前に数え直す
+pub(crate) fn reset() {
+    PARSE_NANOS.store(0, Ordering::Relaxed);
+    PARSED_FILES.store(0, Ordering::Relaxed);
+}
+
+/// ライブラリのファイルを 1 つ読んだ
+pub(crate) fn parsed(d: Duration) {
+    PARSE_NANOS.fetch_add(d.as_nanos() as u64, Ordering::Relaxed);
+    PARSED_FILES.fetch_add(1, Ordering::Relaxed);
+}
+
+fn modules(m: &Module) -> usize {
+    usize::from(m.code.is_some()) + m.children.values().map(modules).sum::<usize>()
+}
+
+/// mod の中まで数えた項目 (mod 自体は数えない)
+fn items(items: &[Item]) -> usize {
+    items.iter().map(|it| match it {
+        Item::Mod(m) => m.content.as_ref().map_or(0, |(_, inner)| self::items(inner)),
+        _ => 1,
+    }).sum()
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.1}ms", d.as_secs_f64() * 1000.0)
+}
+
+/// trees は束ねたモジュール木、lib は束ねたライブラリの部分、out は出力のすべて
+pub(crate) fn print(resolve: Duration, render: Duration, trees: &[&Module], lib: &str, out: &str) {
+    let parse = Duration::from_nanos(PARSE_NANOS.load(Ordering::Relaxed));
+    let files = PARSED_FILES.load(Ordering::Relaxed);
+    let items = syn::parse_file(lib).map_or_else(|_| "?".to_string(), |f| items(&f.items).to_string());
+    eprintln!("[stats] parse    {:>9}  ({files} files, summed over threads)", ms(parse));
+    eprintln!("[stats] resolve  {:>9}  (including parse)", ms(resolve));
+    eprintln!("[stats] render   {:>9}", ms(render));
+    eprintln!(
+        "[stats] {} modules, {items} items, {} bytes / {} lines out ({} bytes of library)",
+        trees.iter().map(|m| modules(m)).sum::<usize>(),
+        out.len(),
+        out.lines().count(),
+        lib.len()
+    );
+}
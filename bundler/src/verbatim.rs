@@ -0,0 +1,196 @@
+//! syn が読めない・prettyplease が書けない項目を、元のソースのまま通す
+//!
+//! 読めない (新しい構文など) 項目や prettyplease が panic する項目 (`safe fn` など) は、
+//! その項目だけ `__bundler_verbatim! { "元のソース" }` に置き換えて他の処理を通し、
+//! 最後に書き出すときに元のソースへ戻す。ファイル全体を TokenStream::to_string() にするよりずっと読める。
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{spanned::Spanned, File, Item, ItemMod};
+
+const MARKER: &str = "__bundler_verbatim";
+
+/// 元のソースを持たせた置き換え用の項目
+pub(crate) fn placeholder(text: &str) -> Item {
+    let marker = quote::format_ident!("{MARKER}");
+    syn::parse_quote!(#marker! { #text })
+}
+
+fn text_of(it: &Item) -> Option<String> {
+    let Item::Macro(m) = it else { return None };
+    if !m.mac.path.is_ident(MARKER) { return None; }
+    syn::parse2::<syn::LitStr>(m.mac.tokens.clone()).ok().map(|l| l.value())
+}
+
+/// span の元のソース。2 行目以降は 1 行目の桁の分だけ字下げを外す
+fn source_of(span: Span) -> Option<String> {
+    let text = span.source_text()?;
+    let col = span.start().column;
+    let mut lines = text.lines();
+    let mut out = lines.next()?.to_string();
+    for l in lines {
+        let strip = l.len() - l.trim_start_matches(' ').len();
+        out.push('\n');
+        out.push_str(&l[strip.min(col)..]);
+    }
+    Some(out)
+}
+
+/// `const X: T = { … } + 1;` のように、`{ … }` の後も `;` まで続く項目か
+fn continues_past_brace(cur: &[TokenTree]) -> bool {
+    let kw = cur.iter().find_map(|t| match t {
+        TokenTree::Ident(i) if i != "pub" && i != "unsafe" => Some(i.to_string()),
+        _ => None,
+    });
+    let has_eq = cur.iter().any(|t| matches!(t, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Alone));
+    match kw.as_deref() {
+        Some("use") => true,
+        Some("const" | "static" | "type" | "let") => has_eq,
+        _ => false,
+    }
+}
+
+/// トップレベルのトークン列を項目ごとに分ける (`;` か、項目を閉じる `{ … }` まで)
+fn split_items(ts: TokenStream) -> Vec<Vec<TokenTree>> {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let mut out = Vec::new();
+    let mut cur = Vec::new();
+    let mut i = 0;
+    let is_punct = |i: usize, c: char| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == c);
+    while i < tokens.len() {
+        // `#![…]` はモジュールに入れると置けないので落とす (to_tokens も内側の属性は出さない)
+        if cur.is_empty() && is_punct(i, '#') && is_punct(i + 1, '!')
+            && matches!(tokens.get(i + 2), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket)
+        {
+            i += 3;
+            continue;
+        }
+        let t = tokens[i].clone();
+        i += 1;
+        let ends = match &t {
+            TokenTree::Punct(p) => p.as_char() == ';',
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                cur.push(t.clone());
+                if continues_past_brace(&cur) { continue; }
+                if is_punct(i, ';') {
+                    cur.push(tokens[i].clone());
+                    i += 1;
+                }
+                out.push(std::mem::take(&mut cur));
+                continue;
+            }
+            _ => false,
+        };
+        cur.push(t);
+        if ends { out.push(std::mem::take(&mut cur)); }
+    }
+    if !cur.is_empty() { out.push(cur); }
+    out
+}
+
+/// parse_file と同じだが、読めない項目は置き換えて残りを読む。字句から読めないときだけ Err
+pub(crate) fn parse_file(src: &str) -> syn::Result<File> {
+    let err = match syn::parse_file(src) {
+        Ok(f) => return Ok(f),
+        Err(e) => e,
+    };
+    let Ok(ts) = src.parse::<TokenStream>() else { return Err(err) };
+    let mut items = Vec::new();
+    for chunk in split_items(ts) {
+        let ts: TokenStream = chunk.iter().cloned().collect();
+        match syn::parse2::<Item>(ts.clone()) {
+            Ok(it) => items.push(it),
+            Err(_) => {
+                let span = chunk.first().zip(chunk.last()).and_then(|(a, b)| a.span().join(b.span()));
+                let text = span.and_then(source_of).unwrap_or_else(|| ts.to_string());
+                // 同じファイルを何度か読むので警告は 1 度だけ
+                thread_local!(static WARNED: std::cell::RefCell<std::collections::BTreeSet<String>> = Default::default());
+                if WARNED.with(|w| w.borrow_mut().insert(text.clone())) {
+                    eprintln!("warning: kept an item verbatim (syn cannot parse it): {}", text.lines().next().unwrap_or(""));
+                }
+                items.push(placeholder(&text));
+            }
+        }
+    }
+    Ok(File { shebang: None, attrs: Vec::new(), items })
+}
+
+/// prettyplease が panic する項目を置き換える
+fn guard(items: &mut [Item]) {
+    for it in items.iter_mut() {
+        if let Item::Mod(ItemMod { content: Some((_, inner)), .. }) = it {
+            guard(inner);
+            continue;
+        }
+        let f = File { shebang: None, attrs: Vec::new(), items: vec![it.clone()] };
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let ok = std::panic::catch_unwind(|| prettyplease::unparse(&f)).is_ok();
+        std::panic::set_hook(hook);
+        if !ok {
+            let text = source_of(it.span()).unwrap_or_else(|| quote!(#it).to_string());
+            *it = placeholder(&text);
+        }
+    }
+}
+
+/// prettyplease::unparse の panic しない版。置き換えた項目は置き換えたまま (途中のコードに使う)
+pub(crate) fn unparse(f: &File) -> String {
+    let mut f = f.clone();
+    guard(&mut f.items);
+    prettyplease::unparse(&f)
+}
+
+/// 置き換えた項目を `__bundler_verbatim!(番号);` にして元のソースを集める
+pub(crate) fn take(f: &mut File) -> Vec<String> {
+    fn walk(items: &mut [Item], out: &mut Vec<String>) {
+        for it in items.iter_mut() {
+            if let Item::Mod(ItemMod { content: Some((_, inner)), .. }) = it {
+                walk(inner, out);
+            } else if let Some(text) = text_of(it) {
+                let n = proc_macro2::Literal::usize_unsuffixed(out.len());
+                let marker = quote::format_ident!("{MARKER}");
+                *it = syn::parse_quote!(#marker!(#n););
+                out.push(text);
+            }
+        }
+    }
+    guard(&mut f.items);
+    let mut out = Vec::new();
+    walk(&mut f.items, &mut out);
+    out
+}
+
+/// 書き出したものの `__bundler_verbatim!(番号);` を元のソースに戻す。
+/// 元のソースは書き換えていないので、ライブラリの中の `crate::` はここで `crate::<name>::` にする
+pub(crate) fn restore(out: &str, texts: &[String], name: &str) -> String {
+    if texts.is_empty() { return out.to_string(); }
+    let mut res = String::with_capacity(out.len());
+    let mut rest = out;
+    while let Some(pos) = rest.find(MARKER) {
+        res.push_str(&rest[..pos]);
+        let after = &rest[pos + MARKER.len()..];
+        // `!(3);` / `! (3) ;` のどちらの書き方でも
+        let Some(end) = after.find(';') else {
+            res.push_str(&rest[pos..]);
+            return res;
+        };
+        let digits: String = after[..end].chars().skip_while(|c| !c.is_ascii_digit()).take_while(char::is_ascii_digit).collect();
+        let Some(text) = digits.parse::<usize>().ok().and_then(|n| texts.get(n)) else {
+            res.push_str(MARKER);
+            rest = after;
+            continue;
+        };
+        // 置いた行の字下げに合わせる
+        let indent: String = res.rsplit('\n').next().unwrap_or("").chars().take_while(|c| *c == ' ').collect();
+        let text = text.replace("crate::", &format!("crate::{name}::"));
+        res.push_str(&text.replace('\n', &format!("\n{indent}")));
+        rest = &after[end + 1..];
+        // minify で 1 行に詰めたところに `//` のコメントを戻したら、後ろを行の外に出す
+        if text.lines().last().is_some_and(|l| l.trim_start().starts_with("//")) && !rest.starts_with('\n') {
+            res.push('\n');
+            res.push_str(&indent);
+        }
+    }
+    res.push_str(rest);
+    res
+}
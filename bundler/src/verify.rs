@@ -0,0 +1,87 @@
+//! 束ねたソースを一時的な cargo プロジェクトに置いて `cargo check` する (--verify)
+//!
+//! 可視性・マクロ・足りないモジュールのような束ね方の誤りは、ジャッジに出すまで分からない。手元でコンパイラに通しておく。
+//! 解答のある Cargo.toml の [dependencies] (proconio など) はそのまま持っていく (束ねたライブラリそのものは除く)。
+use std::{env, fs, path::{Path, PathBuf}, process::Command};
+
+use anyhow::{bail, Context, Result};
+use toml::{Table, Value};
+
+use crate::edition::Edition;
+
+/// dir から上へ辿って最初の Cargo.toml を読んだものと、その置き場所
+fn manifest_near(dir: &Path) -> Option<(Table, PathBuf)> {
+    let manifest = dir.ancestors().map(|d| d.join("Cargo.toml")).find(|p| p.is_file())?;
+    let table = fs::read_to_string(&manifest).ok()?.parse::<Table>().ok()?;
+    Some((table, manifest.parent()?.to_path_buf()))
+}
+
+/// 束ねたものを置く使い捨てプロジェクトの Cargo.toml。
+///
+/// dir (解答のあるところ) から上へ辿って最初の Cargo.toml の [dependencies] を持っていく (path 依存はそのディレクトリからの絶対パスにする)。
+/// ただし束ねたはずのライブラリ (lib_roots) への path 依存は外す。残すと束ね漏れた `use library::…` が通ってしまう。
+/// edition が None なら元の Cargo.toml のもの、bin があれば src/main.rs の代わりに `submit` という名前の bin にする
+pub fn scratch_manifest(dir: Option<&Path>, lib_roots: &[PathBuf], edition: Option<Edition>, bin: Option<&Path>) -> String {
+    let (orig, base) = dir.and_then(manifest_near).unwrap_or_default();
+    let libs: Vec<PathBuf> = lib_roots.iter()
+        .flat_map(|src| [src.clone(), src.parent().filter(|p| p.join("Cargo.toml").is_file()).unwrap_or(src).to_path_buf()])
+        .filter_map(|p| fs::canonicalize(p).ok())
+        .collect();
+    let mut deps = orig.get("dependencies").and_then(Value::as_table).cloned().unwrap_or_default();
+    deps.retain(|_, d| {
+        let path = d.get("path").and_then(Value::as_str).and_then(|p| fs::canonicalize(base.join(p)).ok());
+        !path.is_some_and(|p| libs.contains(&p))
+    });
+    for (_, dep) in deps.iter_mut() {
+        if let Value::Table(t) = dep
+            && let Some(Value::String(p)) = t.get_mut("path")
+        {
+            *p = base.join(&*p).display().to_string();
+        }
+    }
+    let edition = match edition {
+        Some(e) => Value::from(e.as_str()),
+        None => orig.get("package").and_then(|p| p.get("edition")).cloned().unwrap_or(Value::from("2021")),
+    };
+
+    let mut manifest = Table::new();
+    manifest.insert("package".into(), Value::Table(Table::from_iter([
+        ("name".to_string(), Value::from("bundled")),
+        ("version".to_string(), Value::from("0.1.0")),
+        ("edition".to_string(), edition),
+    ])));
+    manifest.insert("dependencies".into(), Value::Table(deps));
+    if let Some(bin) = bin {
+        manifest.insert("bin".into(), Value::Array(vec![Value::Table(Table::from_iter([
+            ("name".to_string(), Value::from("submit")),
+            ("path".to_string(), Value::from(bin.display().to_string())),
+        ]))]));
+    }
+    // 親のディレクトリに workspace があっても巻き込まれないように
+    manifest.insert("workspace".into(), Value::Table(Table::new()));
+    toml::to_string(&manifest).unwrap_or_default()
+}
+
+/// src を 1 つの bin として cargo check する。target_dir が解答のあるディレクトリなら、そこの依存も使う
+pub(crate) fn cargo_check(src: &str, target_dir: Option<&Path>, lib_roots: &[PathBuf], edition: Edition) -> Result<()> {
+    let dir = env::temp_dir().join(format!("bundler-verify-{}", std::process::id()));
+    fs::create_dir_all(&dir).with_context(|| format!("create {:?}", dir))?;
+    fs::write(dir.join("Cargo.toml"), scratch_manifest(target_dir, lib_roots, Some(edition), None))?;
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(dir.join("src").join("main.rs"), src)?;
+
+    // 依存のビルドは毎回やり直さなくて済むよう、target は pid に依らない場所に置く
+    let out = Command::new("cargo")
+        .args(["check", "--quiet", "--message-format=short", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", env::temp_dir().join("bundler-verify-target"))
+        .output()
+        .context("failed to run cargo")?;
+    let _ = fs::remove_dir_all(&dir);
+    if !out.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&out.stderr));
+        bail!("the bundled source does not pass cargo check (--verify)");
+    }
+    eprintln!("cargo check ok");
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! 束ねた出力の回帰テスト
+use std::path::PathBuf;
+
+use bundler::{bundle, notebook, verify, Options};
+
+/// ライブラリを使わない解答は、ライブラリを設定していない (空のパス・無いパス) ときもそのまま出る
+#[test]
+fn solution_without_library() {
+    let src = "use std::io::*;\nfn main() { let v = vec![1]; println!(\"{:?}\", v); }\n";
+    for root in ["", "/nonexistent/library/src"] {
+        let roots = [("library".to_string(), PathBuf::from(root))];
+        let out = bundle(&roots, src, &Options::default()).unwrap();
+        assert!(out.ends_with(src), "{out}");
+    }
+}
+
+/// tests/fixtures/<name>/src を library として
+fn fixture(name: &str) -> [(String, PathBuf); 1] {
+    [("library".to_string(), PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name).join("src"))]
+}
+
+/// 束ねた出力をコンパイルして走らせ、標準出力を返す
+fn compile_and_run(out: &str, tag: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("bundler-test-{tag}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.rs"), out).unwrap();
+    let rustc = std::process::Command::new("rustc")
+        .args(["--edition", "2021", "-A", "warnings", "-o"])
+        .arg(dir.join("main"))
+        .arg(dir.join("main.rs"))
+        .output()
+        .unwrap();
+    assert!(rustc.status.success(), "{out}\n{}", String::from_utf8_lossy(&rustc.stderr));
+    let run = std::process::Command::new(dir.join("main")).output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&run.stdout).into_owned()
+}
+
+/// fixtures/resolve で src を束ねて走らせた出力
+fn bundle_and_run(src: &str, tag: &str) -> String {
+    let out = bundle(&fixture("resolve"), src, &Options::default()).unwrap();
+    compile_and_run(&out, tag)
+}
+
+/// --minify-idents で短くした名前が、同じ名前の別のもの (`std::mem::swap`) を巻き込まずにコンパイルできる
+#[test]
+fn minified_idents_compile() {
+    let src = "use library::pair::sorted;\nfn main() { println!(\"{:?}\", sorted(5, 3)); }\n";
+    let opts = Options { minify_idents: true, ..Options::default() };
+    let out = bundle(&fixture("minify"), src, &opts).unwrap();
+    assert!(out.contains("std::mem::swap"), "{out}");
+    assert!(!out.contains("double_of"), "{out}");
+    assert_eq!(compile_and_run(&out, "minify"), "(3, 5)\n");
+}
+
+/// `use library::graph::*;` はモジュールの項目も子モジュールも持ってくる
+#[test]
+fn glob_import() {
+    let src = "use library::graph::*;\nfn main() {\n    let mut g = vec![vec![]; 3];\n    add_edge(&mut g, 0, 1);\n    println!(\"{} {:?}\", degree(&g, 0), bfs::dist(&g, 0)[1]);\n}\n";
+    assert_eq!(bundle_and_run(src, "glob"), "1 Some(1)\n");
+}
+
+/// `as` で付けた別名 (項目もモジュールも) がそのまま使える
+#[test]
+fn renamed_imports() {
+    let src = "use library::ds::fenwick::Fenwick as BIT;\nuse library::math::gcd as g;\nfn main() {\n    let mut b = BIT::new(4);\n    b.add(1, 5);\n    println!(\"{} {}\", b.sum(3), g::gcd(12, 18));\n}\n";
+    assert_eq!(bundle_and_run(src, "rename"), "5 6\n");
+}
+
+/// math/mod.rs に書いた項目 (と、そこから使う子モジュール)
+#[test]
+fn mod_rs_layout() {
+    let src = "use library::math::is_coprime;\nfn main() { println!(\"{} {}\", is_coprime(4, 9), is_coprime(4, 6)); }\n";
+    assert_eq!(bundle_and_run(src, "modrs"), "true false\n");
+}
+
+/// `super::super::` と `self::super::super::super::` の use
+#[test]
+fn chained_super() {
+    let src = "use library::math::nt::crt::lcm_pair;\nfn main() { println!(\"{:?}\", lcm_pair(4, 6)); }\n";
+    assert_eq!(bundle_and_run(src, "super"), "Pair(4, 12)\n");
+}
+
+/// `#[path = "impl/fenwick_impl.rs"] pub mod fenwick;`
+#[test]
+fn path_attribute() {
+    let src = "use library::ds::fenwick::Fenwick;\nfn main() {\n    let mut b = Fenwick::new(4);\n    b.add(0, 2);\n    b.add(3, 7);\n    println!(\"{} {}\", b.sum(3), b.sum(4));\n}\n";
+    assert_eq!(bundle_and_run(src, "path"), "2 9\n");
+}
+
+/// use を書かずに式と型の中で `crate::util::…` を使うモジュール
+#[test]
+fn expression_and_type_paths() {
+    let src = "use library::math::pow::{pow_mod, with_square};\nfn main() { println!(\"{} {:?}\", pow_mod(2, 10), with_square(3)); }\n";
+    assert_eq!(bundle_and_run(src, "exprpath"), "1024 Pair(3, 9)\n");
+}
+
+/// 束ねた出力をもう一度束ねても同じものになる
+#[test]
+fn rebundle_is_idempotent() {
+    let src = "use library::ds::fenwick::Fenwick as BIT;\nuse library::graph::*;\nuse library::math::nt::crt::lcm_pair;\nuse library::math::pow::with_square;\nfn main() {\n    let b = BIT::new(1);\n    let g: Vec<Vec<usize>> = vec![vec![]];\n    println!(\"{} {} {:?} {:?}\", b.sum(1), degree(&g, 0), lcm_pair(2, 3), with_square(2));\n}\n";
+    let once = bundle(&fixture("resolve"), src, &Options::default()).unwrap();
+    let twice = bundle(&fixture("resolve"), &once, &Options::default()).unwrap();
+    assert_eq!(once, twice);
+    assert_eq!(compile_and_run(&twice, "rebundle"), "0 0 Pair(2, 6) Pair(2, 4)\n");
+}
+
+/// 子モジュールの宣言しかないファイル (`ds.rs` = `mod fenwick;`) はノートの節にならない
+#[test]
+fn notebook_skips_declaration_only_modules() {
+    let dir = std::env::temp_dir().join(format!("bundler-test-notebook-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src/ds")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "pub mod ds;\n").unwrap();
+    std::fs::write(dir.join("src/ds.rs"), "pub mod fenwick;\n").unwrap();
+    std::fs::write(dir.join("src/ds/fenwick.rs"), "pub struct Fenwick(pub Vec<i64>);\n").unwrap();
+    let out = dir.join("note.md");
+    notebook::export(notebook::Format::Markdown, notebook::Paper::A4, &dir.join("src"), &out, &[]).unwrap();
+    let note = std::fs::read_to_string(&out).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(note.contains("## library::ds::fenwick"), "{note}");
+    assert!(!note.contains("## library::ds\n"), "{note}");
+}
+
+/// --verify の使い捨てプロジェクトには、束ねたライブラリへの path 依存を持っていかない (ほかの依存は残す)
+#[test]
+fn scratch_manifest_drops_library() {
+    let dir = std::env::temp_dir().join(format!("bundler-test-scratch-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("library/src")).unwrap();
+    std::fs::create_dir_all(dir.join("contest/src")).unwrap();
+    std::fs::write(dir.join("library/Cargo.toml"), "[package]\nname = \"library\"\nversion = \"0.1.0\"\n").unwrap();
+    std::fs::write(
+        dir.join("contest/Cargo.toml"),
+        "[package]\nname = \"contest\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[dependencies]\nlibrary = { path = \"../library\" }\nproconio = \"0.4\"\n",
+    ).unwrap();
+    let manifest = verify::scratch_manifest(Some(&dir.join("contest/src")), &[dir.join("library/src")], None, None);
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(!manifest.contains("library"), "{manifest}");
+    assert!(manifest.contains("proconio") && manifest.contains("2018"), "{manifest}");
+}
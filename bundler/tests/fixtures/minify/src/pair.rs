@@ -0,0 +1,27 @@
+/// 小さい方を先に
+pub fn sorted(mut a: u64, mut b: u64) -> (u64, u64) {
+    if a > b {
+        swap(&mut a, &mut b);
+    }
+    (a, double_of(a) / 2, b).into_pair()
+}
+
+fn swap(a: &mut u64, b: &mut u64) {
+    std::mem::swap(a, b);
+}
+
+fn double_of(x: u64) -> u64 {
+    x * FACTOR
+}
+
+const FACTOR: u64 = 2;
+
+trait IntoPair {
+    fn into_pair(self) -> (u64, u64);
+}
+
+impl IntoPair for (u64, u64, u64) {
+    fn into_pair(self) -> (u64, u64) {
+        (self.1, self.2)
+    }
+}
@@ -0,0 +1,2 @@
+#[path = "impl/fenwick_impl.rs"]
+pub mod fenwick;
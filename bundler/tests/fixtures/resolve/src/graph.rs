@@ -0,0 +1,10 @@
+pub mod bfs;
+
+pub fn add_edge(g: &mut [Vec<usize>], a: usize, b: usize) {
+    g[a].push(b);
+    g[b].push(a);
+}
+
+pub fn degree(g: &[Vec<usize>], v: usize) -> usize {
+    g[v].len()
+}
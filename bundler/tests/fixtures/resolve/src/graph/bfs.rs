@@ -0,0 +1,16 @@
+use std::collections::VecDeque;
+
+pub fn dist(g: &[Vec<usize>], s: usize) -> Vec<Option<usize>> {
+    let mut d = vec![None; g.len()];
+    d[s] = Some(0);
+    let mut q = VecDeque::from([s]);
+    while let Some(v) = q.pop_front() {
+        for &u in &g[v] {
+            if d[u].is_none() {
+                d[u] = d[v].map(|x| x + 1);
+                q.push_back(u);
+            }
+        }
+    }
+    d
+}
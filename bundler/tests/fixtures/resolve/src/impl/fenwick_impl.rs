@@ -0,0 +1,25 @@
+pub struct Fenwick(Vec<i64>);
+
+impl Fenwick {
+    pub fn new(n: usize) -> Self {
+        Fenwick(vec![0; n + 1])
+    }
+
+    pub fn add(&mut self, i: usize, x: i64) {
+        let mut i = i + 1;
+        while i < self.0.len() {
+            self.0[i] += x;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// [0, i) の和
+    pub fn sum(&self, mut i: usize) -> i64 {
+        let mut s = 0;
+        while i > 0 {
+            s += self.0[i];
+            i -= i & i.wrapping_neg();
+        }
+        s
+    }
+}
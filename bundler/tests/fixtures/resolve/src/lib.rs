@@ -0,0 +1,4 @@
+pub mod ds;
+pub mod graph;
+pub mod math;
+pub mod util;
@@ -0,0 +1,7 @@
+pub mod gcd;
+pub mod nt;
+pub mod pow;
+
+pub fn is_coprime(a: u64, b: u64) -> bool {
+    gcd::gcd(a, b) == 1
+}
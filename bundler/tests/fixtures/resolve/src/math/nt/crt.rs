@@ -0,0 +1,7 @@
+use super::super::gcd::gcd;
+use self::super::super::super::util::Pair;
+
+/// (a, lcm(a, b))
+pub fn lcm_pair(a: u64, b: u64) -> Pair {
+    Pair(a, a / gcd(a, b) * b)
+}
@@ -0,0 +1,16 @@
+pub fn pow_mod(mut a: u64, mut e: u64) -> u64 {
+    let mut r = 1;
+    a %= crate::util::MOD;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = r * a % crate::util::MOD;
+        }
+        a = a * a % crate::util::MOD;
+        e >>= 1;
+    }
+    r
+}
+
+pub fn with_square(a: u64) -> crate::util::Pair {
+    crate::util::Pair(a, pow_mod(a, 2))
+}
@@ -0,0 +1,4 @@
+pub const MOD: u64 = 998244353;
+
+#[derive(Debug)]
+pub struct Pair(pub u64, pub u64);
@@ -0,0 +1,279 @@
+//! AHC (ヒューリスティックコンテスト) 用: 公式のローカルテスタで複数 seed を走らせて得点を集める
+//!
+//! 公式ツールの配置 (tools/in/0000.txt, tools/src/bin/{gen,vis,tester}.rs) をそのまま使う。
+//! 出力は out/0000.txt に書く。
+//!
+//! ```toml
+//! [ahc]
+//! tools = "tools"      # 公式ツールを展開した場所
+//! objective = "max"    # 得点を大きくする問題か小さくする問題か
+//! visualizer = "https://img.atcoder.jp/ahc001/xxxx.html"  # ウェブ版 (任意)
+//! ```
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+    thread,
+    time::Instant,
+};
+
+use serde::Deserialize;
+
+use crate::{Project, SubmitError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    #[default]
+    Max,
+    Min,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub tools: PathBuf,
+    pub objective: Objective,
+    /// 問題文にあるウェブ版ビジュアライザの URL
+    pub visualizer: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { tools: PathBuf::from("tools"), objective: Objective::Max, visualizer: None }
+    }
+}
+
+/// 1 seed 分の結果
+#[derive(Debug, Clone)]
+pub struct SeedResult {
+    pub seed: u64,
+    /// 得点が読めなければ None (WA / RE など)
+    pub score: Option<i64>,
+    pub secs: f64,
+    /// 失敗したときのテスタ・解答の stderr の最後の方
+    pub error: Option<String>,
+}
+
+fn input_of(cfg: &Config, seed: u64) -> PathBuf {
+    cfg.tools.join("in").join(format!("{seed:04}.txt"))
+}
+
+pub fn output_of(seed: u64) -> PathBuf {
+    PathBuf::from("out").join(format!("{seed:04}.txt"))
+}
+
+fn tool_bin(cfg: &Config, name: &str) -> PathBuf {
+    cfg.tools.join("target").join("release").join(name)
+}
+
+/// tester があればインタラクティブ
+fn interactive(cfg: &Config) -> bool {
+    cfg.tools.join("src").join("bin").join("tester.rs").is_file()
+}
+
+/// 公式ツールを release でビルドし、足りない入力を gen で作る
+pub fn prepare(cfg: &Config, seeds: &[u64]) -> Result<(), SubmitError> {
+    if !cfg.tools.join("Cargo.toml").is_file() {
+        eprintln!("{} has no Cargo.toml; unpack the official local tester there (or set [ahc] tools)", cfg.tools.display());
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    let status = Command::new("cargo")
+        .args(["build", "--release", "--quiet", "--manifest-path"])
+        .arg(cfg.tools.join("Cargo.toml"))
+        .status()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    if !status.success() {
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    let missing: Vec<u64> = seeds.iter().copied().filter(|&s| !input_of(cfg, s).is_file()).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    // gen は seeds.txt の i 行目の seed から in/{i:04}.txt を作るので 0 から通しで作る
+    let upto = *missing.iter().max().unwrap();
+    let list: String = (0..=upto).map(|s| format!("{s}\n")).collect();
+    let seeds_file = cfg.tools.join("seeds.txt");
+    fs::write(&seeds_file, list).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let generator = fs::canonicalize(tool_bin(cfg, "gen")).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let status = Command::new(generator)
+        .arg("seeds.txt")
+        .current_dir(&cfg.tools)
+        .stdout(crate::child_stdout())
+        .status()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    if !status.success() {
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    Ok(())
+}
+
+/// `Score = 1234` の数字
+pub fn parse_score(text: &str) -> Option<i64> {
+    text.lines()
+        .filter(|l| l.contains("Score") || l.contains("score"))
+        .find_map(|l| l.rsplit(['=', ':']).next()?.trim().parse().ok())
+}
+
+fn tail(bytes: &[u8]) -> String {
+    let s = String::from_utf8_lossy(bytes);
+    let lines: Vec<&str> = s.lines().collect();
+    lines[lines.len().saturating_sub(5)..].join("\n")
+}
+
+/// 1 seed を走らせて採点する (ビルド・prepare は済ませておくこと)
+pub fn run_seed(project: &Project, cfg: &Config, seed: u64) -> SeedResult {
+    let fail = |secs, e: String| SeedResult { seed, score: None, secs, error: Some(e) };
+    let input = input_of(cfg, seed);
+    let output = output_of(seed);
+    let _ = fs::create_dir_all("out");
+    let (Ok(stdin), Ok(stdout)) = (File::open(&input), File::create(&output)) else {
+        return fail(0.0, format!("cannot open {} / {}", input.display(), output.display()));
+    };
+
+    let start = Instant::now();
+    let mut cmd = if interactive(cfg) {
+        // tester <解答のコマンド> < in > out。得点は tester の stderr に出る
+        let mut c = Command::new(tool_bin(cfg, "tester"));
+        c.args(["sh", "-c", &project.run]);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.args(["-c", &project.run]);
+        c
+    };
+    project.limits.apply(&mut cmd);
+    let out = cmd.stdin(stdin).stdout(stdout).stderr(Stdio::piped()).output();
+    let secs = start.elapsed().as_secs_f64();
+    let out = match out {
+        Ok(o) => o,
+        Err(e) => return fail(secs, e.to_string()),
+    };
+    if !out.status.success() {
+        return fail(secs, format!("{}\n{}", out.status, tail(&out.stderr)));
+    }
+    if interactive(cfg) {
+        return match parse_score(&String::from_utf8_lossy(&out.stderr)) {
+            Some(score) => SeedResult { seed, score: Some(score), secs, error: None },
+            None => fail(secs, tail(&out.stderr)),
+        };
+    }
+
+    // vis <in> <out> が得点を出す
+    let vis = Command::new(tool_bin(cfg, "vis")).arg(&input).arg(&output).output();
+    match vis {
+        Ok(v) => {
+            let text = format!("{}{}", String::from_utf8_lossy(&v.stdout), String::from_utf8_lossy(&v.stderr));
+            match parse_score(&text) {
+                Some(score) if score > 0 || v.status.success() => SeedResult { seed, score: Some(score), secs, error: None },
+                _ => fail(secs, tail(text.as_bytes())),
+            }
+        }
+        Err(e) => fail(secs, format!("failed to run vis: {e}")),
+    }
+}
+
+/// seed を jobs 並列で走らせる (結果は seeds の順)。時間いっぱい回す解答は並列にすると
+/// 1 seed あたりの CPU が減るので、本番に近い得点が欲しければ jobs を小さくする
+pub fn run_seeds(project: &Project, cfg: &Config, seeds: &[u64], jobs: usize) -> Result<Vec<SeedResult>, SubmitError> {
+    project.build()?;
+    prepare(cfg, seeds)?;
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(seeds.len()));
+    thread::scope(|sc| {
+        for _ in 0..jobs.max(1) {
+            sc.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&seed) = seeds.get(i) else { break };
+                let r = run_seed(project, cfg, seed);
+                results.lock().unwrap().push((i, r));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    Ok(results.into_iter().map(|(_, r)| r).collect())
+}
+
+/// 既定の並列数
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// `0..100` / `5` (= 0..5) / `3,7,9`
+pub fn parse_seeds(s: &str) -> Option<Vec<u64>> {
+    if let Some((a, b)) = s.split_once("..") {
+        return Some((a.parse().ok()?..b.parse().ok()?).collect());
+    }
+    if s.contains(',') {
+        return s.split(',').map(|x| x.trim().parse().ok()).collect();
+    }
+    Some((0..s.parse().ok()?).collect())
+}
+
+/// 合計・平均 (得点が読めた seed のみ) と失敗数
+pub fn summary(results: &[SeedResult]) -> (i64, f64, usize) {
+    let scores: Vec<i64> = results.iter().filter_map(|r| r.score).collect();
+    let total: i64 = scores.iter().sum();
+    let avg = if scores.is_empty() { 0.0 } else { total as f64 / scores.len() as f64 };
+    (total, avg, results.len() - scores.len())
+}
+
+/// ブラウザ・既定のアプリで開く (WSL / Linux / macOS)
+pub fn open(target: &str) -> Result<(), SubmitError> {
+    for opener in ["wslview", "xdg-open", "open"] {
+        if Command::new(opener).arg(target).stdout(Stdio::null()).stderr(Stdio::null()).spawn().is_ok() {
+            return Ok(());
+        }
+    }
+    eprintln!("no opener found (wslview / xdg-open / open); open {target} yourself");
+    Err(SubmitError::CommandExecuteFailed)
+}
+
+/// ローカルの vis で seed の可視化を作り、できたファイルを返す。
+/// vis はカレントディレクトリに vis.html (問題によっては out.svg など) を書くので out/vis-<seed>/ で走らせる
+pub fn visualize_local(cfg: &Config, seed: u64) -> Result<PathBuf, SubmitError> {
+    let io_err = |_| SubmitError::CommandExecuteFailed;
+    let input = fs::canonicalize(input_of(cfg, seed)).map_err(io_err)?;
+    let output = fs::canonicalize(output_of(seed)).map_err(|_| {
+        eprintln!("{} not found; run acsub score first", output_of(seed).display());
+        SubmitError::CommandExecuteFailed
+    })?;
+    let vis = fs::canonicalize(tool_bin(cfg, "vis")).map_err(io_err)?;
+    let dir = PathBuf::from("out").join(format!("vis-{seed:04}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).map_err(io_err)?;
+    let status = Command::new(vis)
+        .arg(&input)
+        .arg(&output)
+        .current_dir(&dir)
+        .stdout(crate::child_stdout())
+        .status()
+        .map_err(io_err)?;
+    if !status.success() {
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    let made = fs::read_dir(&dir).map_err(io_err)?.flatten().map(|e| e.path()).find(|p| {
+        p.extension().is_some_and(|x| x == "html" || x == "svg" || x == "png" || x == "gif")
+    });
+    made.ok_or_else(|| {
+        eprintln!("vis did not write an html/svg/png file");
+        SubmitError::CommandExecuteFailed
+    })
+}
+
+/// ウェブ版の URL (seed 指定付き)。出力は貼り付けられるようにクリップボードへ入れる
+pub fn visualize_web(cfg: &Config, seed: u64) -> Result<String, SubmitError> {
+    let Some(base) = &cfg.visualizer else {
+        eprintln!("set [ahc] visualizer in ac_config.toml to the web visualizer url");
+        return Err(SubmitError::CommandExecuteFailed);
+    };
+    let output = fs::read_to_string(output_of(seed)).map_err(|_| {
+        eprintln!("{} not found; run acsub score first", output_of(seed).display());
+        SubmitError::CommandExecuteFailed
+    })?;
+    crate::copy_to_clipboard(&output)?;
+    let sep = if base.contains('?') { '&' } else { '?' };
+    Ok(format!("{base}{sep}lang=ja&seed={seed}"))
+}
@@ -0,0 +1,100 @@
+//! 短い問題 ID (`a`) を ac_config.toml の規則で展開する
+//!
+//! ```toml
+//! [alias]
+//! pattern = "{contest_id}_{alias}"  # 既定。contest_id はジャッジの URL から取る
+//! contest_id = "abc350"             # URL に contest が無いとき
+//!
+//! [alias.map]                       # 個別の対応 (yukicoder の番号など)。pattern より優先
+//! a = "1234"
+//! ```
+use std::{collections::BTreeMap, fs::read_to_string, path::Path};
+
+use serde::Deserialize;
+
+use crate::project::contest_of;
+
+const DEFAULT_PATTERN: &str = "{contest_id}_{alias}";
+
+#[derive(Deserialize, Default)]
+struct AliasConfig {
+    pattern: Option<String>,
+    contest_id: Option<String>,
+    #[serde(default)]
+    map: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    alias: AliasConfig,
+    url: Option<String>,
+    judge: Option<String>,
+    #[serde(default)]
+    judges: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    pattern: String,
+    contest_id: Option<String>,
+    map: BTreeMap<String, String>,
+}
+
+/// カレントディレクトリの ac_config.toml から読む。無ければ何も展開しない
+pub fn rules() -> Rules {
+    let Some(cfg) = read_to_string("ac_config.toml").ok().and_then(|s| toml::from_str::<Config>(&s).ok()) else {
+        return Rules::default();
+    };
+    // 使うジャッジの URL、無ければどれかの URL から contest を取る
+    let urls = cfg.judge.as_ref().and_then(|j| cfg.judges.get(j)).into_iter()
+        .chain(cfg.judges.values())
+        .filter_map(|j| j.get("url")?.as_str().map(str::to_string))
+        .chain(cfg.url);
+    let contest_id = cfg.alias.contest_id.or_else(|| urls.filter_map(|u| contest_of(&u)).next());
+    Rules {
+        pattern: cfg.alias.pattern.unwrap_or_else(|| DEFAULT_PATTERN.to_string()),
+        contest_id,
+        map: cfg.alias.map,
+    }
+}
+
+/// 展開の対象にする短い ID (英数字 3 文字まで)
+fn is_short(id: &str) -> bool {
+    (1..=3).contains(&id.len()) && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+impl Rules {
+    /// 展開できなければそのまま返す。その名前の解答が既にあれば展開しない
+    pub fn expand(&self, id: &str) -> String {
+        if Path::new("src/bin").join(format!("{id}.rs")).is_file() {
+            return id.to_string();
+        }
+        if let Some(full) = self.map.get(id) {
+            return full.clone();
+        }
+        match &self.contest_id {
+            Some(contest) if is_short(id) && self.pattern.contains("{alias}") => {
+                self.pattern.replace("{contest_id}", contest).replace("{alias}", id)
+            }
+            _ => id.to_string(),
+        }
+    }
+
+    /// 展開の逆 (表示用)。`abc350_a` → `a`、`1234` → map のキー
+    pub fn short(&self, id: &str) -> Option<String> {
+        if let Some((alias, _)) = self.map.iter().find(|(_, full)| *full == id) {
+            return Some(alias.clone());
+        }
+        let contest = self.contest_id.as_ref()?;
+        let (head, tail) = self.pattern.replace("{contest_id}", contest).split_once("{alias}")
+            .map(|(h, t)| (h.to_string(), t.to_string()))?;
+        let alias = id.strip_prefix(&head)?.strip_suffix(&tail)?;
+        is_short(alias).then(|| alias.to_string())
+    }
+}
+
+/// ac_config.toml の規則で展開する
+pub fn resolve(id: &str) -> String {
+    rules().expand(id)
+}
@@ -0,0 +1,150 @@
+use std::process::Command;
+
+use crate::constraints::{self, Constraint};
+
+/// サンプル 1 組
+pub struct Sample {
+    pub input: String,
+    pub output: String,
+}
+
+pub struct Problem {
+    pub url: String,
+    /// "A - Welcome to AtCoder"
+    pub name: String,
+    pub samples: Vec<Sample>,
+    pub time_limit_ms: Option<u64>,
+    pub memory_limit_mb: Option<u64>,
+    pub constraints: Vec<Constraint>,
+}
+
+pub struct Task {
+    pub url: String,
+    /// "A"
+    pub alphabet: String,
+    pub name: String,
+}
+
+pub struct Contest {
+    pub url: String,
+    pub name: String,
+    pub tasks: Vec<Task>,
+}
+
+/// curl でページを取ってくる
+pub fn fetch(url: &str) -> Result<String, String> {
+    let out = Command::new("curl")
+        .args(["-sfL", url])
+        .output()
+        .map_err(|e| format!("failed to run curl: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("failed to fetch {url}"));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// タグを落としてテキストだけにする
+fn strip_tags(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    unescape(&out)
+}
+
+/// `open` ... `close` の間を返す
+fn between<'a>(s: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let s = &s[s.find(open)? + open.len()..];
+    Some(&s[..s.find(close)?])
+}
+
+/// `<h3>{heading} n</h3>` の直後の `<pre>` を順に取る
+fn pres_after(html: &str, heading: &str) -> Vec<String> {
+    html.split(&format!("<h3>{heading}"))
+        .skip(1)
+        .filter_map(|sec| between(sec, "<pre>", "</pre>").or_else(|| between(sec, "<pre", "</pre>").map(|p| &p[p.find('>').map_or(0, |i| i + 1)..])))
+        .map(|p| {
+            let t = strip_tags(p);
+            let t = t.trim_start_matches(['\r', '\n']);
+            if t.ends_with('\n') { t.to_string() } else { format!("{t}\n") }
+        })
+        .collect()
+}
+
+/// `<h3>制約</h3>` の `<li>` を 1 行ずつ
+fn parse_constraints(html: &str) -> Vec<Constraint> {
+    let sec = ["<h3>制約</h3>", "<h3>Constraints</h3>"].iter()
+        .find_map(|h| between(html, h, "</ul>"));
+    let Some(sec) = sec else { return Vec::new() };
+    sec.split("<li>")
+        .skip(1)
+        .map(|li| strip_tags(li.split("</li>").next().unwrap_or(li)))
+        .flat_map(|line| constraints::parse_line(line.trim()))
+        .collect()
+}
+
+/// "2 sec" / "1024 MB" の数字
+fn leading_number(s: &str) -> Option<f64> {
+    let n: String = s.trim().chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    n.parse().ok()
+}
+
+pub fn parse_problem(url: &str, html: &str) -> Problem {
+    let name = between(html, "<title>", "</title>").map(strip_tags).unwrap_or_default();
+    // 日本語版があればそちら、無ければ英語版の見出し
+    let (mut ins, mut outs) = (pres_after(html, "入力例"), pres_after(html, "出力例"));
+    if ins.is_empty() {
+        (ins, outs) = (pres_after(html, "Sample Input"), pres_after(html, "Sample Output"));
+    }
+    let samples = ins.into_iter().zip(outs).map(|(input, output)| Sample { input, output }).collect();
+
+    let time_limit_ms = ["実行時間制限:", "Time Limit:"].iter()
+        .find_map(|k| leading_number(html.split(k).nth(1)?))
+        .map(|sec| (sec * 1000.0) as u64);
+    let memory_limit_mb = ["メモリ制限:", "Memory Limit:"].iter()
+        .find_map(|k| leading_number(html.split(k).nth(1)?))
+        .map(|mb| mb as u64);
+
+    let constraints = parse_constraints(html);
+
+    Problem { url: url.to_string(), name: name.trim().to_string(), samples, time_limit_ms, memory_limit_mb, constraints }
+}
+
+/// /contests/{id}/tasks の表から問題一覧を作る
+pub fn parse_contest(url: &str, html: &str) -> Contest {
+    let name = between(html, "<title>", "</title>").map(strip_tags).unwrap_or_default();
+    let base = url.split("/contests/").next().unwrap_or("https://atcoder.jp");
+    let body = between(html, "<tbody>", "</tbody>").unwrap_or("");
+    let tasks = body.split("<tr>").skip(1).filter_map(|row| {
+        let mut cells = row.split("<td").skip(1);
+        let first = cells.next()?;
+        let second = cells.next()?;
+        let href = between(first, "href=\"", "\"")?;
+        Some(Task {
+            url: format!("{base}{href}"),
+            alphabet: strip_tags(&first[first.find('>')? + 1..]).trim().to_string(),
+            name: strip_tags(&second[second.find('>')? + 1..]).trim().to_string(),
+        })
+    }).collect();
+    Contest { url: url.to_string(), name: name.trim().to_string(), tasks }
+}
+
+/// 問題 URL → その contest の URL
+pub fn contest_url(url: &str) -> Option<String> {
+    let (base, rest) = url.split_once("/contests/")?;
+    Some(format!("{base}/contests/{}", rest.split('/').next()?))
+}
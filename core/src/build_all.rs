@@ -0,0 +1,80 @@
+//! コンテストの全 bin をまとめてビルドする (cargo の並列ビルドに任せる)
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+};
+
+use serde_json::Value;
+
+use crate::SubmitError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatus {
+    Ok,
+    /// コンパイルエラー (最初のメッセージ)
+    Failed(String),
+    /// 依存が落ちたなどでビルドされなかった
+    NotBuilt,
+}
+
+/// cargo metadata から bin ターゲットの名前を取る
+pub fn bin_names() -> Result<Vec<String>, SubmitError> {
+    let out = Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .output()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    if !out.status.success() {
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    let meta: Value = serde_json::from_slice(&out.stdout).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let mut bins: Vec<String> = meta["packages"].as_array().into_iter().flatten()
+        .flat_map(|p| p["targets"].as_array().into_iter().flatten())
+        .filter(|t| t["kind"].as_array().is_some_and(|k| k.iter().any(|k| k == "bin")))
+        .filter_map(|t| t["name"].as_str().map(str::to_string))
+        .collect();
+    bins.sort();
+    Ok(bins)
+}
+
+/// `cargo build --bins --keep-going` を 1 回だけ走らせて bin ごとの結果を集める
+pub fn build_all() -> Result<BTreeMap<String, BuildStatus>, SubmitError> {
+    let mut status: BTreeMap<String, BuildStatus> =
+        bin_names()?.into_iter().map(|b| (b, BuildStatus::NotBuilt)).collect();
+
+    // テスト時 (cargo run --features local) と同じ成果物になるように
+    let has_local = read_to_string("Cargo.toml")
+        .ok()
+        .and_then(|s| toml::from_str::<toml::Value>(&s).ok())
+        .is_some_and(|v| v.get("features").and_then(|f| f.get("local")).is_some());
+
+    let mut cmd = Command::new("cargo");
+    cmd.args(["build", "--bins", "--keep-going", "--message-format=json"]);
+    if has_local {
+        cmd.args(["--features", "local"]);
+    }
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+
+    for line in BufReader::new(child.stdout.take().unwrap()).lines().map_while(Result::ok) {
+        let Ok(msg) = serde_json::from_str::<Value>(&line) else { continue };
+        let Some(name) = msg["target"]["name"].as_str() else { continue };
+        let Some(entry) = status.get_mut(name) else { continue };
+        match msg["reason"].as_str() {
+            Some("compiler-artifact") if *entry == BuildStatus::NotBuilt => *entry = BuildStatus::Ok,
+            Some("compiler-message")
+                if msg["message"]["level"] == "error" && !matches!(entry, BuildStatus::Failed(_)) =>
+            {
+                let text = msg["message"]["message"].as_str().unwrap_or("error").to_string();
+                *entry = BuildStatus::Failed(text);
+            }
+            _ => {}
+        }
+    }
+    let _ = child.wait();
+    Ok(status)
+}
@@ -0,0 +1,88 @@
+//! ビルドした実行ファイルの大きさとビルド設定
+use std::{env, fs, path::{Path, PathBuf}};
+
+use toml::Value;
+
+use crate::Project;
+
+pub struct BuildInfo {
+    pub binary: PathBuf,
+    pub size: u64,
+    pub profile: String,
+    pub opt_level: String,
+    pub debug_assertions: bool,
+    pub overflow_checks: bool,
+    pub debug_symbols: bool,
+}
+
+/// `--name value` の value
+fn arg_after<'a>(words: &[&'a str], name: &str) -> Option<&'a str> {
+    words.iter().position(|w| *w == name).and_then(|i| words.get(i + 1)).copied()
+}
+
+/// Cargo.toml の [profile.<name>] で上書きされた値
+fn profile_override(manifest: &Path, profile: &str, key: &str) -> Option<Value> {
+    let v: Value = toml::from_str(&fs::read_to_string(manifest).ok()?).ok()?;
+    v.get("profile")?.get(profile)?.get(key).cloned()
+}
+
+/// ELF / Mach-O のセクション名に debug_info があるか
+fn has_debug_symbols(bin: &Path) -> bool {
+    let Ok(bytes) = fs::read(bin) else { return false };
+    [b".debug_info".as_slice(), b"__debug_info".as_slice()]
+        .iter()
+        .any(|pat| bytes.windows(pat.len()).any(|w| w == *pat))
+}
+
+/// project.build のコマンドから成果物と設定を割り出す
+pub fn inspect(project: &Project) -> Option<BuildInfo> {
+    let build = project.build.as_deref()?;
+    let words: Vec<&str> = build.split_whitespace().collect();
+
+    let (binary, profile, opt_level, debug_assertions, overflow_checks) = match *words.first()? {
+        "cargo" => {
+            let release = words.contains(&"--release");
+            let profile = if release { "release" } else { "dev" };
+            let manifest = PathBuf::from(arg_after(&words, "--manifest-path").unwrap_or("Cargo.toml"));
+            let target = env::var_os("CARGO_TARGET_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| manifest.parent().unwrap_or(Path::new(".")).join("target"));
+            let bin = match arg_after(&words, "--bin") {
+                Some(b) => b.to_string(),
+                None => {
+                    let v: Value = toml::from_str(&fs::read_to_string(&manifest).ok()?).ok()?;
+                    v.get("package")?.get("name")?.as_str()?.to_string()
+                }
+            };
+            let binary = target.join(if release { "release" } else { "debug" }).join(bin);
+            let get_bool = |key: &str| {
+                profile_override(&manifest, profile, key).and_then(|v| v.as_bool()).unwrap_or(!release)
+            };
+            let opt = profile_override(&manifest, profile, "opt-level")
+                .map(|v| v.to_string().trim_matches('"').to_string())
+                .unwrap_or_else(|| if release { "3" } else { "0" }.to_string());
+            (binary, profile.to_string(), opt, get_bool("debug-assertions"), get_bool("overflow-checks"))
+        }
+        "rustc" => {
+            let binary = PathBuf::from(arg_after(&words, "-o")?);
+            let optimized = words.contains(&"-O");
+            let opt = if optimized { "2" } else { "0" }.to_string();
+            (binary, "rustc".to_string(), opt, !optimized, !optimized)
+        }
+        _ => return None,
+    };
+    let size = fs::metadata(&binary).ok()?.len();
+    let debug_symbols = has_debug_symbols(&binary);
+    Some(BuildInfo { binary, size, profile, opt_level, debug_assertions, overflow_checks, debug_symbols })
+}
+
+impl BuildInfo {
+    pub fn print(&self) {
+        let on = |b: bool| if b { "on" } else { "off" };
+        eprintln!("binary: {} ({:.1} KiB)", self.binary.display(), self.size as f64 / 1024.0);
+        eprintln!(
+            "profile: {} (opt-level={}, debug-assertions={}, overflow-checks={}, debug symbols={})",
+            self.profile, self.opt_level, on(self.debug_assertions), on(self.overflow_checks), on(self.debug_symbols),
+        );
+    }
+}
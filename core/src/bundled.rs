@@ -0,0 +1,93 @@
+//! 束ねた submit.rs そのものをビルドしてサンプルに通す
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+use crate::{bundler_config, child_stdout, library_roots, oj_test, Bundle, Project, SubmitError, TestResult};
+
+/// source から上に辿って最初の Cargo.toml
+fn manifest_of(source: &Path) -> Option<PathBuf> {
+    let source = fs::canonicalize(source).ok()?;
+    source.ancestors().skip(1).map(|d| d.join("Cargo.toml")).find(|p| p.is_file())
+}
+
+/// submit.rs の実行ファイルを作ってそのパスを返す
+fn build(project: &Project, bundle: &Bundle) -> Result<PathBuf, SubmitError> {
+    let lib_roots: Vec<PathBuf> = library_roots(project, &bundler_config())?.into_iter().map(|(_, src)| src).collect();
+    build_submit(&project.source, &bundle.path, &lib_roots)
+}
+
+/// source の属するプロジェクトの依存で submit をビルドする
+fn build_submit(source: &Path, submit: &Path, lib_roots: &[PathBuf]) -> Result<PathBuf, SubmitError> {
+    let submit = fs::canonicalize(submit).map_err(|_| SubmitError::CommandExecuteFailed)?;
+
+    let Some(manifest) = manifest_of(source) else {
+        // cargo プロジェクトでなければ rustc で直接
+        let bin = submit.with_extension("out");
+        let status = Command::new("rustc")
+            .args(["-O", "--edition", "2021", "-o"])
+            .arg(&bin)
+            .arg(&submit)
+            .stdout(child_stdout())
+            .status()
+            .map_err(|_| SubmitError::CommandExecuteFailed)?;
+        return if status.success() { Ok(bin) } else { Err(SubmitError::BundleBuildFailed) };
+    };
+
+    // 依存のビルド結果を使い回すため target ディレクトリは元と共有する
+    let target_dir = manifest.parent().unwrap().join("target");
+    let scratch = target_dir.join("acsub").join("submit");
+    fs::create_dir_all(&scratch).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    // `local` feature は付けない = ジャッジと同じ
+    let toml = bundler::verify::scratch_manifest(manifest.parent(), lib_roots, None, Some(&submit));
+    fs::write(scratch.join("Cargo.toml"), toml).map_err(|_| SubmitError::CommandExecuteFailed)?;
+
+    let status = Command::new("cargo")
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(scratch.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .stdout(child_stdout())
+        .status()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    if !status.success() {
+        return Err(SubmitError::BundleBuildFailed);
+    }
+    Ok(target_dir.join("debug").join("submit"))
+}
+
+/// 提出するファイルでサンプルを確認する
+pub fn test_bundled(project: &Project, bundle: &Bundle) -> Result<TestResult, SubmitError> {
+    let bin = build(project, bundle)?;
+    oj_test(&bin.display().to_string(), project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 束ね漏れた `use library::…` は、Cargo.toml に library の path 依存があってもビルドに落ちる
+    #[test]
+    fn unbundled_library_use_fails() {
+        let dir = std::env::temp_dir().join(format!("acsub-test-bundled-{}", std::process::id()));
+        let lib = dir.join("library");
+        let contest = dir.join("contest");
+        fs::create_dir_all(lib.join("src")).unwrap();
+        fs::create_dir_all(contest.join("src/bin")).unwrap();
+        fs::write(lib.join("Cargo.toml"), "[package]\nname = \"library\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        fs::write(lib.join("src/lib.rs"), "pub fn x() -> i32 { 1 }\n").unwrap();
+        fs::write(
+            contest.join("Cargo.toml"),
+            "[package]\nname = \"contest\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nlibrary = { path = \"../library\" }\n",
+        ).unwrap();
+        let source = contest.join("src/bin/a.rs");
+        let submit = contest.join("submit.rs");
+        fs::write(&source, "use library::x;\nfn main() { println!(\"{}\", x()); }\n").unwrap();
+        fs::write(&submit, "use library::x;\nfn main() { println!(\"{}\", x()); }\n").unwrap();
+
+        let manifest = bundler::verify::scratch_manifest(Some(&contest), &[lib.join("src")], None, Some(&submit));
+        assert!(!manifest.contains("library"), "{manifest}");
+        let result = build_submit(&source, &submit, &[lib.join("src")]);
+        assert!(matches!(result, Err(SubmitError::BundleBuildFailed)));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,179 @@
+//! 手元のテストケース置き場 (Project::test_dir) の操作
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{sandbox, Project};
+
+/// アーカイブを tmp に展開する (zip は unzip、それ以外は tar に任せる)
+fn extract(archive: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    let is_zip = archive.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    let status = if is_zip {
+        Command::new("unzip").arg("-qo").arg(archive).arg("-d").arg(dest).status()?
+    } else {
+        Command::new("tar").arg("xf").arg(archive).arg("-C").arg(dest).status()?
+    };
+    if !status.success() {
+        return Err(io::Error::other(format!("failed to extract {}", archive.display())));
+    }
+    Ok(())
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for e in fs::read_dir(dir)? {
+        let p = e?.path();
+        if p.is_dir() { walk(&p, out)?; } else { out.push(p); }
+    }
+    Ok(())
+}
+
+const IN_DIRS: &[(&str, &[&str])] = &[
+    ("in", &["out", "ans", "output"]),
+    ("input", &["output", "out", "answer"]),
+    ("inputs", &["outputs", "answers"]),
+];
+const IN_PREFIXES: &[(&str, &[&str])] = &[
+    ("input", &["output", "answer"]),
+    ("in", &["out", "ans"]),
+];
+
+/// 入力ファイルなら (ケース名, 出力ファイルの候補)
+///
+/// - `in/01.txt` ↔ `out/01.txt`
+/// - `input_01.txt` ↔ `output_01.txt`
+/// - `01.in` ↔ `01.out` / `01.ans`
+fn output_candidates(p: &Path) -> Option<(String, Vec<PathBuf>)> {
+    let file = p.file_name()?.to_str()?;
+    let stem = p.file_stem()?.to_str()?;
+    let parent = p.parent()?;
+
+    if p.extension().is_some_and(|e| e == "in") {
+        return Some((stem.to_string(), ["out", "ans"].iter().map(|x| p.with_extension(x)).collect()));
+    }
+    let dir_name = parent.file_name().and_then(|d| d.to_str()).unwrap_or("");
+    if let Some((_, outs)) = IN_DIRS.iter().find(|(d, _)| dir_name.eq_ignore_ascii_case(d)) {
+        let grand = parent.parent()?;
+        return Some((stem.to_string(), outs.iter().map(|o| grand.join(o).join(file)).collect()));
+    }
+    for (pre, outs) in IN_PREFIXES {
+        let Some(rest) = file.strip_prefix(pre) else { continue };
+        // input01.txt / input_01.txt / in-01.txt
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) { continue; }
+        let name = rest.trim_start_matches(['_', '-', '.']);
+        let name = name.rsplit_once('.').map_or(name, |(n, _)| n).to_string();
+        return Some((name, outs.iter().map(|o| parent.join(format!("{o}{rest}"))).collect()));
+    }
+    None
+}
+
+/// f のあるディレクトリの、展開先からの相対パスを `-` でつないだもの (`in/` などの入力のディレクトリは除く)。
+/// 別のディレクトリに同じ名前のケースがあるときに頭に付けて分ける
+fn group_prefix(root: &Path, f: &Path) -> String {
+    let mut dirs: Vec<String> = f.parent()
+        .and_then(|p| p.strip_prefix(root).ok())
+        .map(|p| p.iter().map(|c| c.to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    if dirs.last().is_some_and(|d| IN_DIRS.iter().any(|(i, _)| d.eq_ignore_ascii_case(i))) {
+        dirs.pop();
+    }
+    dirs.join("-")
+}
+
+/// アーカイブ内の入出力の組を test_dir に name.in / name.out として取り込む。
+/// 別のディレクトリに同じ名前があれば `sub-01` のようにディレクトリを頭に付ける
+pub fn import_archive(archive: &Path, test_dir: &Path) -> io::Result<usize> {
+    let tmp = env::temp_dir().join(format!("acsub-import-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&tmp);
+    extract(archive, &tmp)?;
+    let mut files = Vec::new();
+    walk(&tmp, &mut files)?;
+    files.sort();
+
+    let mut pairs = Vec::new();
+    for f in &files {
+        let Some((name, cands)) = output_candidates(f) else { continue };
+        let Some(out) = cands.into_iter().find(|c| c.is_file()) else { continue };
+        pairs.push((group_prefix(&tmp, f), name, f, out));
+    }
+    let mut seen = BTreeMap::<&str, usize>::new();
+    for (_, name, _, _) in &pairs {
+        *seen.entry(name.as_str()).or_default() += 1;
+    }
+    let mut named = BTreeSet::new();
+    let mut renamed = Vec::new();
+    for (prefix, name, f, out) in &pairs {
+        let unique = if seen[name.as_str()] > 1 && !prefix.is_empty() { format!("{prefix}-{name}") } else { name.clone() };
+        if !named.insert(unique.clone()) {
+            let _ = fs::remove_dir_all(&tmp);
+            return Err(io::Error::other(format!("{} has more than one case named {unique}", archive.display())));
+        }
+        renamed.push((unique, f, out));
+    }
+
+    fs::create_dir_all(test_dir)?;
+    for (name, f, out) in &renamed {
+        fs::copy(f, test_dir.join(format!("{name}.in")))?;
+        fs::copy(out, test_dir.join(format!("{name}.out")))?;
+    }
+    let _ = fs::remove_dir_all(&tmp);
+    if renamed.is_empty() {
+        return Err(io::Error::other(format!("no input/output pairs found in {}", archive.display())));
+    }
+    Ok(renamed.len())
+}
+
+/// 他人の generator を隔離して count 回走らせ、test_dir に gen-1.in, ... を作る。書いたファイルのパス。
+/// generator には何番目か (シードに使う) を引数で渡す
+pub fn generate(project: &Project, generator: &str, count: usize) -> Result<Vec<PathBuf>, String> {
+    let command = sandbox::isolated(generator)?;
+    let _ = fs::create_dir_all(&project.test_dir);
+    let mut written = Vec::new();
+    for i in 1..=count {
+        let mut cmd = Command::new("sh");
+        project.limits.apply(&mut cmd);
+        let out = match cmd.args(["-c", &format!("{command} {i}")]).output() {
+            Ok(o) if o.status.success() => o.stdout,
+            Ok(o) => return Err(format!("generator failed ({}):\n{}", o.status, String::from_utf8_lossy(&o.stderr))),
+            Err(e) => return Err(format!("failed to run the generator: {e}")),
+        };
+        let path = project.test_dir.join(format!("gen-{i}.in"));
+        fs::write(&path, out).map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 別のディレクトリにある同じ名前のケースは上書きし合わず、ディレクトリ名で分かれる
+    #[test]
+    fn same_names_in_subdirectories() {
+        let base = env::temp_dir().join(format!("acsub-test-cases-{}", std::process::id()));
+        let src = base.join("src");
+        for (dir, body) in [("small", "1"), ("large", "2")] {
+            for io in ["in", "out"] {
+                fs::create_dir_all(src.join(dir).join(io)).unwrap();
+                fs::write(src.join(dir).join(io).join("01.txt"), body).unwrap();
+            }
+        }
+        fs::write(src.join("02.in"), "3").unwrap();
+        fs::write(src.join("02.out"), "3").unwrap();
+        let archive = base.join("cases.tar");
+        let status = Command::new("tar").arg("cf").arg(&archive).arg("-C").arg(&src).arg(".").status().unwrap();
+        assert!(status.success());
+
+        let test_dir = base.join("test");
+        assert_eq!(import_archive(&archive, &test_dir).unwrap(), 3);
+        let mut names: Vec<String> = fs::read_dir(&test_dir).unwrap().flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        assert_eq!(names, ["02.in", "02.out", "large-01.in", "large-01.out", "small-01.in", "small-01.out"]);
+        assert_eq!(fs::read_to_string(test_dir.join("large-01.in")).unwrap(), "2");
+        let _ = fs::remove_dir_all(&base);
+    }
+}
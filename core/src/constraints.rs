@@ -0,0 +1,107 @@
+//! 問題文の制約 (`1 \leq N \leq 2 \times 10^5` など) を構造化して持つ
+//!
+//! 取ってきた制約は `.acsub/constraints/<problem id>.json` に置いておき、
+//! 雛形のコメントや gen-max の変数に使う。
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atcoder;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Constraint {
+    /// 問題文の式そのもの
+    pub text: String,
+    /// `N` `A_i` など
+    pub name: String,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// max を数値にできたとき
+    pub max_value: Option<i64>,
+}
+
+/// `2 \times 10^5` `10^{18}` `200000` `2e5` → 数値
+pub fn eval(s: &str) -> Option<i64> {
+    let s = s.replace(['{', '}', ' ', ','], "").replace("\\times", "*").replace("\\cdot", "*").replace('×', "*");
+    let mut v: i64 = 1;
+    for factor in s.split('*') {
+        let f = match factor.split_once('^') {
+            Some((b, e)) => b.parse::<i64>().ok()?.checked_pow(e.parse().ok()?)?,
+            None => factor.parse().ok()?,
+        };
+        v = v.checked_mul(f)?;
+    }
+    Some(v)
+}
+
+/// 1 行分の式を変数ごとの制約にする。`1 \leq N, M \leq 10^5` は N と M の 2 つ
+pub fn parse_line(text: &str) -> Vec<Constraint> {
+    let norm = text
+        .replace("\\leq", "≤")
+        .replace("\\le", "≤")
+        .replace("\\lt", "<")
+        .replace('≦', "≤");
+    let parts: Vec<&str> = norm.split(['≤', '<']).map(str::trim).collect();
+    let (min, names, max) = match parts.as_slice() {
+        [lo, name, hi] => (Some(*lo), *name, Some(*hi)),
+        [name, hi] => (None, *name, Some(*hi)),
+        _ => return Vec::new(),
+    };
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty() && !n.contains(char::is_whitespace))
+        .map(|name| Constraint {
+            text: text.trim().to_string(),
+            name: name.to_string(),
+            min: min.map(str::to_string),
+            max: max.map(str::to_string),
+            max_value: max.and_then(eval),
+        })
+        .collect()
+}
+
+/// gen-max に渡す変数 (名前 → 最大値)
+pub fn vars(cs: &[Constraint]) -> BTreeMap<String, i64> {
+    cs.iter().filter_map(|c| Some((c.name.clone(), c.max_value?))).collect()
+}
+
+/// 雛形の先頭に差し込むコメント
+pub fn comment_block(cs: &[Constraint]) -> String {
+    let mut texts: Vec<&str> = cs.iter().map(|c| c.text.as_str()).collect();
+    texts.dedup();
+    let mut s = "// 制約\n".to_string();
+    for t in texts {
+        s += &format!("//   {t}\n");
+    }
+    s
+}
+
+fn file(root: &Path, problem_id: &str) -> PathBuf {
+    root.join(".acsub").join("constraints").join(format!("{problem_id}.json"))
+}
+
+pub fn save(root: &Path, problem_id: &str, cs: &[Constraint]) -> std::io::Result<()> {
+    let path = file(root, problem_id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(cs).unwrap_or_default())
+}
+
+pub fn load(root: &Path, problem_id: &str) -> Option<Vec<Constraint>> {
+    serde_json::from_str(&fs::read_to_string(file(root, problem_id)).ok()?).ok()
+}
+
+/// 保存済みのものがあればそれを、無ければ問題文から取って保存する
+pub fn load_or_fetch(root: &Path, problem_id: &str, url: &str) -> Vec<Constraint> {
+    if let Some(cs) = load(root, problem_id) {
+        return cs;
+    }
+    let Ok(html) = atcoder::fetch(url) else { return Vec::new() };
+    let cs = atcoder::parse_problem(url, &html).constraints;
+    if !cs.is_empty() {
+        let _ = save(root, problem_id, &cs);
+    }
+    cs
+}
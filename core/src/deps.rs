@@ -0,0 +1,71 @@
+//! 提出先で使えないクレートを使っていないかの確認
+use std::{collections::BTreeSet, fs::read_to_string, path::Path};
+
+use syn::visit::Visit;
+use toml::Value;
+
+use crate::{bundler_config, judge::is_atcoder_crate, Project};
+
+/// bundler が提出ファイルに埋め込むクレート (bundler.toml の [libraries] と [vendor]、無ければ root_ident)
+fn bundled_crates() -> BTreeSet<String> {
+    let cfg = bundler_config();
+    cfg.libraries.keys().chain(cfg.vendor.keys()).cloned().chain([cfg.root_ident().to_string()]).collect()
+}
+
+/// ソースの `use` / パスの先頭に出てくる識別子
+fn leading_idents(src: &str) -> BTreeSet<String> {
+    struct V(BTreeSet<String>);
+    impl<'ast> Visit<'ast> for V {
+        fn visit_item_use(&mut self, i: &'ast syn::ItemUse) {
+            if let syn::UseTree::Path(p) = &i.tree {
+                self.0.insert(p.ident.to_string());
+            }
+        }
+        fn visit_item_extern_crate(&mut self, i: &'ast syn::ItemExternCrate) {
+            self.0.insert(i.ident.to_string());
+        }
+        fn visit_path(&mut self, p: &'ast syn::Path) {
+            if p.segments.len() > 1 {
+                self.0.insert(p.segments[0].ident.to_string());
+            }
+            syn::visit::visit_path(self, p);
+        }
+    }
+    let Ok(f) = syn::parse_file(src) else { return BTreeSet::new() };
+    let mut v = V(BTreeSet::new());
+    v.visit_file(&f);
+    v.0
+}
+
+/// source から上に辿って見つかった Cargo.toml の [dependencies] (use で書く名前)
+fn cargo_deps(source: &Path) -> BTreeSet<String> {
+    let Some(manifest) = source.ancestors().skip(1).map(|d| d.join("Cargo.toml")).find(|p| p.is_file()) else {
+        return BTreeSet::new();
+    };
+    let Some(v) = read_to_string(manifest).ok().and_then(|s| toml::from_str::<Value>(&s).ok()) else {
+        return BTreeSet::new();
+    };
+    v.get("dependencies")
+        .and_then(Value::as_table)
+        .map(|t| t.keys().map(|k| k.replace('-', "_")).collect())
+        .unwrap_or_default()
+}
+
+/// ジャッジにも無く、bundler も埋め込まない依存クレート
+pub fn foreign_crates(project: &Project) -> Vec<String> {
+    let Ok(src) = read_to_string(&project.source) else { return Vec::new() };
+    let deps = cargo_deps(&project.source);
+    let on_atcoder = project.url.contains("atcoder.jp");
+    let bundled = bundled_crates();
+    leading_idents(&src)
+        .into_iter()
+        .filter(|i| deps.contains(i))
+        .filter(|i| !bundled.contains(i))
+        .filter(|i| !(on_atcoder && is_atcoder_crate(i)))
+        .collect()
+}
+
+/// bundler が束ねる library (use で書く名前が name のもの) を使っているか
+pub fn uses_library(project: &Project, name: &str) -> bool {
+    read_to_string(&project.source).is_ok_and(|src| leading_idents(&src).contains(name))
+}
@@ -0,0 +1,135 @@
+//! 制約の簡単な記述から最大ケースを作る
+//!
+//! 1 行が出力の 1 行に対応し、空白区切りの各トークンは次のどれか:
+//!
+//! - `n=2e5`     変数 n を定義してその値を出す
+//! - `1..1e9`    範囲内の乱数 (端は数値か変数)
+//! - `1..1e9*n`  乱数を n 個、空白区切りで
+//! - `[a-z]*n`   文字クラスから n 文字の文字列
+//! - `42` / `n`  定数・変数の値
+//!
+//! 行頭に `*m:` を付けるとその行を m 回繰り返す (辺のリストなど)。
+//! 行は改行か `;` で区切る。問題文の制約の上限 (N, A_i など) は最初から変数として使える。
+use std::{collections::BTreeMap, fs, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::Project;
+
+/// xorshift64
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn from_time() -> Rng {
+        let t = SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |d| d.as_nanos() as u64);
+        Rng::new(t)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// [lo, hi]
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        if hi <= lo { return lo; }
+        let w = (hi - lo) as u64 + 1;
+        lo + (self.next_u64() % w) as i64
+    }
+}
+
+/// `2e5` `200000` `-1` → 数値、それ以外は変数名として引く
+fn value(s: &str, vars: &BTreeMap<String, i64>) -> Result<i64, String> {
+    if let Some(v) = vars.get(s) {
+        return Ok(*v);
+    }
+    if let Some((m, e)) = s.split_once(['e', 'E']) {
+        let m: f64 = m.parse().map_err(|_| format!("bad number `{s}`"))?;
+        let e: i32 = e.parse().map_err(|_| format!("bad number `{s}`"))?;
+        return Ok((m * 10f64.powi(e)).round() as i64);
+    }
+    s.parse().map_err(|_| format!("unknown value `{s}`"))
+}
+
+/// `[a-z0-9]` → 使う文字
+fn char_class(s: &str) -> Result<Vec<char>, String> {
+    let inner: Vec<char> = s.trim_start_matches('[').trim_end_matches(']').chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < inner.len() {
+        if i + 2 < inner.len() && inner[i + 1] == '-' {
+            out.extend(inner[i]..=inner[i + 2]);
+            i += 3;
+        } else {
+            out.push(inner[i]);
+            i += 1;
+        }
+    }
+    if out.is_empty() { return Err(format!("empty character class `{s}`")); }
+    Ok(out)
+}
+
+fn token(t: &str, vars: &mut BTreeMap<String, i64>, rng: &mut Rng) -> Result<String, String> {
+    if let Some((name, v)) = t.split_once('=') {
+        let v = value(v, vars)?;
+        vars.insert(name.to_string(), v);
+        return Ok(v.to_string());
+    }
+    let (body, count) = match t.rsplit_once('*') {
+        Some((b, c)) => (b, Some(value(c, vars)?)),
+        None => (t, None),
+    };
+    if body.starts_with('[') {
+        let cs = char_class(body)?;
+        let n = count.unwrap_or(1);
+        return Ok((0..n).map(|_| cs[rng.next_u64() as usize % cs.len()]).collect());
+    }
+    let one = |rng: &mut Rng| -> Result<String, String> {
+        match body.split_once("..") {
+            Some((lo, hi)) => Ok(rng.range(value(lo, vars)?, value(hi, vars)?).to_string()),
+            None => Ok(value(body, vars)?.to_string()),
+        }
+    };
+    match count {
+        Some(n) => Ok((0..n).map(|_| one(rng)).collect::<Result<Vec<_>, _>>()?.join(" ")),
+        None => one(rng),
+    }
+}
+
+/// spec から 1 ケース分の入力を作る。vars は事前に分かっている変数 (制約など)
+pub fn generate(spec: &str, vars: &BTreeMap<String, i64>, rng: &mut Rng) -> Result<String, String> {
+    let mut vars = vars.clone();
+    let mut out = String::new();
+    for line in spec.split(['\n', ';']).map(str::trim).filter(|l| !l.is_empty()) {
+        let (times, line) = match line.strip_prefix('*').and_then(|l| l.split_once(':')) {
+            Some((n, rest)) => (value(n.trim(), &vars)?, rest.trim()),
+            None => (1, line),
+        };
+        for _ in 0..times {
+            let toks = line
+                .split_whitespace()
+                .map(|t| token(t, &mut vars, rng))
+                .collect::<Result<Vec<_>, _>>()?;
+            out += &toks.join(" ");
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// spec で count 個のケースを作り、test_dir に max-1.in, max-2.in, ... として書く。書いたファイルのパス
+pub fn write_cases(project: &Project, spec: &str, vars: &BTreeMap<String, i64>, count: usize, rng: &mut Rng) -> Result<Vec<PathBuf>, String> {
+    let _ = fs::create_dir_all(&project.test_dir);
+    let mut written = Vec::new();
+    for i in 1..=count {
+        let input = generate(spec, vars, rng).map_err(|e| format!("bad spec: {e}"))?;
+        let path = project.test_dir.join(format!("max-{i}.in"));
+        fs::write(&path, input).map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
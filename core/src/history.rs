@@ -0,0 +1,68 @@
+//! 提出 (クリップボードへコピー) したソースの記録
+//!
+//! `.acsub/history.jsonl` に 1 行ずつ追記する。ハッシュはコピーした内容そのものの FNV-1a の先頭 8 桁で、
+//! ジャッジ側のコードと照らし合わせるのに使う。
+use std::{fs::{self, OpenOptions}, io::Write, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{hash, Project};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// UNIX 秒
+    pub time: u64,
+    pub url: String,
+    pub source: String,
+    pub hash: String,
+    pub bytes: usize,
+}
+
+fn history_file() -> PathBuf {
+    PathBuf::from(".acsub").join("history.jsonl")
+}
+
+/// 提出したものと同じ内容かを見る短いハッシュ。前後の空白の違いは無視する
+pub fn short_hash(src: &str) -> String {
+    hash::hex(src.trim().as_bytes())[..8].to_string()
+}
+
+pub fn record(project: &Project, src: &str) -> Entry {
+    let entry = Entry {
+        time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        url: project.url.clone(),
+        source: project.source.display().to_string(),
+        hash: short_hash(src),
+        bytes: src.len(),
+    };
+    let _ = fs::create_dir_all(".acsub");
+    if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(history_file()) {
+        let _ = writeln!(f, "{}", serde_json::to_string(&entry).unwrap_or_default());
+    }
+    entry
+}
+
+/// 古い順
+pub fn load() -> Vec<Entry> {
+    fs::read_to_string(history_file())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// UNIX 秒 → `2024-04-20 12:34:56 UTC`
+pub fn format_time(t: u64) -> String {
+    let (days, secs) = ((t / 86400) as i64, t % 86400);
+    // civil_from_days (Howard Hinnant)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02} {:02}:{:02}:{:02} UTC", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,400 @@
+//! acsub の中身 (サンプル取得・テスト・バンドル・コピー)
+//!
+//! CLI 以外 (bot やダッシュボード) からも同じ流れを使えるようにライブラリにしている。
+use std::{fs::File, io::{BufRead, BufReader, Write}, path::PathBuf, process::{Command, Stdio}, sync::atomic::{AtomicBool, Ordering}, time::Instant};
+
+pub use project::Project;
+
+pub mod ahc;
+pub mod alias;
+pub mod atcoder;
+pub mod build_all;
+pub mod build_info;
+pub mod bundled;
+pub mod cases;
+pub mod constraints;
+pub mod deps;
+pub mod genmax;
+pub mod history;
+pub mod judge;
+pub mod library;
+pub mod project;
+pub mod repro;
+pub mod runtimes;
+pub mod samples;
+pub mod sandbox;
+pub mod scores;
+pub mod share;
+pub mod size;
+pub mod status;
+pub mod stress;
+pub mod style;
+mod test_cache;
+pub mod tune;
+pub mod variants;
+pub mod workspace;
+
+/// 内容のハッシュは束ねた出力の bundle-hash と同じものを使う
+pub use bundler::hash;
+
+#[derive(Debug)]
+pub enum SubmitError {
+    SampleFailed,
+    CommandExecuteFailed,
+    UnsupportedLanguage(String),
+    /// ジャッジに無く、束ねられもしないクレート
+    ForeignCrates(Vec<String>),
+    /// bundler が束ねられなかった
+    BundleFailed(String),
+    /// use library しているのにライブラリの場所が設定されていない
+    LibraryNotConfigured,
+    /// 設定されたライブラリの場所がおかしい
+    LibraryNotFound(String),
+    /// 束ねた submit.rs がコンパイルできない
+    BundleBuildFailed,
+    /// 取ってきたサンプルが壊れている
+    BrokenSamples(Vec<String>),
+    /// 指定したケースが test_dir に無い
+    CaseNotFound(String),
+}
+
+impl SubmitError {
+    pub fn message(&self) -> String {
+        match self {
+            SubmitError::CommandExecuteFailed => "Something Wrong.".to_string(),
+            SubmitError::SampleFailed => "Wrong Answer, or Runtime Error occured.".to_string(),
+            SubmitError::UnsupportedLanguage(lang) => format!("language {lang} is not supported."),
+            SubmitError::ForeignCrates(crates) => format!(
+                "these crates are neither available on the judge nor bundled: {}",
+                crates.join(", ")
+            ),
+            SubmitError::BundleFailed(e) => format!("bundler failed: {e}"),
+            SubmitError::LibraryNotConfigured => format!(
+                "this solution uses `library` but its location is not configured.\n  set ACSUB_LIBRARY, `library = \"...\"` in ac_config.toml or {}",
+                library::global_config().map_or("~/.config/cp-assists/config.toml".to_string(), |p| p.display().to_string()),
+            ),
+            SubmitError::LibraryNotFound(e) => format!("library not found: {e}"),
+            SubmitError::BundleBuildFailed => "bundled submit.rs failed to compile.".to_string(),
+            SubmitError::CaseNotFound(case) => format!("case {case} not found."),
+            SubmitError::BrokenSamples(problems) => format!("downloaded samples look broken:\n  {}", problems.join("\n  ")),
+        }
+    }
+}
+
+/// サンプルテストの結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestResult {
+    Passed,
+    Failed,
+}
+
+/// 束ねたソースと保存先
+#[derive(Debug)]
+pub struct Bundle {
+    pub path: PathBuf,
+    pub source: String,
+    /// history::short_hash
+    pub hash: String,
+}
+
+/// stdout を JSON などに使う呼び出し元のため、子プロセスの出力を stderr へ回す
+static QUIET_STDOUT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet_stdout(quiet: bool) {
+    QUIET_STDOUT.store(quiet, Ordering::Relaxed);
+}
+
+pub(crate) fn child_stdout() -> Stdio {
+    if QUIET_STDOUT.load(Ordering::Relaxed) {
+        Stdio::from(std::io::stderr())
+    } else {
+        Stdio::inherit()
+    }
+}
+
+fn utf8_to_utf16le_bytes(src: &str) -> Vec<u8> {
+    let mut v = Vec::with_capacity(2 + src.len() * 2 + 2);
+    v.extend_from_slice(&[0xFF, 0xFE]); // BOM
+    for u in src.encode_utf16() {
+        v.push((u & 0x00FF) as u8);
+        v.push((u >> 8) as u8);
+    }
+    v.extend_from_slice(&[0x00, 0x00]); // NUL 2Byte
+    v
+}
+
+/// 提出前のチェックの設定
+#[derive(Debug, Clone)]
+pub struct SubmitOptions {
+    /// サンプルを確認する (--with-no-test で false)
+    pub check: bool,
+    /// 束ねた submit.rs でもサンプルを確認する
+    pub test_bundled: bool,
+    /// 手元にあってもサンプルを取り直す
+    pub refresh: bool,
+    /// 変更が無くてもテストをやり直す
+    pub force: bool,
+    /// ビルド後に実行ファイルの大きさとビルド設定を表示する
+    pub build_info: bool,
+}
+
+impl Default for SubmitOptions {
+    fn default() -> Self {
+        SubmitOptions { check: true, test_bundled: true, refresh: false, force: false, build_info: false }
+    }
+}
+
+/// サンプルを oj t で確認する
+pub fn run_tests(project: &Project) -> Result<TestResult, SubmitError> {
+    project.build()?;
+    oj_test(&project.run, project)
+}
+
+/// 変更が無ければ前回の結果を使う run_tests (opts.force で必ず実行)
+pub fn run_tests_cached(project: &Project, opts: &SubmitOptions) -> Result<TestResult, SubmitError> {
+    let key = test_cache::key(project)?;
+    if !opts.force && let Some(hit) = test_cache::lookup(project, &key) {
+        for line in hit.log.lines() {
+            print_child_line(&format!("(cached) {line}"));
+        }
+        print_child_line("(cached) nothing changed since the last run; use --force to rerun");
+        return Ok(hit.result);
+    }
+    project.build()?;
+    if opts.build_info && let Some(info) = build_info::inspect(project) {
+        info.print();
+    }
+    let (result, log) = oj_test_logged(&project.run, project)?;
+    test_cache::store(project, &key, result, &log);
+    let times = runtimes::parse(&log, &project.test_dir);
+    for (case, prev, now) in runtimes::regressions(project, &times) {
+        eprintln!("{}", style::yellow(&format!("slower than the last passing run: {case} {prev:.3}s -> {now:.3}s")));
+    }
+    if result == TestResult::Passed {
+        runtimes::store(project, times);
+    }
+    Ok(result)
+}
+
+/// 子プロセスの 1 行を child_stdout と同じ行き先に出す
+fn print_child_line(line: &str) {
+    let line = style::oj_line(line);
+    if QUIET_STDOUT.load(Ordering::Relaxed) {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+/// command を project の test_dir のサンプルで oj t する (ビルドは済ませておくこと)
+pub(crate) fn oj_test(command: &str, project: &Project) -> Result<TestResult, SubmitError> {
+    oj_test_logged(command, project).map(|(r, _)| r)
+}
+
+/// oj t の出力を流しつつ、キャッシュ用に控えておく
+fn oj_test_logged(command: &str, project: &Project) -> Result<(TestResult, String), SubmitError> {
+    let start = Instant::now();
+    let mut cmd = Command::new("oj");
+    // checker は他人のものかもしれないので隔離して走らせる
+    if let Some(checker) = &project.checker {
+        let judge = sandbox::isolated(checker).map_err(|e| {
+            eprintln!("{e}");
+            SubmitError::CommandExecuteFailed
+        })?;
+        cmd.arg("t").arg("--judge-command").arg(judge);
+    } else {
+        cmd.arg("t");
+    }
+    if let Some(tl) = project.time_limit_ms {
+        cmd.arg("--tle").arg(format!("{}", tl as f64 / 1000.0));
+    }
+    let mut child = cmd
+        .arg("-c")
+        .arg(project.limits.wrap_shell(command))
+        .arg("-d")
+        .arg(&project.test_dir)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let mut log = String::new();
+    for line in BufReader::new(child.stdout.take().unwrap()).lines().map_while(Result::ok) {
+        print_child_line(&line);
+        log += &line;
+        log.push('\n');
+    }
+    let Ok(status) = child.wait() else {
+        return Err(SubmitError::CommandExecuteFailed);
+    };
+    eprintln!("run: {:.2}s", start.elapsed().as_secs_f64());
+    match status.code() {
+        Some(0) => Ok((TestResult::Passed, log)),
+        Some(_) => Ok((TestResult::Failed, log)),
+        // シグナルで落ちた (制限に掛かった SIGXCPU / SIGKILL など)
+        None => {
+            let line = format!("oj was killed by {}", signal_name(&status));
+            eprintln!("{}", style::red(&line));
+            log += &line;
+            log.push('\n');
+            Ok((TestResult::Failed, log))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_name(status: &std::process::ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+    match status.signal() {
+        Some(libc::SIGXCPU) => "SIGXCPU (CPU time limit)".to_string(),
+        Some(libc::SIGKILL) => "SIGKILL".to_string(),
+        Some(libc::SIGXFSZ) => "SIGXFSZ (file size limit)".to_string(),
+        Some(n) => format!("signal {n}"),
+        None => "a signal".to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn signal_name(_status: &std::process::ExitStatus) -> String {
+    "a signal".to_string()
+}
+
+/// カレントディレクトリかホームの bundler.toml。bundler の CLI と同じ設定で束ねるため
+pub(crate) fn bundler_config() -> bundler::config::Config {
+    bundler::config::find()
+        .unwrap_or_else(|e| {
+            eprintln!("warning: {e:#}");
+            None
+        })
+        .unwrap_or_default()
+}
+
+/// bundler に渡す (名前, ライブラリの src)。bundler.toml の [libraries] が無ければ library::locate で探したもの。[vendor] も足す
+pub(crate) fn library_roots(project: &Project, cfg: &bundler::config::Config) -> Result<Vec<(String, PathBuf)>, SubmitError> {
+    let mut roots = cfg.roots();
+    if roots.is_empty() {
+        let lib_root = match library::locate(Some(project))? {
+            Some(root) => root,
+            None if deps::uses_library(project, cfg.root_ident()) => return Err(SubmitError::LibraryNotConfigured),
+            // library を使っていなければ参照されない
+            None => PathBuf::new(),
+        };
+        roots.push((cfg.root_ident().to_string(), lib_root));
+    }
+    roots.extend(cfg.vendor_roots());
+    Ok(roots)
+}
+
+/// bundler.toml の設定。問題ごとの上限 (ac_config.toml) があればそちらを使う
+pub(crate) fn bundle_options(project: &Project, cfg: &bundler::config::Config) -> bundler::Options {
+    let mut opts = cfg.options();
+    opts.max_bytes = project.max_bytes.or(opts.max_bytes);
+    opts
+}
+
+/// bundler でライブラリを束ねたソースを作る
+pub fn bundle_source(project: &Project) -> Result<String, SubmitError> {
+    let cfg = bundler_config();
+    let roots = library_roots(project, &cfg)?;
+    bundler::bundle_file(&roots, &project.source, &bundle_options(project, &cfg))
+        .map_err(|e| SubmitError::BundleFailed(format!("{e:#}")))
+}
+
+/// bundler でライブラリを束ねて submit.rs に保存する
+pub fn bundle(project: &Project) -> Result<Bundle, SubmitError> {
+    let bundled_src = bundle_source(project)?;
+
+    // submit.rs へ保存
+    let path = PathBuf::from("submit.rs");
+    let mut file = File::create(&path).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    file.write_all(bundled_src.as_bytes())
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let hash = history::short_hash(&bundled_src);
+    Ok(Bundle { path, source: bundled_src, hash })
+}
+
+/// 解答の .cpp の `#include "…"` を展開して submit.cpp に保存する
+pub fn bundle_cpp(project: &Project) -> Result<Bundle, SubmitError> {
+    let main = project.source.with_extension("cpp");
+    let include_dirs: Vec<PathBuf> = library::locate_cpp().into_iter().collect();
+    let bundled_src = bundler::cpp::bundle(&main, &include_dirs)
+        .map_err(|e| SubmitError::BundleFailed(format!("{e:#}")))?;
+
+    let path = PathBuf::from("submit.cpp");
+    std::fs::write(&path, &bundled_src).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let hash = history::short_hash(&bundled_src);
+    Ok(Bundle { path, source: bundled_src, hash })
+}
+
+/// 解答の .py のライブラリの import を展開して submit.py に保存する
+pub fn bundle_py(project: &Project) -> Result<Bundle, SubmitError> {
+    let main = project.source.with_extension("py");
+    let lib_dirs: Vec<PathBuf> = library::locate_py().into_iter().collect();
+    let bundled_src = bundler::py::bundle(&main, &lib_dirs)
+        .map_err(|e| SubmitError::BundleFailed(format!("{e:#}")))?;
+
+    let path = PathBuf::from("submit.py");
+    std::fs::write(&path, &bundled_src).map_err(|_| SubmitError::CommandExecuteFailed)?;
+    let hash = history::short_hash(&bundled_src);
+    Ok(Bundle { path, source: bundled_src, hash })
+}
+
+/// クリップボードへコピー（UTF-16LE）
+pub fn copy_to_clipboard(src: &str) -> Result<(), SubmitError> {
+    let utf16_bytes = utf8_to_utf16le_bytes(src);
+    let mut child = Command::new("clip.exe")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        stdin.write_all(&utf16_bytes).unwrap();
+    }
+    child.wait().unwrap();
+    Ok(())
+}
+
+/// サンプル取得 → テスト → バンドル → コピー
+pub fn submit(lang: &str, project: &Project, opts: &SubmitOptions) -> Result<Bundle, SubmitError> {
+    project.fetch_samples(opts.refresh)?;
+
+
+    if lang == "rs" {
+        if opts.check {
+            let foreign = deps::foreign_crates(project);
+            if !foreign.is_empty() {
+                return Err(SubmitError::ForeignCrates(foreign));
+            }
+            if run_tests_cached(project, opts)? == TestResult::Failed {
+                return Err(SubmitError::SampleFailed);
+            }
+        }
+
+        // ファイルマージ
+        // let output = Command::new("uv")
+        //     .args(["run", "python3"])
+        //     .arg("../../util/file_merger.py")
+        //     .arg(id)
+        //     .stdout(Stdio::inherit())
+        //     .status();
+        // if output.is_err() {
+        //     return Err(SubmitError::CommandExecuteFailed);
+        // }
+
+        let bundled = bundle(project)?;
+        if opts.check && opts.test_bundled
+            && bundled::test_bundled(project, &bundled)? == TestResult::Failed
+        {
+            return Err(SubmitError::SampleFailed);
+        }
+        copy_to_clipboard(&bundled.source)?;
+        history::record(project, &bundled.source);
+        Ok(bundled)
+    } else if lang == "cpp" || lang == "py" {
+        // サンプルのテストはまだ rs だけ。取得はしておく
+        let bundled = if lang == "cpp" { bundle_cpp(project)? } else { bundle_py(project)? };
+        copy_to_clipboard(&bundled.source)?;
+        history::record(project, &bundled.source);
+        Ok(bundled)
+    } else {
+        Err(SubmitError::UnsupportedLanguage(lang.to_string()))
+    }
+}
@@ -0,0 +1,103 @@
+//! 束ねるライブラリ (`use library::…`) の場所
+//!
+//! 次の順に探す。パスはクレートのルート (Cargo.toml のあるところ) でも src でもよい。
+//!
+//! 1. 環境変数 `ACSUB_LIBRARY`
+//! 2. カレントディレクトリの ac_config.toml の `library = "..."`
+//! 3. 解答の Cargo.toml の `library = { path = "..." }`
+//! 4. `~/.config/cp-assists/config.toml` の `library = "..."`
+use std::{env, fs::read_to_string, path::{Path, PathBuf}};
+
+use dirs::{config_dir, home_dir};
+use toml::Value;
+
+use crate::{Project, SubmitError};
+
+/// `library = "..."` を読む
+fn library_key(config: &Path) -> Option<String> {
+    let v: Value = toml::from_str(&read_to_string(config).ok()?).ok()?;
+    Some(v.get("library")?.as_str()?.to_string())
+}
+
+/// 解答の Cargo.toml にある library の path 依存 (manifest からの相対パスを直す)
+fn from_cargo(source: &Path) -> Option<PathBuf> {
+    let source = source.canonicalize().ok()?;
+    let manifest = source.ancestors().skip(1).map(|d| d.join("Cargo.toml")).find(|p| p.is_file())?;
+    let v: Value = toml::from_str(&read_to_string(&manifest).ok()?).ok()?;
+    let path = v.get("dependencies")?.get("library")?.get("path")?.as_str()?;
+    Some(manifest.parent()?.join(path))
+}
+
+pub fn global_config() -> Option<PathBuf> {
+    Some(config_dir()?.join("cp-assists").join("config.toml"))
+}
+
+fn expand_home(p: &str) -> PathBuf {
+    match (p.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(p),
+    }
+}
+
+/// 設定されたパス → bundler に渡す src。見つからなければどこの設定が悪いかを添えて返す
+fn validate(path: PathBuf, origin: &str) -> Result<PathBuf, SubmitError> {
+    let src = if path.join("Cargo.toml").is_file() { path.join("src") } else { path.clone() };
+    if src.join("lib.rs").is_file() {
+        return Ok(src);
+    }
+    Err(SubmitError::LibraryNotFound(format!(
+        "{} (from {origin}) has no lib.rs; point it at the library crate or its src directory",
+        path.display()
+    )))
+}
+
+/// 設定されていなければ None
+pub fn locate(project: Option<&Project>) -> Result<Option<PathBuf>, SubmitError> {
+    if let Ok(p) = env::var("ACSUB_LIBRARY") {
+        return validate(expand_home(&p), "$ACSUB_LIBRARY").map(Some);
+    }
+    if let Some(p) = library_key(Path::new("ac_config.toml")) {
+        return validate(expand_home(&p), "ac_config.toml").map(Some);
+    }
+    if let Some(p) = project.and_then(|p| from_cargo(&p.source)) {
+        return validate(p, "Cargo.toml").map(Some);
+    }
+    if let Some(config) = global_config()
+        && let Some(p) = library_key(&config)
+    {
+        return validate(expand_home(&p), &config.display().to_string()).map(Some);
+    }
+    Ok(None)
+}
+
+/// src → クレートのルート (Cargo.toml の path 依存に書く場所)
+pub fn crate_root(src: &Path) -> PathBuf {
+    match src.parent() {
+        Some(parent) if parent.join("Cargo.toml").is_file() => parent.to_path_buf(),
+        _ => src.to_path_buf(),
+    }
+}
+
+/// `var` → ac_config.toml の `key` → グローバル設定の `key` の順に探したディレクトリ
+fn locate_dir(var: &str, key: &str) -> Option<PathBuf> {
+    if let Ok(p) = env::var(var) {
+        return Some(expand_home(&p));
+    }
+    let read = |config: &Path| -> Option<String> {
+        let v: Value = toml::from_str(&read_to_string(config).ok()?).ok()?;
+        Some(v.get(key)?.as_str()?.to_string())
+    };
+    read(Path::new("ac_config.toml"))
+        .or_else(|| read(&global_config()?))
+        .map(|p| expand_home(&p))
+}
+
+/// C++ のヘッダライブラリ (`#include "mylib/…"` を探すディレクトリ)。`ACSUB_CPP_LIBRARY` か `cpp_library`
+pub fn locate_cpp() -> Option<PathBuf> {
+    locate_dir("ACSUB_CPP_LIBRARY", "cpp_library")
+}
+
+/// Python のライブラリ (`from mylib.x import …` の mylib があるディレクトリ)。`ACSUB_PY_LIBRARY` か `py_library`
+pub fn locate_py() -> Option<PathBuf> {
+    locate_dir("ACSUB_PY_LIBRARY", "py_library")
+}
@@ -0,0 +1,603 @@
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::{collections::BTreeMap, env::current_dir, fs::{self, read_to_string, OpenOptions}, io::{BufRead, BufReader, Write}, path::{Path, PathBuf}, process::{Command, Stdio}, time::{Duration, Instant}};
+
+use serde::Deserialize;
+use toml::{from_str, Value};
+
+use crate::{ahc, samples, sandbox::Limits, SubmitError};
+
+#[derive(Deserialize)]
+struct Config {
+    /// 以前の形式 ([judges] が無いときだけ使う)
+    url: Option<String>,
+    /// この contest で使う judges のキー
+    judge: Option<String>,
+    #[serde(default)]
+    judges: BTreeMap<String, Judge>,
+    /// 問題ごとの最大ケースの記述 (genmax)
+    #[serde(default, rename = "gen-max")]
+    gen_max: BTreeMap<String, String>,
+    #[serde(default)]
+    limits: Limits,
+    /// 問題ごとの checker (oj t --judge-command、隔離して走らせる)
+    #[serde(default)]
+    checkers: BTreeMap<String, String>,
+    /// 実行時間制限 (oj t --tle)
+    time_limit_ms: Option<u64>,
+    /// 提出できるソースの長さの上限 (バイト)。[judges.<name>] の max_bytes が優先
+    max_bytes: Option<usize>,
+    /// 得点を競うコンテスト (AHC)
+    #[serde(default)]
+    heuristic: bool,
+    #[serde(default)]
+    ahc: ahc::Config,
+}
+
+/// `[judges.<name>]`
+#[derive(Deserialize)]
+struct Judge {
+    /// `{problem_id}` を置き換える問題 URL
+    url: String,
+    /// acsub の言語 (rs など) → ジャッジの言語 ID
+    #[serde(default)]
+    languages: BTreeMap<String, String>,
+    /// [limits] をこのジャッジだけ上書きする
+    limits: Option<Limits>,
+    /// 提出できるソースの長さの上限 (バイト)
+    max_bytes: Option<usize>,
+}
+
+/// どのツールのディレクトリ構成か
+#[derive(Clone)]
+pub enum Layout {
+    /// ac_config.toml + src/bin/{id}.rs (標準)
+    AcConfig,
+    /// cargo-compete の Cargo.toml / compete.toml
+    CargoCompete { test_suite: Option<PathBuf> },
+    /// cargo-atcoder (src/bin/{a,b,..}.rs)。cookie は oj 用に変換したセッション
+    CargoAtcoder { cookie: Option<PathBuf> },
+    /// atcoder-cli (contest.acc.json + 問題ごとのディレクトリ)
+    Acc,
+}
+
+/// 1 問分の場所と実行方法
+#[derive(Clone)]
+pub struct Project {
+    pub layout: Layout,
+    pub contest_id: Option<String>,
+    pub url: String,
+    /// 束ねて提出するソース
+    pub source: PathBuf,
+    /// サンプルの置き場所 (oj の test/)
+    pub test_dir: PathBuf,
+    /// oj t -c に渡す実行コマンド
+    pub run: String,
+    /// テスト前に一度だけ走らせるビルドコマンド (実行時間と分けて計るため)
+    pub build: Option<String>,
+    /// ac_config.toml の [gen-max] にある最大ケースの記述
+    pub gen_max: Option<String>,
+    /// 解答を走らせるときの rlimit
+    pub limits: Limits,
+    /// 出力の正誤を判定するコマンド (`<cmd> input actual expected`)。無ければ完全一致
+    pub checker: Option<String>,
+    /// ac_config.toml の judges から選んだもの
+    pub judge: Option<String>,
+    /// acsub の言語 → ジャッジの言語 ID
+    pub languages: BTreeMap<String, String>,
+    /// これを超えたら TLE にする
+    pub time_limit_ms: Option<u64>,
+    /// 束ねたソースがこれを超えたら提出しない
+    pub max_bytes: Option<usize>,
+    /// 得点を競うコンテスト (AHC) のプロジェクト
+    pub heuristic: bool,
+    /// AHC のローカルテスタの設定
+    pub ahc: ahc::Config,
+}
+
+impl Project {
+    /// lang (rs など) をこのジャッジに提出するときの言語 ID
+    pub fn language_id(&self, lang: &str) -> Option<&str> {
+        self.languages.get(lang).map(String::as_str)
+    }
+}
+
+/// URL (https://atcoder.jp/contests/abc350/tasks/abc350_a) から contest ID を取る
+pub(crate) fn contest_of(url: &str) -> Option<String> {
+    let rest = url.split("/contests/").nth(1)?;
+    Some(rest.split('/').next()?.to_string())
+}
+
+fn from_ac_config(dir: &Path, problem_id: &str, judge: Option<&str>) -> Option<Project> {
+    // $(pwd) の ac_config.tomlを読む
+    let path = dir.join("ac_config.toml");
+    if !path.exists() {
+        return None;
+    }
+
+    let src = read_to_string(&path).expect("failed to read content.");
+    let mut cfg: Config = from_str(&src).expect("failed to parse.");
+
+    // --judge > judge = "..." > 1 つしか無ければそれ > 以前の url
+    let name = judge.map(str::to_string)
+        .or(cfg.judge.clone())
+        .or_else(|| (cfg.judges.len() == 1).then(|| cfg.judges.keys().next().unwrap().clone()));
+    let (template, languages, limits, max_bytes) = match (&name, cfg.url.take()) {
+        (Some(n), _) => {
+            let Some(j) = cfg.judges.remove(n) else {
+                eprintln!("judge `{n}` is not defined in ac_config.toml (known: {})", cfg.judges.keys().cloned().collect::<Vec<_>>().join(", "));
+                return None;
+            };
+            (j.url, j.languages, j.limits.unwrap_or(cfg.limits), j.max_bytes.or(cfg.max_bytes))
+        }
+        (None, Some(url)) => (url, BTreeMap::new(), cfg.limits, cfg.max_bytes),
+        (None, None) => {
+            eprintln!("ac_config.toml has several [judges]; choose one with `judge = \"...\"` or --judge");
+            return None;
+        }
+    };
+    // URLを生成
+    let place_holder = "{problem_id}";
+    let url = template.replace(place_holder, problem_id);
+    // 得点は速さで変わるので AHC では最適化して走らせる
+    let release = if cfg.heuristic { " --release" } else { "" };
+
+    Some(Project {
+        layout: Layout::AcConfig,
+        contest_id: contest_of(&url),
+        url,
+        source: PathBuf::from(format!("src/bin/{problem_id}.rs")),
+        test_dir: PathBuf::from("test"),
+        run: format!("cargo run{release} --features local --bin {problem_id}"),
+        build: Some(format!("cargo build{release} --features local --bin {problem_id}")),
+        gen_max: cfg.gen_max.get(problem_id).cloned(),
+        limits,
+        checker: cfg.checkers.get(problem_id).cloned(),
+        judge: name,
+        languages,
+        time_limit_ms: cfg.time_limit_ms,
+        max_bytes,
+        heuristic: cfg.heuristic,
+        ahc: cfg.ahc,
+    })
+}
+
+/// 親ディレクトリを遡って name を探す
+fn find_upward(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors().map(|d| d.join(name)).find(|p| p.is_file())
+}
+
+/// `{{ manifest_dir }}/testcases/{{ bin_alias }}.yml` のような liquid テンプレートを埋める
+fn expand_template(tmpl: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    let mut rest = tmpl;
+    while let Some(s) = rest.find("{{") {
+        out += &rest[..s];
+        let Some(e) = rest[s..].find("}}") else { break };
+        let key = rest[s + 2..s + e].trim();
+        // `problem | kebabcase` のようなフィルタは素通し
+        let key = key.split('|').next().unwrap().trim();
+        out += vars.iter().find(|(k, _)| *k == key).map(|(_, v)| *v).unwrap_or("");
+        rest = &rest[s + e + 2..];
+    }
+    out + rest
+}
+
+fn from_cargo_compete(dir: &Path, problem_id: &str) -> Option<Project> {
+    let manifest: Value = from_str(&read_to_string(dir.join("Cargo.toml")).ok()?).ok()?;
+    let bins = manifest.get("package")?.get("metadata")?.get("cargo-compete")?.get("bin")?.as_table()?;
+
+    // problem_id は bin 名 (abc350-a), alias (a), task ID (abc350_a) のどれでもよい
+    let (bin_name, alias, url) = bins.iter().find_map(|(key, v)| {
+        let url = v.get("problem")?.as_str()?.to_string();
+        // 古い形式は [bin.a] name = "abc350-a"
+        let (name, alias) = match v.get("name").and_then(Value::as_str) {
+            Some(name) => (name.to_string(), key.clone()),
+            None => (key.clone(), v.get("alias")?.as_str()?.to_string()),
+        };
+        let task = url.rsplit('/').next().unwrap_or_default();
+        [name.as_str(), alias.as_str(), task].contains(&problem_id).then_some((name, alias, url))
+    })?;
+
+    let source = manifest
+        .get("bin")
+        .and_then(Value::as_array)
+        .and_then(|bs| bs.iter().find(|b| b.get("name").and_then(Value::as_str) == Some(&bin_name)))
+        .and_then(|b| b.get("path")?.as_str())
+        .map(|p| dir.join(p))
+        .unwrap_or_else(|| dir.join(format!("src/bin/{alias}.rs")));
+
+    let contest_id = contest_of(&url)
+        .or_else(|| manifest.get("package")?.get("name")?.as_str().map(str::to_string));
+
+    // compete.toml の test-suite からサンプルの YAML を決める
+    let test_suite = find_upward(dir, "compete.toml").and_then(|p| {
+        let cfg: Value = from_str(&read_to_string(&p).ok()?).ok()?;
+        let tmpl = cfg.get("test-suite")?.as_str()?;
+        let manifest_dir = dir.to_string_lossy();
+        let contest = contest_id.clone().unwrap_or_default();
+        Some(PathBuf::from(expand_template(tmpl, &[
+            ("manifest_dir", &manifest_dir),
+            ("bin_alias", &alias),
+            ("bin_name", &bin_name),
+            ("contest", &contest),
+            ("problem", &alias),
+        ])))
+    });
+
+    Some(Project {
+        layout: Layout::CargoCompete { test_suite },
+        contest_id,
+        url,
+        source,
+        test_dir: PathBuf::from("test").join(&alias),
+        run: format!("cargo run --bin {bin_name}"),
+        build: Some(format!("cargo build --bin {bin_name}")),
+        gen_max: None,
+        limits: Limits::default(),
+        checker: None,
+        judge: None,
+        languages: BTreeMap::new(),
+        time_limit_ms: None,
+        max_bytes: None,
+        heuristic: false,
+        ahc: ahc::Config::default(),
+    })
+}
+
+/// cargo-atcoder のセッション (REVEL_SESSION) を oj の LWP cookie jar に書き直す
+fn atcoder_cookie_jar() -> Option<PathBuf> {
+    let cache = dirs::cache_dir()?;
+    let session = read_to_string(cache.join("cargo-atcoder").join("session.json")).ok()?;
+    let start = session.find("REVEL_SESSION=")? + "REVEL_SESSION=".len();
+    let value: String = session[start..]
+        .chars()
+        .take_while(|c| !matches!(c, ';' | '"' | '\\' | ',' | ' '))
+        .collect();
+    let jar = cache.join("cp-assists").join("atcoder-cookie.jar");
+    fs::create_dir_all(jar.parent()?).ok()?;
+    // セッションそのものなので自分だけが読めるように。前に作ったものも書く前に絞る
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+        if jar.exists() {
+            fs::set_permissions(&jar, fs::Permissions::from_mode(0o600)).ok()?;
+        }
+    }
+    let mut file = options.open(&jar).ok()?;
+    write!(file,
+        "#LWP-Cookies-2.0\nSet-Cookie3: REVEL_SESSION=\"{value}\"; path=\"/\"; domain=\"atcoder.jp\"; path_spec; secure; discard; HttpOnly=None; version=0\n"
+    ).ok()?;
+    Some(jar)
+}
+
+fn from_cargo_atcoder(dir: &Path, problem_id: &str) -> Option<Project> {
+    // グローバル設定が無ければ cargo-atcoder のプロジェクトとはみなさない
+    if !dirs::config_dir()?.join("cargo-atcoder.toml").is_file() {
+        return None;
+    }
+    let manifest: Value = from_str(&read_to_string(dir.join("Cargo.toml")).ok()?).ok()?;
+    // cargo atcoder new abc350 → package 名が contest ID、bin が a, b, ...
+    let contest = manifest.get("package")?.get("name")?.as_str()?.to_string();
+    let bin = problem_id.strip_prefix(&format!("{contest}_")).unwrap_or(problem_id);
+    let path = manifest.get("bin")?.as_array()?.iter().find_map(|b| {
+        (b.get("name")?.as_str()? == bin).then(|| b.get("path")?.as_str().map(str::to_string))?
+    })?;
+
+    Some(Project {
+        layout: Layout::CargoAtcoder { cookie: atcoder_cookie_jar() },
+        url: format!("https://atcoder.jp/contests/{contest}/tasks/{contest}_{bin}"),
+        contest_id: Some(contest),
+        source: dir.join(path),
+        test_dir: PathBuf::from("test").join(bin),
+        run: format!("cargo run --bin {bin}"),
+        build: Some(format!("cargo build --bin {bin}")),
+        gen_max: None,
+        limits: Limits::default(),
+        checker: None,
+        judge: None,
+        languages: BTreeMap::new(),
+        time_limit_ms: None,
+        max_bytes: None,
+        heuristic: false,
+        ahc: ahc::Config::default(),
+    })
+}
+
+#[derive(Deserialize)]
+struct AccContest {
+    contest: AccContestInfo,
+    tasks: Vec<AccTask>,
+}
+
+#[derive(Deserialize)]
+struct AccContestInfo {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct AccTask {
+    id: String,
+    label: String,
+    url: String,
+    directory: Option<AccDirectory>,
+}
+
+#[derive(Deserialize)]
+struct AccDirectory {
+    path: String,
+    testdir: String,
+    submit: String,
+}
+
+/// contest.acc.json (contest フォルダ直下、または問題フォルダの 1 つ上) から探す
+fn from_acc(dir: &Path, problem_id: &str) -> Option<Project> {
+    let json = [dir, dir.parent()?].into_iter().map(|d| d.join("contest.acc.json")).find(|p| p.is_file())?;
+    let root = json.parent()?;
+    let acc: AccContest = serde_json::from_str(&read_to_string(&json).ok()?).ok()?;
+
+    // problem_id は task ID (abc350_a), ラベル (A), ディレクトリ名 (a) のどれでもよい
+    let task = acc.tasks.iter().find(|t| {
+        t.id == problem_id
+            || t.label.eq_ignore_ascii_case(problem_id)
+            || t.directory.as_ref().is_some_and(|d| d.path == problem_id)
+    })?;
+    let d = task.directory.as_ref()?;
+    let task_dir = root.join(&d.path);
+    let source = task_dir.join(&d.submit);
+
+    // 問題フォルダが cargo プロジェクトならそれを、そうでなければ rustc で直接
+    let (run, build) = if task_dir.join("Cargo.toml").is_file() {
+        let manifest = task_dir.join("Cargo.toml");
+        (
+            format!("cargo run --manifest-path {}", manifest.display()),
+            Some(format!("cargo build --manifest-path {}", manifest.display())),
+        )
+    } else {
+        let bin = task_dir.join("a.out");
+        (
+            bin.display().to_string(),
+            Some(format!("rustc -O --edition 2021 -o {} {}", bin.display(), source.display())),
+        )
+    };
+
+    Some(Project {
+        layout: Layout::Acc,
+        contest_id: Some(acc.contest.id),
+        url: task.url.clone(),
+        source,
+        test_dir: task_dir.join(&d.testdir),
+        run,
+        build,
+        gen_max: None,
+        limits: Limits::default(),
+        checker: None,
+        judge: None,
+        languages: BTreeMap::new(),
+        time_limit_ms: None,
+        max_bytes: None,
+        heuristic: false,
+        ahc: ahc::Config::default(),
+    })
+}
+
+/// カレントディレクトリの構成を見て problem_id の Project を作る
+pub fn try_detect(problem_id: &str) -> Option<Project> {
+    try_detect_judge(problem_id, None)
+}
+
+/// judge は ac_config.toml の [judges.<name>] のどれを使うか (他の構成では無視)
+pub fn try_detect_judge(problem_id: &str, judge: Option<&str>) -> Option<Project> {
+    let dir = current_dir().unwrap();
+    from_ac_config(&dir, problem_id, judge)
+        .or_else(|| from_cargo_compete(&dir, problem_id))
+        .or_else(|| from_cargo_atcoder(&dir, problem_id))
+        .or_else(|| from_acc(&dir, problem_id))
+}
+
+//------------------------------------------------------------
+// cargo-compete のテストスイート (testcases/*.yml)
+//------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct TestSuite {
+    #[serde(default)]
+    cases: Vec<Case>,
+    #[serde(default)]
+    extend: Vec<Extend>,
+}
+
+#[derive(Deserialize)]
+struct Case {
+    name: Option<String>,
+    #[serde(rename = "in")]
+    input: String,
+    out: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Extend {
+    /// `cargo compete download --full` のシステムテスト
+    Text {
+        path: String,
+        #[serde(rename = "in")]
+        input: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// YAML のケースを oj 形式 (name.in / name.out) に書き出す
+fn materialize_suite(suite_path: &Path, test_dir: &Path) -> std::io::Result<usize> {
+    let src = read_to_string(suite_path)?;
+    let suite: TestSuite = serde_yaml::from_str(&src)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    fs::create_dir_all(test_dir)?;
+    let mut n = 0;
+    for (i, c) in suite.cases.iter().enumerate() {
+        let name = c.name.clone().unwrap_or_else(|| format!("sample{}", i + 1));
+        fs::write(test_dir.join(format!("{name}.in")), &c.input)?;
+        if let Some(out) = &c.out {
+            fs::write(test_dir.join(format!("{name}.out")), out)?;
+        }
+        n += 1;
+    }
+    let base = suite_path.parent().unwrap_or(Path::new("."));
+    for e in &suite.extend {
+        let Extend::Text { path, input } = e else { continue };
+        // in: /in/*.txt → <path>/in, 出力は <path>/out の同名ファイル
+        let root = base.join(path);
+        let in_dir = root.join(input.trim_start_matches('/').rsplit_once('/').map_or("in", |(d, _)| d));
+        let Ok(entries) = fs::read_dir(&in_dir) else { continue };
+        for entry in entries.flatten() {
+            let p = entry.path();
+            let Some(stem) = p.file_stem().and_then(|s| s.to_str()) else { continue };
+            fs::copy(&p, test_dir.join(format!("{stem}.in")))?;
+            let out = root.join("out").join(entry.file_name());
+            if out.is_file() {
+                fs::copy(&out, test_dir.join(format!("{stem}.out")))?;
+            }
+            n += 1;
+        }
+    }
+    Ok(n)
+}
+
+/// 取得中の staging に置く印 (中身は URL)。残っていれば前回は途中で切れている
+const PARTIAL_MARKER: &str = ".acsub-partial";
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+
+impl Project {
+    /// サンプルを test_dir に用意する
+    /// 既にあればそれを使う。refresh なら取り直して、検査した上で差し替える
+    pub fn fetch_samples(&self, refresh: bool) -> Result<(), SubmitError> {
+        let cached = samples::has_samples(&self.test_dir);
+        if cached && !refresh {
+            return Ok(());
+        }
+
+        // 一旦別の場所に取ってきて、壊れていなければ差し替える。
+        // 途中で切れたら staging を残しておき、次はそろっているケースを活かして続きから取る
+        let staging = self.sibling_dir("new");
+        let marker = staging.join(PARTIAL_MARKER);
+        let resuming = fs::read_to_string(&marker).is_ok_and(|u| u.trim() == self.url);
+        if resuming {
+            let kept = samples::prune_incomplete(&staging);
+            eprintln!("resuming an interrupted download ({kept} cases already complete)");
+        } else {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        fs::create_dir_all(&staging).map_err(|_| SubmitError::CommandExecuteFailed)?;
+        fs::write(&marker, &self.url).map_err(|_| SubmitError::CommandExecuteFailed)?;
+
+        if let Layout::CargoCompete { test_suite: Some(suite) } = &self.layout
+            && suite.is_file()
+        {
+            if let Err(e) = materialize_suite(suite, &staging) {
+                eprintln!("failed to read {:?}: {e}", suite);
+                return Err(SubmitError::CommandExecuteFailed);
+            }
+        } else {
+            self.download_with_retry(&staging)?;
+        }
+        let _ = fs::remove_file(&marker);
+
+        let problems = samples::verify(&staging);
+        if !problems.is_empty() {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(SubmitError::BrokenSamples(problems));
+        }
+        if cached {
+            let d = samples::diff(&self.test_dir, &staging);
+            if !d.is_empty() {
+                eprintln!("samples were updated on the judge:");
+                for (label, names) in [("added", &d.added), ("changed", &d.changed), ("removed", &d.removed)] {
+                    if !names.is_empty() {
+                        eprintln!("  {label}: {}", names.join(", "));
+                    }
+                }
+            }
+        }
+        samples::replace(&self.test_dir, &staging).map_err(|_| SubmitError::CommandExecuteFailed)?;
+        Ok(())
+    }
+
+    /// test_dir の隣の作業用ディレクトリ (`test.new` など)
+    fn sibling_dir(&self, suffix: &str) -> PathBuf {
+        let mut dir = self.test_dir.clone().into_os_string();
+        dir.push(format!(".{suffix}"));
+        PathBuf::from(dir)
+    }
+
+    /// oj d を数回まで試し、取れたものを staging に足す (既にあるケースは上書きしない)
+    fn download_with_retry(&self, staging: &Path) -> Result<(), SubmitError> {
+        let part = self.sibling_dir("part");
+        for attempt in 1..=DOWNLOAD_ATTEMPTS {
+            let _ = fs::remove_dir_all(&part);
+            let mut cmd = Command::new("oj");
+            if let Layout::CargoAtcoder { cookie: Some(jar) } = &self.layout {
+                cmd.arg("--cookie").arg(jar);
+            }
+            let output = cmd
+                .args(["d", &self.url, "-d"])
+                .arg(&part)
+                .stdout(crate::child_stdout())
+                .status();
+            // 失敗しても取れた分は残す
+            if part.is_dir() {
+                samples::prune_incomplete(&part);
+                let _ = samples::merge_missing(&part, staging);
+            }
+            let _ = fs::remove_dir_all(&part);
+            if output.is_ok_and(|s| s.success()) {
+                return Ok(());
+            }
+            if attempt < DOWNLOAD_ATTEMPTS {
+                eprintln!("download failed; retrying ({attempt}/{DOWNLOAD_ATTEMPTS})");
+                std::thread::sleep(Duration::from_secs(attempt as u64));
+            }
+        }
+        eprintln!("download failed; run the same command again to resume from {}", staging.display());
+        Err(SubmitError::CommandExecuteFailed)
+    }
+
+    /// build があれば実行する
+    ///
+    /// かかった時間を表示し、レジストリの依存まで再コンパイルしていたら警告する
+    pub fn build(&self) -> Result<(), SubmitError> {
+        let Some(build) = &self.build else { return Ok(()) };
+        let start = Instant::now();
+        let mut child = Command::new("sh")
+            .args(["-c", build])
+            .stdout(crate::child_stdout())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|_| SubmitError::CommandExecuteFailed)?;
+        // cargo の "Compiling foo v1.0.0" のうち、パス付きでない = レジストリの依存
+        let mut deps = Vec::new();
+        for line in BufReader::new(child.stderr.take().unwrap()).lines().map_while(Result::ok) {
+            eprintln!("{line}");
+            if let Some(rest) = line.trim_start().strip_prefix("Compiling ")
+                && !rest.contains('(')
+            {
+                deps.push(rest.split_whitespace().next().unwrap_or("").to_string());
+            }
+        }
+        let status = child.wait().map_err(|_| SubmitError::CommandExecuteFailed)?;
+        eprintln!("build: {:.2}s", start.elapsed().as_secs_f64());
+        if !deps.is_empty() {
+            eprintln!(
+                "warning: recompiled {} dependency crates ({}...); the build cache was not used",
+                deps.len(),
+                deps.iter().take(3).cloned().collect::<Vec<_>>().join(", "),
+            );
+        }
+        if !status.success() {
+            return Err(SubmitError::CommandExecuteFailed);
+        }
+        Ok(())
+    }
+}
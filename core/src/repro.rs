@@ -0,0 +1,78 @@
+//! 落ちたケースを共有用にまとめる
+use std::{
+    env::current_dir,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{Project, SubmitError};
+
+/// `sample-1` / `1` のどちらでも test_dir のケースを探す
+pub fn find_case(test_dir: &Path, case: &str) -> Option<(PathBuf, PathBuf)> {
+    [case.to_string(), format!("sample-{case}")].into_iter().find_map(|name| {
+        let input = test_dir.join(format!("{name}.in"));
+        input.is_file().then(|| (input, test_dir.join(format!("{name}.out"))))
+    })
+}
+
+/// export がまとめる先
+pub fn dir_of(problem_id: &str, case: &str) -> PathBuf {
+    PathBuf::from("repro").join(format!("{problem_id}-{case}"))
+}
+
+/// repro/<問題>-<ケース>/ にまとめ、同名の .tar.gz も作る
+pub fn export(project: &Project, problem_id: &str, case: &str) -> Result<PathBuf, SubmitError> {
+    let io_err = |_| SubmitError::CommandExecuteFailed;
+    let (input, expected) = find_case(&project.test_dir, case)
+        .ok_or_else(|| SubmitError::CaseNotFound(case.to_string()))?;
+
+    project.build()?;
+    let mut cmd = Command::new("sh");
+    project.limits.apply(&mut cmd);
+    let out = cmd
+        .args(["-c", &project.run])
+        .stdin(Stdio::from(File::open(&input).map_err(io_err)?))
+        .output()
+        .map_err(io_err)?;
+
+    let dir = dir_of(problem_id, case);
+    let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).map_err(io_err)?;
+
+    fs::copy(&input, dir.join("input.txt")).map_err(io_err)?;
+    if expected.is_file() {
+        fs::copy(&expected, dir.join("expected.txt")).map_err(io_err)?;
+    }
+    fs::write(dir.join("actual.txt"), &out.stdout).map_err(io_err)?;
+    fs::write(dir.join("stderr.txt"), &out.stderr).map_err(io_err)?;
+    let src_name = project.source.file_name().map_or("main.rs".into(), |n| n.to_string_lossy().into_owned());
+    fs::copy(&project.source, dir.join(&src_name)).map_err(io_err)?;
+
+    let matched = fs::read_to_string(&expected)
+        .is_ok_and(|e| e.split_whitespace().eq(String::from_utf8_lossy(&out.stdout).split_whitespace()));
+    let mut command = String::new();
+    command += &format!("# cwd: {}\n", current_dir().map_err(io_err)?.display());
+    if let Some(b) = &project.build {
+        command += &format!("{b}\n");
+    }
+    command += &format!("{} < {}\n", project.run, input.display());
+    fs::write(dir.join("command.txt"), &command).map_err(io_err)?;
+    fs::write(dir.join("README.txt"), format!(
+        "problem: {}\ncase: {}\n{}\nmatches expected: {}\nsource: {}\n",
+        project.url, case, out.status, matched, src_name,
+    )).map_err(io_err)?;
+
+    let tarball = PathBuf::from("repro").join(format!("{name}.tar.gz"));
+    let status = Command::new("tar")
+        .arg("czf").arg(&tarball)
+        .arg("-C").arg("repro")
+        .arg(&name)
+        .status()
+        .map_err(io_err)?;
+    if !status.success() {
+        return Err(SubmitError::CommandExecuteFailed);
+    }
+    Ok(tarball)
+}
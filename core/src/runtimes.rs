@@ -0,0 +1,72 @@
+//! 最後に全部通ったときのケースごとの実行時間を覚えておき、遅くなったケースを知らせる
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+
+use crate::Project;
+
+/// これ以上遅くなったら知らせる (倍率と差の両方を超えたとき)
+const SLOWDOWN_RATIO: f64 = 1.5;
+const SLOWDOWN_SECS: f64 = 0.05;
+
+fn store_file() -> PathBuf {
+    PathBuf::from(".acsub").join("runtimes.json")
+}
+
+fn load() -> BTreeMap<String, BTreeMap<String, f64>> {
+    fs::read_to_string(store_file())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// oj t の出力からケース名 → 秒
+///
+/// ```text
+/// [*] sample-1
+/// [*] time: 0.003457 sec
+/// [+] AC
+/// ```
+pub fn parse(log: &str, test_dir: &Path) -> BTreeMap<String, f64> {
+    let cases: Vec<String> = fs::read_dir(test_dir)
+        .map(|d| d.flatten().filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            Some(name.strip_suffix(".in")?.to_string())
+        }).collect())
+        .unwrap_or_default();
+    let mut out = BTreeMap::new();
+    let mut current = None;
+    for line in log.lines() {
+        // 先頭の [*] / [INFO] などを落とす
+        let msg = line.trim().split_once(']').map_or(line, |(_, m)| m).trim();
+        if cases.iter().any(|c| c == msg) {
+            current = Some(msg.to_string());
+        } else if let Some(t) = msg.strip_prefix("time:")
+            && let Some(case) = &current
+            && let Some(secs) = t.split_whitespace().next().and_then(|s| s.parse().ok())
+        {
+            out.insert(case.clone(), secs);
+        }
+    }
+    out
+}
+
+/// 前回全部通ったときより大きく遅くなったケース (名前, 前回, 今回)
+pub fn regressions(project: &Project, now: &BTreeMap<String, f64>) -> Vec<(String, f64, f64)> {
+    let Some(prev) = load().remove(&project.source.to_string_lossy().into_owned()) else { return Vec::new() };
+    now.iter()
+        .filter_map(|(case, &t)| {
+            let &p = prev.get(case)?;
+            (t > p * SLOWDOWN_RATIO && t - p > SLOWDOWN_SECS).then(|| (case.clone(), p, t))
+        })
+        .collect()
+}
+
+/// 全部通った実行の時間を次の比較用に保存する
+pub fn store(project: &Project, now: BTreeMap<String, f64>) {
+    if now.is_empty() {
+        return;
+    }
+    let mut all = load();
+    all.insert(project.source.to_string_lossy().into_owned(), now);
+    let _ = fs::create_dir_all(".acsub");
+    let _ = fs::write(store_file(), serde_json::to_string_pretty(&all).unwrap_or_default());
+}
@@ -0,0 +1,157 @@
+//! サンプルのディレクトリ (oj 形式: name.in / name.out) の検査
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// name → (入力, 出力)
+type Cases = BTreeMap<String, (Option<Vec<u8>>, Option<Vec<u8>>)>;
+
+fn read_cases(dir: &Path) -> Cases {
+    let mut cases = Cases::new();
+    let Ok(entries) = fs::read_dir(dir) else { return cases };
+    for e in entries.flatten() {
+        let p = e.path();
+        let (Some(stem), Some(ext)) = (p.file_stem().and_then(|s| s.to_str()), p.extension()) else { continue };
+        let Ok(body) = fs::read(&p) else { continue };
+        let entry = cases.entry(stem.to_string()).or_default();
+        if ext == "in" {
+            entry.0 = Some(body);
+        } else if ext == "out" {
+            entry.1 = Some(body);
+        }
+    }
+    cases
+}
+
+/// ジャッジから取ってきたサンプル (oj の `sample-1`、cargo-compete の `sample1`) か。
+/// それ以外 (case import・gen-max・stress で足したもの) はサンプルを取り直しても残す
+fn is_sample(name: &str) -> bool {
+    name.starts_with("sample")
+}
+
+fn read_samples(dir: &Path) -> Cases {
+    let mut cases = read_cases(dir);
+    cases.retain(|name, _| is_sample(name));
+    cases
+}
+
+/// サンプルが 1 つでもあるか
+pub fn has_samples(dir: &Path) -> bool {
+    read_samples(dir).values().any(|(i, _)| i.is_some())
+}
+
+/// 空のファイルや対になっていない入出力を列挙する
+pub fn verify(dir: &Path) -> Vec<String> {
+    let cases = read_cases(dir);
+    if cases.is_empty() {
+        return vec![format!("no samples in {}", dir.display())];
+    }
+    let mut problems = Vec::new();
+    for (name, (input, output)) in &cases {
+        match (input, output) {
+            (None, _) => problems.push(format!("{name}: output without input")),
+            (_, None) => problems.push(format!("{name}: input without output")),
+            (Some(i), _) if i.iter().all(u8::is_ascii_whitespace) => problems.push(format!("{name}.in is empty")),
+            (_, Some(o)) if o.iter().all(u8::is_ascii_whitespace) => problems.push(format!("{name}.out is empty")),
+            _ => {}
+        }
+    }
+    problems
+}
+
+/// 途中で切れた取得の残りから、対になっていない・空のファイルを消す。残った (= 使える) ケースの数を返す
+pub fn prune_incomplete(dir: &Path) -> usize {
+    let mut kept = 0;
+    for (name, (input, output)) in read_cases(dir) {
+        let complete = [&input, &output].iter().all(|f| f.as_ref().is_some_and(|b| !b.iter().all(u8::is_ascii_whitespace)));
+        if complete {
+            kept += 1;
+        } else {
+            let _ = fs::remove_file(dir.join(format!("{name}.in")));
+            let _ = fs::remove_file(dir.join(format!("{name}.out")));
+        }
+    }
+    kept
+}
+
+/// src にあって dst に無いファイルを移す
+pub fn merge_missing(src: &Path, dst: &Path) -> std::io::Result<()> {
+    for e in fs::read_dir(src)?.flatten() {
+        let to = dst.join(e.file_name());
+        if !to.exists() {
+            fs::rename(e.path(), to)?;
+        }
+    }
+    Ok(())
+}
+
+/// 以前のサンプルと比べて増えた・変わった・消えたもの
+#[derive(Debug, Default)]
+pub struct SampleDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl SampleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+pub fn diff(old: &Path, new: &Path) -> SampleDiff {
+    let (old, new) = (read_samples(old), read_cases(new));
+    let mut d = SampleDiff::default();
+    for (name, case) in &new {
+        match old.get(name) {
+            None => d.added.push(name.clone()),
+            Some(o) if o != case => d.changed.push(name.clone()),
+            _ => {}
+        }
+    }
+    d.removed = old.keys().filter(|n| !new.contains_key(*n)).cloned().collect();
+    d
+}
+
+/// dir のサンプルを staging の中身で置き換える。サンプルでないケースには触らない
+pub fn replace(dir: &Path, staging: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for name in read_samples(dir).keys() {
+        let _ = fs::remove_file(dir.join(format!("{name}.in")));
+        let _ = fs::remove_file(dir.join(format!("{name}.out")));
+    }
+    for e in fs::read_dir(staging)?.flatten() {
+        fs::rename(e.path(), dir.join(e.file_name()))?;
+    }
+    fs::remove_dir_all(staging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// サンプルを取り直しても、取り込んだケースや stress の反例は残り、「消えた」ことにもならない
+    #[test]
+    fn refresh_keeps_user_cases() {
+        let base = std::env::temp_dir().join(format!("acsub-test-samples-{}", std::process::id()));
+        let (dir, staging) = (base.join("test"), base.join("test.new"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(&staging).unwrap();
+        for (d, name, body) in [(&dir, "sample-1", "1"), (&dir, "sample-2", "2"), (&dir, "stress-1", "3"), (&staging, "sample-1", "4")] {
+            fs::write(d.join(format!("{name}.in")), body).unwrap();
+            fs::write(d.join(format!("{name}.out")), body).unwrap();
+        }
+        assert!(has_samples(&dir));
+        let d = diff(&dir, &staging);
+        assert_eq!((d.changed, d.removed), (vec!["sample-1".to_string()], vec!["sample-2".to_string()]));
+        replace(&dir, &staging).unwrap();
+        let names: Vec<String> = read_cases(&dir).into_keys().collect();
+        assert_eq!(names, ["sample-1", "stress-1"]);
+        assert_eq!(fs::read_to_string(dir.join("sample-1.in")).unwrap(), "4");
+        assert!(!staging.exists());
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!has_samples(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("01.in"), "x").unwrap();
+        assert!(!has_samples(&dir));
+        let _ = fs::remove_dir_all(&base);
+    }
+}
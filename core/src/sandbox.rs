@@ -0,0 +1,120 @@
+//! 解答を rlimit 付きで走らせる (暴走してもマシンが固まったりディスクが埋まったりしないように)。
+//! 落としてきた checker / generator は isolated でさらに隔離する。
+//!
+//! rlimit は ac_config.toml で変えられる。0 なら制限しない。
+//!
+//! ```toml
+//! [limits]
+//! cpu_secs = 10
+//! memory_mb = 2048
+//! file_mb = 64
+//! ```
+use std::process::Command;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// CPU 時間 (秒)
+    pub cpu_secs: u64,
+    /// アドレス空間 (MB)
+    pub memory_mb: u64,
+    /// 書き込めるファイルの大きさ (MB)
+    pub file_mb: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { cpu_secs: 10, memory_mb: 2048, file_mb: 64 }
+    }
+}
+
+impl Limits {
+    /// cmd とその子孫に制限をかける (core dump も止める)
+    #[cfg(unix)]
+    pub fn apply(&self, cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let limits = *self;
+        // SAFETY: fork 後の子で setrlimit を呼ぶだけ (async-signal-safe)
+        unsafe {
+            cmd.pre_exec(move || {
+                let set = |res, v: u64| {
+                    let lim = libc::rlimit { rlim_cur: v as libc::rlim_t, rlim_max: v as libc::rlim_t };
+                    // 元の上限より大きくはできないが、それは気にしない
+                    libc::setrlimit(res, &lim);
+                };
+                set(libc::RLIMIT_CORE, 0);
+                if limits.cpu_secs > 0 {
+                    set(libc::RLIMIT_CPU, limits.cpu_secs);
+                }
+                if limits.memory_mb > 0 {
+                    set(libc::RLIMIT_AS, limits.memory_mb << 20);
+                }
+                if limits.file_mb > 0 {
+                    set(libc::RLIMIT_FSIZE, limits.file_mb << 20);
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn apply(&self, _cmd: &mut Command) {}
+
+    /// シェルのコマンドの頭に ulimit を付ける。oj t -c に渡せば oj や他のケースではなく、1 回ごとの解答だけに効く。
+    /// (oj は /bin/sh で走らせる。sh の -f は 512 バイト単位、-v は KB)
+    #[cfg(unix)]
+    pub fn wrap_shell(&self, command: &str) -> String {
+        let mut out = String::from("ulimit -c 0; ");
+        if self.cpu_secs > 0 {
+            out += &format!("ulimit -t {}; ", self.cpu_secs);
+        }
+        if self.memory_mb > 0 {
+            out += &format!("ulimit -v {}; ", self.memory_mb << 10);
+        }
+        if self.file_mb > 0 {
+            out += &format!("ulimit -f {}; ", self.file_mb << 11);
+        }
+        out + command
+    }
+
+    #[cfg(not(unix))]
+    pub fn wrap_shell(&self, command: &str) -> String {
+        command.to_string()
+    }
+}
+
+/// sh の 1 引数になるように ' で囲む
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn on_path(bin: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|p| std::env::split_paths(&p).any(|d| d.join(bin).is_file()))
+}
+
+/// 落としてきた checker / generator など信用できないものを走らせるシェルコマンドにする。
+/// 後ろに付けた引数はそのまま command に渡る。
+///
+/// bwrap があればネットワーク無し・カレントディレクトリ以外は読み取り専用 (ホームは見えない)、
+/// 無ければ unshare でネットワークだけ切る。どちらも無ければ走らせない。
+pub fn isolated(command: &str) -> Result<String, String> {
+    let inner = format!("sh -c {} sh", quote(&format!("{command} \"$@\"")));
+    if on_path("bwrap") {
+        let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+        let cwd = quote(&cwd.display().to_string());
+        let mut s = "bwrap --unshare-all --die-with-parent --new-session".to_string();
+        for dir in ["/usr", "/bin", "/lib", "/lib64", "/sbin", "/etc", "/tmp"] {
+            s += &format!(" --ro-bind-try {dir} {dir}");
+        }
+        s += &format!(" --proc /proc --dev /dev --bind {cwd} {cwd} --chdir {cwd} {inner}");
+        return Ok(s);
+    }
+    if on_path("unshare") {
+        eprintln!("bwrap not found: only the network is cut off (install bubblewrap to restrict the filesystem too)");
+        return Ok(format!("unshare --map-root-user --net {inner}"));
+    }
+    Err("neither bwrap nor unshare is available; refusing to run an untrusted program".to_string())
+}
@@ -0,0 +1,115 @@
+//! AHC の seed ごとの得点の記録と、前回との比較
+//!
+//! `.acsub/scores/<problem id>.jsonl` に 1 回の実行を 1 行で追記する。
+//! version は解答のソースのハッシュで、同じソースを何度走らせたかも分かる。
+use std::{collections::BTreeMap, fs::{self, OpenOptions}, io::Write, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ahc::{Objective, SeedResult};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    /// UNIX 秒
+    pub time: u64,
+    pub version: String,
+    /// seed → 得点 (失敗した seed は入れない)
+    pub scores: BTreeMap<u64, i64>,
+    /// 失敗した seed
+    #[serde(default)]
+    pub failed: Vec<u64>,
+}
+
+fn file(problem_id: &str) -> PathBuf {
+    PathBuf::from(".acsub").join("scores").join(format!("{problem_id}.jsonl"))
+}
+
+/// 古い順
+pub fn load(problem_id: &str) -> Vec<Run> {
+    fs::read_to_string(file(problem_id))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+pub fn record(problem_id: &str, version: &str, results: &[SeedResult]) -> Run {
+    let run = Run {
+        time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        version: version.to_string(),
+        scores: results.iter().filter_map(|r| Some((r.seed, r.score?))).collect(),
+        failed: results.iter().filter(|r| r.score.is_none()).map(|r| r.seed).collect(),
+    };
+    let path = file(problem_id);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(f, "{}", serde_json::to_string(&run).unwrap_or_default());
+    }
+    run
+}
+
+/// 2 回の実行で共通の seed を比べた結果
+#[derive(Debug, Default)]
+pub struct Delta {
+    pub improved: usize,
+    pub regressed: usize,
+    pub same: usize,
+    /// 共通の seed での合計の差 (今回 - 前回)
+    pub total: i64,
+}
+
+pub fn compare(prev: &Run, now: &Run, objective: Objective) -> Delta {
+    let mut d = Delta::default();
+    for (seed, &a) in &now.scores {
+        let Some(&b) = prev.scores.get(seed) else { continue };
+        d.total += a - b;
+        let better = match objective {
+            Objective::Max => a > b,
+            Objective::Min => a < b,
+        };
+        if a == b {
+            d.same += 1;
+        } else if better {
+            d.improved += 1;
+        } else {
+            d.regressed += 1;
+        }
+    }
+    d
+}
+
+/// これまでの全実行での seed ごとの最良の得点
+pub fn best(runs: &[Run], objective: Objective) -> BTreeMap<u64, i64> {
+    let mut best = BTreeMap::new();
+    for run in runs {
+        for (&seed, &s) in &run.scores {
+            best.entry(seed)
+                .and_modify(|b: &mut i64| {
+                    *b = match objective {
+                        Objective::Max => (*b).max(s),
+                        Objective::Min => (*b).min(s),
+                    }
+                })
+                .or_insert(s);
+        }
+    }
+    best
+}
+
+/// AHC の相対評価と同じく、seed ごとに 最大化なら 自分/最良、最小化なら 最良/自分 を足したもの。
+/// 失敗した seed は 0 点。(合計, seed 数)
+pub fn relative(run: &Run, best: &BTreeMap<u64, i64>, objective: Objective) -> (f64, usize) {
+    let mut sum = 0.0;
+    for (seed, &s) in &run.scores {
+        let Some(&b) = best.get(seed) else { continue };
+        sum += match objective {
+            Objective::Max if b > 0 => s as f64 / b as f64,
+            Objective::Min if s > 0 => b as f64 / s as f64,
+            // 最良も 0 点なら満点扱い
+            _ => 1.0,
+        };
+    }
+    (sum, run.scores.len() + run.failed.len())
+}
@@ -0,0 +1,108 @@
+//! ソースや repro を paste サービスに上げて URL を返す
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf}, process::{Command, Stdio}};
+
+use serde_json::{json, Value};
+
+pub enum Backend {
+    /// https://paste.rs (認証なし、1 ファイル)
+    PasteRs,
+    /// GitHub Gist (secret)。token は GITHUB_TOKEN か `gh auth token`
+    Gist { token: String },
+}
+
+impl Backend {
+    /// name (無ければ $ACSUB_SHARE、それも無ければ paste.rs) から選ぶ
+    pub fn from_name(name: Option<&str>) -> Result<Backend, String> {
+        let name = name.map(str::to_string).or_else(|| std::env::var("ACSUB_SHARE").ok());
+        match name.as_deref().unwrap_or("paste.rs") {
+            "paste.rs" => Ok(Backend::PasteRs),
+            "gist" => {
+                let token = std::env::var("GITHUB_TOKEN").ok().or_else(|| {
+                    let out = Command::new("gh").args(["auth", "token"]).output().ok()?;
+                    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+                });
+                token.map(|token| Backend::Gist { token })
+                    .ok_or_else(|| "gist needs GITHUB_TOKEN or `gh auth login`".to_string())
+            }
+            other => Err(format!("unknown share backend: {other} (paste.rs / gist)")),
+        }
+    }
+}
+
+/// dir 直下のテキストファイル (repro/<問題>-<ケース>/ など)
+pub fn files_in(dir: &Path) -> Result<Vec<(String, String)>, String> {
+    let mut files: Vec<(String, String)> = fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {e}", dir.display()))?
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .map(|e| (e.file_name().to_string_lossy().into_owned(), String::from_utf8_lossy(&fs::read(e.path()).unwrap_or_default()).into_owned()))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// body を stdin から curl に渡して応答を返す
+fn curl(args: &[&str], body: &str) -> Result<String, String> {
+    let mut child = Command::new("curl")
+        .args(["-sf"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run curl: {e}"))?;
+    child.stdin.take().unwrap().write_all(body.as_bytes()).map_err(|e| e.to_string())?;
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !out.status.success() {
+        return Err("upload failed".to_string());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// `header = "Authorization: Bearer …"` だけの curl の設定ファイル (0600) を作ってそのパスを返す
+fn auth_config(token: &str) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("acsub-share-{}.curlrc", std::process::id()));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&path).map_err(|e| format!("failed to create {}: {e}", path.display()))?;
+    let token = token.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(file, "header = \"Authorization: Bearer {token}\"").map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// files を上げて URL を返す
+pub fn upload(backend: &Backend, description: &str, files: &[(String, String)]) -> Result<String, String> {
+    match backend {
+        Backend::PasteRs => {
+            // 1 ファイルしか置けないので見出しを付けて繋げる
+            let body = match files {
+                [(_, content)] => content.clone(),
+                _ => files.iter().map(|(name, c)| format!("==> {name} <==\n{c}\n")).collect(),
+            };
+            Ok(curl(&["--data-binary", "@-", "https://paste.rs/"], &body)?.trim().to_string())
+        }
+        Backend::Gist { token } => {
+            let body = json!({
+                "description": description,
+                "public": false,
+                "files": files.iter()
+                    .filter(|(_, c)| !c.is_empty())
+                    .map(|(name, c)| (name.clone(), json!({ "content": c })))
+                    .collect::<serde_json::Map<_, _>>(),
+            });
+            // token をコマンドラインに載せると ps などから見えるので、自分だけが読める curl の設定ファイルで渡す
+            let config = auth_config(token)?;
+            let res = curl(
+                &["-K", &config.display().to_string(), "-X", "POST", "-H", "Accept: application/vnd.github+json", "--data-binary", "@-", "https://api.github.com/gists"],
+                &body.to_string(),
+            );
+            fs::remove_file(&config).ok();
+            let res = res?;
+            let v: Value = serde_json::from_str(&res).map_err(|e| e.to_string())?;
+            v["html_url"].as_str().map(str::to_string).ok_or_else(|| "unexpected response from GitHub".to_string())
+        }
+    }
+}
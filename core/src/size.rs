@@ -0,0 +1,34 @@
+//! コードゴルフ用の長さ
+use std::{fs, path::Path, thread, time::Duration};
+
+/// 束ねたソースの長さ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub bytes: usize,
+    /// UTF-8 の文字数
+    pub chars: usize,
+    /// 末尾の改行を数えないバイト数 (多くのゴルフ用ジャッジの数え方)
+    pub golf: usize,
+}
+
+pub fn measure(src: &str) -> Size {
+    Size {
+        bytes: src.len(),
+        chars: src.chars().count(),
+        golf: src.strip_suffix('\n').map_or(src, |s| s.strip_suffix('\r').unwrap_or(s)).len(),
+    }
+}
+
+/// path が書き換わるたびに changed を呼ぶ (戻らない)
+pub fn watch(path: &Path, mut changed: impl FnMut()) -> ! {
+    let mtime = || fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last = mtime();
+    loop {
+        thread::sleep(Duration::from_millis(500));
+        let now = mtime();
+        if now != last {
+            last = now;
+            changed();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! コンテスト中の各問題の状況 (サンプル・バンドル・提出・ジャッジ結果)
+//!
+//! ジャッジ結果は AtCoder Problems の API から取る。ユーザ名は環境変数 `ACSUB_ATCODER_USER` か
+//! `~/.config/cp-assists/config.toml` の `atcoder_user = "..."`。
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::read_to_string,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+use crate::{atcoder, build_all, bundle_source, history, library, project, test_cache, Project, TestResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Samples {
+    Passed,
+    Failed,
+    /// 最後に試した後でソースかサンプルが変わった
+    Stale,
+    Untested,
+}
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub problem_id: String,
+    pub samples: Samples,
+    /// submit.rs が今のソースを束ねたものか
+    pub bundled: bool,
+    /// 最後にコピーしたもの
+    pub submitted: Option<history::Entry>,
+    /// 最後にコピーしたものが今のソースと同じか
+    pub submitted_current: bool,
+    pub verdict: Option<String>,
+}
+
+/// bin の名前から問題を並べる (variant は除く)
+pub fn problems() -> Vec<String> {
+    build_all::bin_names()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|b| !b.contains("__"))
+        .collect()
+}
+
+pub fn of(project: &Project, problem_id: &str) -> Status {
+    let samples = match test_cache::last(project) {
+        Some((_, false)) => Samples::Stale,
+        Some((TestResult::Passed, true)) => Samples::Passed,
+        Some((TestResult::Failed, true)) => Samples::Failed,
+        None => Samples::Untested,
+    };
+    let current = bundle_source(project).ok().map(|s| history::short_hash(&s));
+    let bundled = current.is_some()
+        && read_to_string("submit.rs").ok().map(|s| history::short_hash(&s)) == current;
+    let submitted = history::load().into_iter().rev().find(|e| e.url == project.url);
+    let submitted_current = submitted.as_ref().is_some_and(|e| Some(&e.hash) == current.as_ref());
+    Status { problem_id: problem_id.to_string(), samples, bundled, submitted, submitted_current, verdict: None }
+}
+
+pub fn atcoder_user() -> Option<String> {
+    if let Ok(u) = env::var("ACSUB_ATCODER_USER") {
+        return Some(u);
+    }
+    let v: toml::Value = toml::from_str(&read_to_string(library::global_config()?).ok()?).ok()?;
+    Some(v.get("atcoder_user")?.as_str()?.to_string())
+}
+
+#[derive(Deserialize)]
+struct Submission {
+    epoch_second: u64,
+    problem_id: String,
+    contest_id: String,
+    result: String,
+}
+
+/// contest の問題ごとの最新の結果。since は UNIX 秒
+pub fn verdicts(user: &str, contest_id: &str, since: u64) -> Result<BTreeMap<String, String>, String> {
+    let url = format!("https://kenkoooo.com/atcoder/atcoder-api/v3/user/submissions?user={user}&from_second={since}");
+    let subs: Vec<Submission> = serde_json::from_str(&atcoder::fetch(&url)?).map_err(|e| e.to_string())?;
+    let mut latest: BTreeMap<String, (u64, String)> = BTreeMap::new();
+    for s in subs.into_iter().filter(|s| s.contest_id == contest_id) {
+        if latest.get(&s.problem_id).is_none_or(|(t, _)| *t <= s.epoch_second) {
+            latest.insert(s.problem_id, (s.epoch_second, s.result));
+        }
+    }
+    Ok(latest.into_iter().map(|(k, (_, r))| (k, r)).collect())
+}
+
+/// 全問題の状況。ジャッジ結果は取れたときだけ埋める
+pub fn all(judge: Option<&str>) -> Vec<Status> {
+    let projects: Vec<(String, Project)> = problems()
+        .into_iter()
+        .filter_map(|id| Some((id.clone(), project::try_detect_judge(&id, judge)?)))
+        .collect();
+    let mut statuses: Vec<Status> = projects.iter().map(|(id, p)| of(p, id)).collect();
+
+    let contest = projects.iter().find_map(|(_, p)| p.contest_id.clone());
+    if let (Some(user), Some(contest)) = (atcoder_user(), contest) {
+        // 最初のコピーから、無ければ 1 週間前から
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let since = statuses.iter().filter_map(|s| s.submitted.as_ref().map(|e| e.time)).min()
+            .map_or(now.saturating_sub(7 * 86400), |t| t.saturating_sub(3600));
+        match verdicts(&user, &contest, since) {
+            Ok(v) => {
+                for s in &mut statuses {
+                    s.verdict = v.get(&s.problem_id).cloned();
+                }
+            }
+            Err(e) => eprintln!("could not fetch verdicts: {e}"),
+        }
+    }
+    statuses
+}
@@ -0,0 +1,66 @@
+//! 小さいランダムケースで本体と brute を突き合わせる
+use std::{collections::BTreeMap, fs, io::{self, Write}, path::PathBuf, process::{Command, Stdio}};
+
+use crate::{genmax::{self, Rng}, Project, SubmitError};
+
+/// 食い違ったケース
+pub struct Mismatch {
+    pub input: String,
+    /// brute の出力
+    pub expected: String,
+    pub actual: String,
+}
+
+/// command に input を食わせた標準出力
+fn run_with(project: &Project, input: &str) -> Result<String, SubmitError> {
+    let mut cmd = Command::new("sh");
+    project.limits.apply(&mut cmd);
+    let mut child = cmd
+        .args(["-c", &project.run])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| SubmitError::CommandExecuteFailed)?;
+    // 読まずに終わる解答もあるので書き込みの失敗は気にしない
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    let out = child.wait_with_output().map_err(|_| SubmitError::CommandExecuteFailed)?;
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// spec (genmax の書式) で count 回ケースを作り、最初に食い違ったものを返す
+pub fn run(
+    main: &Project,
+    brute: &Project,
+    spec: &str,
+    vars: &BTreeMap<String, i64>,
+    count: usize,
+    rng: &mut Rng,
+) -> Result<Option<Mismatch>, SubmitError> {
+    main.build()?;
+    brute.build()?;
+    for i in 1..=count {
+        let input = genmax::generate(spec, vars, rng).map_err(|e| {
+            eprintln!("bad spec: {e}");
+            SubmitError::CommandExecuteFailed
+        })?;
+        let expected = run_with(brute, &input)?;
+        let actual = run_with(main, &input)?;
+        if expected.split_whitespace().ne(actual.split_whitespace()) {
+            return Ok(Some(Mismatch { input, expected, actual }));
+        }
+        if i % 100 == 0 {
+            eprintln!("{i} cases ok");
+        }
+    }
+    Ok(None)
+}
+
+/// 食い違ったケースを test_dir に stress-1.in / stress-1.out (空いている番号) として残し、拡張子を除いたパスを返す
+pub fn save(project: &Project, m: &Mismatch) -> io::Result<PathBuf> {
+    fs::create_dir_all(&project.test_dir)?;
+    let name = (1..).map(|i| format!("stress-{i}")).find(|n| !project.test_dir.join(format!("{n}.in")).exists()).unwrap();
+    fs::write(project.test_dir.join(format!("{name}.in")), &m.input)?;
+    fs::write(project.test_dir.join(format!("{name}.out")), &m.expected)?;
+    Ok(project.test_dir.join(name))
+}
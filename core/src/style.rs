@@ -0,0 +1,57 @@
+//! 端末向けの色付き出力と、パイプ・エディタ向けの素の出力の切り替え
+use std::{env, io::IsTerminal, sync::atomic::{AtomicBool, Ordering}};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// stdout が端末でない (パイプ・エディタのタスク) か NO_COLOR があれば素の出力にする
+pub fn detect() {
+    set_plain(!std::io::stdout().is_terminal() || env::var_os("NO_COLOR").is_some());
+}
+
+/// 素の出力では色・絵文字を出さず、子の cargo にも色を付けさせない
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+    if plain {
+        // SAFETY: 子プロセスを起動する前、スレッドを作る前の main から呼ぶ
+        unsafe { env::set_var("CARGO_TERM_COLOR", "never") };
+    }
+}
+
+pub fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+fn paint(code: &str, s: &str) -> String {
+    if plain() { s.to_string() } else { format!("\x1b[{code}m{s}\x1b[0m") }
+}
+
+pub fn green(s: &str) -> String {
+    paint("32", s)
+}
+
+pub fn red(s: &str) -> String {
+    paint("31", s)
+}
+
+pub fn yellow(s: &str) -> String {
+    paint("33", s)
+}
+
+/// 端末のときだけ付ける絵文字
+pub fn emoji(s: &str) -> &str {
+    if plain() { "" } else { s }
+}
+
+/// oj t の行に色を付ける (パイプで受けると oj 自身は色を付けないため)
+pub fn oj_line(line: &str) -> String {
+    let t = line.trim_start();
+    if t.starts_with("[+]") || t.starts_with("[SUCCESS]") {
+        green(line)
+    } else if t.starts_with("[-]") || t.starts_with("[x]") || t.starts_with("[FAILURE]") || t.starts_with("[ERROR]") {
+        red(line)
+    } else if t.starts_with("[!]") || t.starts_with("[WARNING]") {
+        yellow(line)
+    } else {
+        line.to_string()
+    }
+}
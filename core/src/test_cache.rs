@@ -0,0 +1,82 @@
+//! ソース・ライブラリ・サンプルが変わっていなければテスト結果を使い回す
+use std::{collections::{BTreeMap, BTreeSet}, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{bundle_options, bundler_config, hash, library_roots, Project, SubmitError, TestResult};
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    passed: bool,
+    log: String,
+}
+
+pub struct Hit {
+    pub result: TestResult,
+    pub log: String,
+}
+
+fn cache_file() -> PathBuf {
+    PathBuf::from(".acsub").join("test-cache.json")
+}
+
+fn load() -> BTreeMap<String, Entry> {
+    fs::read_to_string(cache_file())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// 解答と使うライブラリのファイル、checker、制限、実行コマンド、サンプルのハッシュ。
+/// テストは local feature を付けて走るので、束ねたもの (local の部分を落とす) ではなくファイルそのものを見る
+pub fn key(project: &Project) -> Result<String, SubmitError> {
+    let mut h = hash::Hasher::new();
+    h.write(&fs::read(&project.source).map_err(|_| SubmitError::CommandExecuteFailed)?);
+    let cfg = bundler_config();
+    let opts = bundler::Options { strip_features: BTreeSet::new(), ..bundle_options(project, &cfg) };
+    let lib_files = bundler::library_files(&library_roots(project, &cfg)?, &project.source, &opts)
+        .map_err(|e| SubmitError::BundleFailed(format!("{e:#}")))?;
+    for f in lib_files {
+        h.write(f.to_string_lossy().as_bytes());
+        h.write(&fs::read(&f).unwrap_or_default());
+    }
+    h.write(project.checker.as_deref().unwrap_or_default().as_bytes());
+    h.write(format!("{:?}", project.limits).as_bytes());
+    h.write(project.run.as_bytes());
+    let mut files: Vec<_> = fs::read_dir(&project.test_dir)
+        .map(|d| d.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.sort();
+    for f in files {
+        h.write(f.to_string_lossy().as_bytes());
+        h.write(&fs::read(&f).unwrap_or_default());
+    }
+    Ok(h.hex())
+}
+
+pub fn lookup(project: &Project, key: &str) -> Option<Hit> {
+    let e = load().remove(&project.source.to_string_lossy().into_owned())?;
+    let result = if e.passed { TestResult::Passed } else { TestResult::Failed };
+    (e.key == key).then_some(Hit { result, log: e.log })
+}
+
+/// 最後に記録した結果と、それが今のソース・サンプルのものか
+pub fn last(project: &Project) -> Option<(TestResult, bool)> {
+    let e = load().remove(&project.source.to_string_lossy().into_owned())?;
+    let result = if e.passed { TestResult::Passed } else { TestResult::Failed };
+    let fresh = key(project).is_ok_and(|k| k == e.key);
+    Some((result, fresh))
+}
+
+pub fn store(project: &Project, key: &str, result: TestResult, log: &str) {
+    let mut all = load();
+    all.insert(project.source.to_string_lossy().into_owned(), Entry {
+        key: key.to_string(),
+        passed: result == TestResult::Passed,
+        log: log.to_string(),
+    });
+    let path = cache_file();
+    let _ = fs::create_dir_all(path.parent().unwrap());
+    let _ = fs::write(path, serde_json::to_string_pretty(&all).unwrap());
+}
@@ -0,0 +1,178 @@
+//! AHC 解答のパラメータ調整 (グリッドかランダム探索)
+//!
+//! パラメータは環境変数 (`NAME=value cargo run ...`) か、コマンドの後ろの引数 (`--name value`) で渡す。
+//! 試した組と得点は `.acsub/tune/<problem id>.jsonl` に残す。
+use std::{fs::{self, OpenOptions}, io::Write, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ahc::{self, Objective},
+    genmax::Rng,
+    Project, SubmitError,
+};
+
+/// 1 つのパラメータの動かし方
+#[derive(Debug, Clone)]
+pub enum Space {
+    /// lo..hi (step があればグリッドの刻み)
+    Int { lo: i64, hi: i64, step: Option<i64> },
+    Float { lo: f64, hi: f64, step: Option<f64> },
+    /// a,b,c
+    Choice(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub space: Space,
+}
+
+/// `T0=1.0..100.0` / `N=10..50:10` / `MODE=a,b,c`
+pub fn parse_param(s: &str) -> Option<Param> {
+    let (name, spec) = s.split_once('=')?;
+    let (range, step) = match spec.split_once(':') {
+        Some((r, st)) => (r, Some(st)),
+        None => (spec, None),
+    };
+    let space = match range.split_once("..") {
+        Some((lo, hi)) if [lo, hi].iter().any(|x| x.contains('.')) || step.is_some_and(|x| x.contains('.')) => Space::Float {
+            lo: lo.parse().ok()?,
+            hi: hi.parse().ok()?,
+            step: step.map(str::parse).transpose().ok()?,
+        },
+        Some((lo, hi)) => Space::Int {
+            lo: lo.parse().ok()?,
+            hi: hi.parse().ok()?,
+            step: step.map(str::parse).transpose().ok()?,
+        },
+        None => Space::Choice(range.split(',').map(str::to_string).collect()),
+    };
+    Some(Param { name: name.to_string(), space })
+}
+
+impl Space {
+    /// グリッドの値。刻みが無い範囲なら None
+    fn grid(&self) -> Option<Vec<String>> {
+        match self {
+            Space::Int { lo, hi, step: Some(st) } if *st > 0 => {
+                Some((0..).map(|i| lo + i * st).take_while(|v| v <= hi).map(|v| v.to_string()).collect())
+            }
+            Space::Float { lo, hi, step: Some(st) } if *st > 0.0 => {
+                Some((0..).map(|i| lo + i as f64 * st).take_while(|v| *v <= hi + 1e-9).map(|v| v.to_string()).collect())
+            }
+            Space::Choice(c) => Some(c.clone()),
+            _ => None,
+        }
+    }
+
+    fn sample(&self, rng: &mut Rng) -> String {
+        match self {
+            Space::Int { lo, hi, .. } => rng.range(*lo, *hi).to_string(),
+            Space::Float { lo, hi, .. } => {
+                let t = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                (lo + (hi - lo) * t).to_string()
+            }
+            Space::Choice(c) => c[rng.next_u64() as usize % c.len()].clone(),
+        }
+    }
+}
+
+/// 試す組 (名前と値)。全部グリッドにできればその直積、そうでなければ trials 個のランダム
+pub fn candidates(params: &[Param], trials: usize, rng: &mut Rng) -> Vec<Vec<(String, String)>> {
+    let grids: Option<Vec<Vec<String>>> = params.iter().map(|p| p.space.grid()).collect();
+    if let Some(grids) = grids {
+        let mut out: Vec<Vec<(String, String)>> = vec![Vec::new()];
+        for (p, values) in params.iter().zip(grids) {
+            out = out.into_iter()
+                .flat_map(|c| values.iter().map(move |v| {
+                    let mut c = c.clone();
+                    c.push((p.name.clone(), v.clone()));
+                    c
+                }))
+                .collect();
+        }
+        return out;
+    }
+    (0..trials)
+        .map(|_| params.iter().map(|p| (p.name.clone(), p.space.sample(rng))).collect())
+        .collect()
+}
+
+/// パラメータの渡し方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassAs {
+    Env,
+    Args,
+}
+
+/// パラメータ付きで走らせる Project
+fn with_params(project: &Project, values: &[(String, String)], pass: PassAs) -> Project {
+    let mut p = project.clone();
+    p.run = match pass {
+        PassAs::Env => {
+            let env: Vec<String> = values.iter().map(|(k, v)| format!("{k}={}", crate::sandbox::quote(v))).collect();
+            format!("{} {}", env.join(" "), project.run)
+        }
+        PassAs::Args => {
+            let args: Vec<String> = values.iter().map(|(k, v)| format!("--{} {}", k.to_lowercase(), crate::sandbox::quote(v))).collect();
+            format!("{} {}", project.run, args.join(" "))
+        }
+    };
+    p
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trial {
+    pub params: Vec<(String, String)>,
+    /// 失敗した seed を 0 点とした合計
+    pub total: i64,
+    pub failed: usize,
+}
+
+fn file(problem_id: &str) -> PathBuf {
+    PathBuf::from(".acsub").join("tune").join(format!("{problem_id}.jsonl"))
+}
+
+fn record(problem_id: &str, t: &Trial) {
+    let path = file(problem_id);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(f, "{}", serde_json::to_string(t).unwrap_or_default());
+    }
+}
+
+/// objective でよい方が前
+pub fn sort(trials: &mut [Trial], objective: Objective) {
+    // 失敗の少ない方を優先し、同じなら得点
+    trials.sort_by_key(|t| (t.failed, if objective == Objective::Max { -t.total } else { t.total }));
+}
+
+/// 候補を順に seeds で走らせる。on_trial で 1 組ごとに結果を受け取る
+pub fn run(
+    project: &Project,
+    problem_id: &str,
+    candidates: &[Vec<(String, String)>],
+    pass: PassAs,
+    seeds: &[u64],
+    jobs: usize,
+    mut on_trial: impl FnMut(&Trial),
+) -> Result<Vec<Trial>, SubmitError> {
+    let mut trials = Vec::new();
+    for values in candidates {
+        let p = with_params(project, values, pass);
+        let results = ahc::run_seeds(&p, &project.ahc, seeds, jobs)?;
+        let t = Trial {
+            params: values.clone(),
+            total: results.iter().filter_map(|r| r.score).sum(),
+            failed: results.iter().filter(|r| r.score.is_none()).count(),
+        };
+        record(problem_id, &t);
+        on_trial(&t);
+        trials.push(t);
+    }
+    sort(&mut trials, project.ahc.objective);
+    Ok(trials)
+}
@@ -0,0 +1,91 @@
+//! 1 問に複数の解答を置く (abc350_d.rs, abc350_d__brute.rs, abc350_d__v2.rs)
+//!
+//! 提出・テストに使う "active" な variant は `.acsub/active-variants.json` に覚えておく。
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+
+use crate::Project;
+
+pub const SEP: &str = "__";
+/// stress で答え合わせに使う variant
+pub const BRUTE: &str = "brute";
+
+fn state_file() -> PathBuf {
+    PathBuf::from(".acsub").join("active-variants.json")
+}
+
+fn load() -> BTreeMap<String, String> {
+    fs::read_to_string(state_file())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// problem_id で選ばれている variant (無ければ本体)
+pub fn active(problem_id: &str) -> Option<String> {
+    load().remove(problem_id)
+}
+
+/// None で本体に戻す
+pub fn set_active(problem_id: &str, variant: Option<&str>) -> io::Result<()> {
+    let mut all = load();
+    match variant {
+        Some(v) => all.insert(problem_id.to_string(), v.to_string()),
+        None => all.remove(problem_id),
+    };
+    fs::create_dir_all(".acsub")?;
+    fs::write(state_file(), serde_json::to_string_pretty(&all).unwrap_or_default())
+}
+
+/// 本体のファイル名 (拡張子なし)
+fn stem(project: &Project) -> Option<String> {
+    Some(project.source.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// source の横にある variant の名前
+pub fn list(project: &Project) -> Vec<String> {
+    let (Some(stem), Some(dir)) = (stem(project), project.source.parent()) else { return Vec::new() };
+    let prefix = format!("{stem}{SEP}");
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|d| d.flatten().filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            Some(name.strip_suffix(".rs")?.strip_prefix(&prefix)?.to_string())
+        }).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+impl Project {
+    /// variant のソースで実行・提出する Project。ファイルが無いか、
+    /// 実行コマンドを書き換えられない構成 (acc の cargo プロジェクトなど) なら None
+    pub fn variant(&self, name: &str) -> Option<Project> {
+        let stem = stem(self)?;
+        let new_stem = format!("{stem}{SEP}{name}");
+        let source = self.source.with_file_name(format!("{new_stem}.rs"));
+        if !source.is_file() {
+            return None;
+        }
+        let (old_src, new_src) = (self.source.display().to_string(), source.display().to_string());
+        // cargo は src/bin/<file>.rs をファイル名の bin として拾う
+        let rewrite = |cmd: &str| -> String {
+            let mut words: Vec<String> = cmd.split(' ').map(str::to_string).collect();
+            for i in 1..words.len() {
+                if words[i - 1] == "--bin" {
+                    words[i] = new_stem.clone();
+                }
+            }
+            words.join(" ").replace(&old_src, &new_src)
+        };
+        let run = rewrite(&self.run);
+        if run == self.run {
+            return None;
+        }
+        Some(Project {
+            source,
+            run,
+            build: self.build.as_deref().map(rewrite),
+            ..self.clone()
+        })
+    }
+}
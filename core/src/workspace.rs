@@ -0,0 +1,140 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::{constraints, judge::{ATCODER_CRATES, ATCODER_RUST_LANGUAGE_ID, ATCODER_RUST_TOOLCHAIN}, library};
+
+const MAIN_TEMPLATE: &str = "use proconio::input;
+
+fn main() {
+    input! {
+    }
+}
+";
+
+/// 時間いっぱい改善を続けるヒューリスティック向け。{TL} は秒
+const HEURISTIC_TEMPLATE: &str = "use std::time::Instant;
+
+use proconio::input;
+
+const TIME_LIMIT: f64 = {TL};
+
+fn main() {
+    let start = Instant::now();
+    input! {
+    }
+    while start.elapsed().as_secs_f64() < TIME_LIMIT {
+    }
+}
+";
+
+/// コンテストの種類ごとの雛形の作り方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Abc,
+    Arc,
+    Agc,
+    Ahc,
+}
+
+impl Preset {
+    pub fn parse(s: &str) -> Option<Preset> {
+        match s.to_ascii_lowercase().as_str() {
+            "abc" => Some(Preset::Abc),
+            "arc" => Some(Preset::Arc),
+            "agc" => Some(Preset::Agc),
+            "ahc" => Some(Preset::Ahc),
+            _ => None,
+        }
+    }
+
+    /// contest ID の頭 (abc350 → Abc)。分からなければ ABC と同じ
+    pub fn detect(contest_id: &str) -> Preset {
+        contest_id.get(..3).and_then(Preset::parse).unwrap_or(Preset::Abc)
+    }
+
+    /// 問題の記号
+    pub fn letters(self) -> Vec<char> {
+        match self {
+            Preset::Abc => "abcdefg".chars().collect(),
+            Preset::Arc | Preset::Agc => "abcdef".chars().collect(),
+            Preset::Ahc => vec!['a'],
+        }
+    }
+
+    /// 既定の実行時間制限 (ac_config.toml の time_limit_ms に書く)。
+    /// 今のところどれも 2 秒で、違う問題は ac_config.toml を直す
+    pub fn time_limit_ms(self) -> u64 {
+        2000
+    }
+
+    /// 得点を競うコンテストか (ac_config.toml の heuristic)
+    pub fn heuristic(self) -> bool {
+        self == Preset::Ahc
+    }
+
+    fn template(self) -> String {
+        if self.heuristic() {
+            // 出力の時間を残して制限の 0.1 秒前まで
+            let tl = self.time_limit_ms() as f64 / 1000.0 - 0.1;
+            HEURISTIC_TEMPLATE.replace("{TL}", &format!("{tl:.1}"))
+        } else {
+            MAIN_TEMPLATE.to_string()
+        }
+    }
+}
+
+fn cargo_toml(contest_id: &str, problems: &[String]) -> String {
+    let mut s = format!(
+        "[package]\nname = \"{contest_id}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[features]\nlocal = []\n\n[dependencies]\n"
+    );
+    for (name, version) in ATCODER_CRATES {
+        match *name {
+            "proconio" => s += &format!("{name} = {{ version = \"={version}\", features = [\"derive\"] }}\n"),
+            _ => s += &format!("{name} = \"={version}\"\n"),
+        }
+    }
+    // ライブラリが設定されていれば path 依存で入れておく (提出時は bundler が束ねる)
+    let lib = library::locate(None).ok().flatten().map(|src| library::crate_root(&src));
+    if let Some(lib) = lib.filter(|l| l.join("Cargo.toml").is_file()) {
+        s += &format!("library = {{ path = \"{}\" }}\n", lib.display());
+    }
+    for p in problems {
+        s += &format!("\n[[bin]]\nname = \"{p}\"\npath = \"src/bin/{p}.rs\"\n");
+    }
+    s
+}
+
+/// `<dir>/<contest_id>` に AtCoder と同じ依存・ツールチェインのプロジェクトを作る。
+/// letters が空なら preset の問題数にする
+pub fn generate(dir: &Path, contest_id: &str, preset: Preset, letters: &[char]) -> io::Result<PathBuf> {
+    let root = dir.join(contest_id);
+    if root.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", root.display())));
+    }
+    let letters = if letters.is_empty() { preset.letters() } else { letters.to_vec() };
+    let problems: Vec<String> = letters.iter().map(|c| format!("{contest_id}_{c}")).collect();
+
+    fs::create_dir_all(root.join("src").join("bin"))?;
+    fs::write(root.join("Cargo.toml"), cargo_toml(contest_id, &problems))?;
+    fs::write(
+        root.join("rust-toolchain.toml"),
+        format!("[toolchain]\nchannel = \"{ATCODER_RUST_TOOLCHAIN}\"\n"),
+    )?;
+    fs::write(
+        root.join("ac_config.toml"),
+        format!(
+            "judge = \"atcoder\"\ntime_limit_ms = {}\nheuristic = {}\n\n[judges.atcoder]\nurl = \"https://atcoder.jp/contests/{contest_id}/tasks/{{problem_id}}\"\nlanguages = {{ rs = \"{ATCODER_RUST_LANGUAGE_ID}\" }}\n",
+            preset.time_limit_ms(),
+            preset.heuristic(),
+        ),
+    )?;
+    fs::write(root.join(".gitignore"), "target/\ntest/\nout/\nsubmit.rs\n.acsub/\n")?;
+    let template = preset.template();
+    for p in &problems {
+        // 問題文が取れれば制約を雛形の先頭に書いておく
+        let url = format!("https://atcoder.jp/contests/{contest_id}/tasks/{p}");
+        let cs = constraints::load_or_fetch(&root, p, &url);
+        let head = if cs.is_empty() { String::new() } else { constraints::comment_block(&cs) + "\n" };
+        fs::write(root.join("src").join("bin").join(format!("{p}.rs")), head + &template)?;
+    }
+    Ok(root)
+}